@@ -0,0 +1,93 @@
+pub mod chess960 {
+    use crate::chess::color::color::Color;
+    use crate::chess::game::game::Game;
+    use crate::chess::piece::piece::Piece;
+    use crate::chess::piece_definition::piece_definition;
+
+    pub const POSITIONS: usize = 960;
+
+    // The standard start position in the Scharnagl numbering.
+    pub const STANDARD: usize = 518;
+
+    // Squares of the two knights among the five squares left after the bishops and the queen.
+    const KNIGHTS: [(usize, usize); 10] = [(0, 1), (0, 2), (0, 3), (0, 4), (1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4)];
+
+    //
+    // The back rank of start position index in the Scharnagl numbering, from the a file on.
+    // The index picks the light and dark squared bishop, the queen and the knights in turn,
+    // the rooks and the king take the last three squares with the king in the middle.
+    pub fn back_rank(index: usize) -> [Piece; 8] {
+        let mut rank: [Option<Piece>; 8] = [None; 8];
+        let mut n = index % POSITIONS;
+
+        rank[n % 4 * 2 + 1] = Some(Piece::Bishop);
+        n /= 4;
+        rank[n % 4 * 2] = Some(Piece::Bishop);
+        n /= 4;
+
+        let empty = |rank: &[Option<Piece>; 8]| -> Vec<usize> { (0..8).filter(|column| rank[*column].is_none()).collect() };
+
+        let queen = empty(&rank)[n % 6];
+        rank[queen] = Some(Piece::Queen);
+        n /= 6;
+
+        let (first, second) = KNIGHTS[n];
+        let free = empty(&rank);
+        rank[free[first]] = Some(Piece::Knight);
+        rank[free[second]] = Some(Piece::Knight);
+
+        let free = empty(&rank);
+        rank[free[0]] = Some(Piece::Rook);
+        rank[free[1]] = Some(Piece::King);
+        rank[free[2]] = Some(Piece::Rook);
+
+        return rank.map(|piece| piece.unwrap());
+    }
+
+    // A new game from start position index, both rooks keep their castling rights.
+    pub fn start_position(index: usize) -> Game {
+        let rank = back_rank(index);
+        let letters: String = rank.iter().map(|piece| piece_definition::to_letter(piece, &Color::Black)).collect();
+
+        let placement = format!("{}/pppppppp/8/8/8/8/PPPPPPPP/{}", letters, letters.to_uppercase());
+        let mut game = Game::create_board_from_string(&placement, 0);
+
+        let rooks: Vec<usize> = (0..8).filter(|column| rank[*column] == Piece::Rook).collect();
+        game.castling = [Some(rooks[1]), Some(rooks[0]), Some(rooks[1]), Some(rooks[0])];
+        return game;
+    }
+
+    //
+    // Problems with the numbering, every back rank has to be different and legal: bishops on
+    // squares of both colours and the king between the rooks. The standard position has to be
+    // number 518.
+    pub fn check_positions() -> Vec<String> {
+        let mut problems = vec![];
+        let mut seen = std::collections::HashSet::new();
+
+        for index in 0..POSITIONS {
+            let rank = back_rank(index);
+            let columns = |wanted: Piece| -> Vec<usize> { (0..8).filter(|column| rank[*column] == wanted).collect() };
+
+            let bishops = columns(Piece::Bishop);
+            let rooks = columns(Piece::Rook);
+            let king = columns(Piece::King);
+            if bishops.len() != 2 || bishops[0] % 2 == bishops[1] % 2 {
+                problems.push(format!("{}: bishops on {:?}", index, bishops));
+            }
+            if rooks.len() != 2 || king.len() != 1 || king[0] < rooks[0] || king[0] > rooks[1] {
+                problems.push(format!("{}: king on {:?}, rooks on {:?}", index, king, rooks));
+            }
+            if !seen.insert(rank.map(|piece| piece.to_u8())) {
+                problems.push(format!("{}: repeats an earlier position", index));
+            }
+        }
+
+        let standard = [Piece::Rook, Piece::Knight, Piece::Bishop, Piece::Queen, Piece::King, Piece::Bishop, Piece::Knight, Piece::Rook];
+        if back_rank(STANDARD).map(|piece| piece.to_u8()) != standard.map(|piece| piece.to_u8()) {
+            problems.push(format!("{}: is not the standard position", STANDARD));
+        }
+
+        return problems;
+    }
+}
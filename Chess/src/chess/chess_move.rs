@@ -2,7 +2,9 @@ pub mod chess_move {
     use std::fmt;
     use std::fmt::write;
     use crate::chess::color::color::Color;
+    use crate::chess::game::game::Game;
     use crate::chess::piece::piece::Piece;
+    use crate::chess::piece_definition::piece_definition;
     use crate::chess::position::position::Position;
 
     #[derive(Clone)]
@@ -12,7 +14,18 @@ pub mod chess_move {
         pub piece: Piece,
         pub color: Color,
         pub piece_at_position: Option<Piece>,
-        pub move_type: MoveType
+        pub move_type: MoveType,
+        // Pieces a capture blew up besides the captured one, with their squares. Only filled
+        // in for moves in the log of a variant where captures explode.
+        pub exploded: Vec<(Position, Piece, Color)>
+    }
+
+    // Moves are the same when they go between the same squares the same way, so a move from
+    // the log still matches the legal move it was made from once its explosions are filled in.
+    impl PartialEq for ChessMove {
+        fn eq(&self, other: &ChessMove) -> bool {
+            return self.from == other.from && self.to == other.to && self.move_type == other.move_type;
+        }
     }
 
     //
@@ -20,13 +33,15 @@ pub mod chess_move {
     // EnPassant(take position): using ein peasant, containing the position to remove the pawn.
     // Castle(rook from, rook to): holding the data of the rook movement in castling only to should be necessarry tho.
     // Promote: Holding data about the promotion
+    // Drop(piece): a piece from the pocket put on an empty square, from and to are that square.
 
-    #[derive(Debug, Clone)]
+    #[derive(Debug, Clone, PartialEq)]
     pub enum MoveType {
         Move,
         EnPassant(Position),
         Castle(Position, Position),
-        Promote(Piece)
+        Promote(Piece),
+        Drop(Piece)
     }
 
     impl ChessMove {
@@ -40,8 +55,139 @@ pub mod chess_move {
         ) -> ChessMove
         {
             return ChessMove {
-                from, to, piece, color, piece_at_position, move_type
+                from, to, piece, color, piece_at_position, move_type, exploded: vec![]
+            }
+        }
+
+        pub fn is_capture(&self) -> bool {
+            return match self.move_type {
+                MoveType::EnPassant(_) => true,
+                _ => self.piece_at_position.is_some()
+            }
+        }
+
+        pub fn is_drop(&self) -> bool {
+            return match self.move_type {
+                MoveType::Drop(_) => true,
+                _ => false
+            }
+        }
+
+        //
+        // Long algebraic notation as used by the protocol, like e2e4 or e7e8q. Castling is
+        // written as the king moving to its square when it moves at least two squares towards
+        // a rook further out, like e1g1, and as the king taking its own rook otherwise, like in
+        // Chess960. Drops are written like P@e4. ranks is the height of the board, for the square names.
+        pub fn to_uci(&self, ranks: usize) -> String {
+            match self.move_type {
+                MoveType::Castle(rook_from, _) if !Self::is_standard_castling(&self.from, &self.to, &rook_from) =>
+                    return self.to_uci_chess960(ranks),
+                MoveType::Drop(piece) => return format!("{}@{}", Self::piece_letter(&piece), self.to.to_algebraic(ranks)),
+                _ => ()
+            }
+
+            let mut s = format!("{}{}", self.from.to_algebraic(ranks), self.to.to_algebraic(ranks));
+
+            match self.move_type {
+                MoveType::Promote(piece) => s.push(piece_definition::to_letter(&piece, &Color::Black)),
+                _ => ()
+            }
+
+            return s;
+        }
+
+        fn is_standard_castling(king_from: &Position, king_to: &Position, rook_from: &Position) -> bool {
+            if rook_from.column > king_from.column {
+                return king_to.column >= king_from.column + 2 && rook_from.column > king_to.column;
             }
+            return king_to.column + 2 <= king_from.column && rook_from.column < king_to.column;
+        }
+
+        // Like to_uci, but castling is always written as the king taking its own rook.
+        pub fn to_uci_chess960(&self, ranks: usize) -> String {
+            return match self.move_type {
+                MoveType::Castle(rook_from, _) => format!("{}{}", self.from.to_algebraic(ranks), rook_from.to_algebraic(ranks)),
+                _ => self.to_uci(ranks)
+            }
+        }
+
+        // Standard algebraic notation like Nbd7, exd5 or e8=Q+, game is the position before the move.
+        pub fn to_san(&self, game: &Game) -> String {
+            let ranks = game.board.num_rows();
+            let mut san = String::new();
+
+            match self.move_type {
+                MoveType::Castle(rook_from, _) => if rook_from.column > self.from.column {
+                    san.push_str("O-O")
+                } else {
+                    san.push_str("O-O-O")
+                },
+                MoveType::Drop(piece) => {
+                    san.push(Self::piece_letter(&piece));
+                    san.push('@');
+                    san.push_str(&self.to.to_algebraic(ranks));
+                },
+                _ => {
+                    if self.piece == Piece::Pawn {
+                        if self.is_capture() {
+                            san.push_str(&self.from.to_algebraic(ranks)[..1]);
+                        }
+                    } else {
+                        san.push(Self::piece_letter(&self.piece));
+
+                        // Name the file, the rank or both when another piece of the same kind can go there too.
+                        let others: Vec<ChessMove> = game.get_all_legal_moves().into_iter()
+                            .filter(|other| other.piece == self.piece && other.to == self.to && other.from != self.from && !other.is_drop())
+                            .collect();
+
+                        if !others.is_empty() {
+                            let from = self.from.to_algebraic(ranks);
+                            if !others.iter().any(|other| other.from.column == self.from.column) {
+                                san.push_str(&from[..1]);
+                            } else if !others.iter().any(|other| other.from.row == self.from.row) {
+                                san.push_str(&from[1..]);
+                            } else {
+                                san.push_str(&from);
+                            }
+                        }
+                    }
+
+                    if self.is_capture() {
+                        san.push('x');
+                    }
+
+                    san.push_str(&self.to.to_algebraic(ranks));
+
+                    match self.move_type {
+                        MoveType::Promote(piece) => {
+                            san.push('=');
+                            san.push(Self::piece_letter(&piece));
+                        },
+                        _ => ()
+                    }
+                }
+            }
+
+            let mut new_game = game.clone();
+            match new_game.move_piece(&game.get_turn(), self) {
+                Ok(mut after_move) => {
+                    after_move.turn += 1;
+                    if after_move.is_check(&after_move.get_turn()) {
+                        if after_move.get_all_legal_moves().is_empty() {
+                            san.push('#');
+                        } else {
+                            san.push('+');
+                        }
+                    }
+                },
+                Err(_) => ()
+            }
+
+            return san;
+        }
+
+        fn piece_letter(piece: &Piece) -> char {
+            return piece_definition::definition(piece).letter;
         }
     }
 
@@ -55,7 +201,8 @@ pub mod chess_move {
                 MoveType::Promote(piece) => {
                     let x = format!("Promote ({}): ", piece);
                     s.push_str(x.as_str())
-                }
+                },
+                MoveType::Drop(_) => s.push_str("Drop: ")
             }
 
             let x = format!("{} of color {} from {} => {}", self.piece, self.color, self.from, self.to);
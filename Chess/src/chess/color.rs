@@ -2,12 +2,12 @@ pub mod color {
     use std::{fmt, ops};
     use crate::chess::piece::piece::Piece;
 
-    const FOURTH_BIT_BITS_MASK: u8 = 0b0000_1000;
+    const FIFTH_BIT_MASK: u8 = 0b0001_0000;
 
     #[derive(Debug, Clone)]
     pub enum Color {
         White = 0,
-        Black = 8
+        Black = 16
     }
 
     impl Color {
@@ -15,7 +15,7 @@ pub mod color {
         pub fn from_u8(value: u8) -> Color {
             match value {
                 0 => Color::White,
-                8 => Color::Black,
+                16 => Color::Black,
                 _ => panic!("Unknown value: {}", value),
             }
         }
@@ -23,7 +23,7 @@ pub mod color {
         pub fn to_u8(value: &Color) -> u8 {
             return match value {
                 Color::White => 0_u8,
-                Color::Black => 8_u8,
+                Color::Black => 16_u8,
             }
         }
 
@@ -35,7 +35,7 @@ pub mod color {
         }
 
         pub fn get_piece_color(input: u8) -> Color {
-            if input & FOURTH_BIT_BITS_MASK == 0 {
+            if input & FIFTH_BIT_MASK == 0 {
                 return Color::White
             }
             return Color::Black
@@ -1,5 +1,8 @@
 pub mod game {
     use std::fmt::Display;
+    use std::iter::Peekable;
+    use std::str::Chars;
+    use std::sync::Arc;
     use array2d::Array2D;
     use crate::chess::chess_error::chess_error::ChessError;
     use crate::chess::chess_move::chess_move::{ChessMove, MoveType};
@@ -7,101 +10,264 @@ pub mod game {
     use crate::chess::color::color::Color;
     use crate::chess::log::log::Log;
     use crate::chess::piece::piece::Piece;
+    use crate::chess::piece_definition::piece_definition;
+    use crate::chess::player_view::player_view::PlayerView;
     use crate::chess::position::position::Position;
+    use crate::chess::variant::variant::{self, Outcome, Variant};
 
     #[derive(Clone)]
     pub struct Game {
         pub board: Array2D<u8>,
         pub turn: u32,
         pub log: Log,
+        // Column of the rook each castling right belongs to, as white king side, white queen side,
+        // black king side and black queen side. In Chess960 the rooks can start on any column.
+        pub castling: [Option<usize>; 4],
+        pub variant: Arc<dyn Variant>,
+        // Checks white and black have given, for variants that count them.
+        pub checks_given: [u32; 2],
+        // Pieces in hand of white and black for variants with drops, by Game::pocket_index.
+        pub pockets: [[u32; 6]; 2],
+        // Squares of promoted pieces, they go into a pocket as pawns when taken.
+        pub promoted: Vec<Position>,
     }
 
+    // Boards from Gardner's 5x5 minichess up to 12x12.
+    pub const MIN_BOARD_SIZE: usize = 5;
+    pub const MAX_BOARD_SIZE: usize = 12;
+
+    // Pawns only have their double step on boards with at least this many ranks.
+    const DOUBLE_STEP_MIN_RANKS: usize = 8;
+
     impl Game {
         pub fn new() -> Game {
+            return Game::with_size(8, 8);
+        }
+
+        // An empty board of rows by columns squares.
+        pub fn with_size(rows: usize, columns: usize) -> Game {
             return Game {
-                board: Array2D::filled_with(0, 8, 8),
+                board: Array2D::filled_with(0, rows, columns),
                 turn: 0,
-                log: Log::new()
+                log: Log::new(),
+                castling: [None; 4],
+                variant: variant::standard(),
+                checks_given: [0; 2],
+                pockets: [[0; 6]; 2],
+                promoted: vec![]
             }
         }
 
         pub fn default() -> Game {
-            return Game::create_board_from_string("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR", 0);
+            return Game::create_board_from_string(variant::STANDARD_PLACEMENT, 0);
+        }
+
+        // The start position of a variant.
+        pub fn with_variant(variant: Arc<dyn Variant>) -> Game {
+            let mut game = Game::create_board_from_string(variant.start_placement(), 0);
+            if !variant.has_castling() {
+                game.castling = [None; 4];
+            }
+            game.variant = variant;
+            return game;
         }
 
         pub fn create_board_from_string(positions: &str, turn: u32) -> Game {
-            let mut game = Game::new();
+            // Pieces in hand follow the placement in brackets.
+            let (positions, pockets) = match positions.split_once('[') {
+                Some((placement, pockets)) => (placement, pockets.trim_end_matches(']')),
+                None => (positions, "")
+            };
+
+            let (rows, columns) = Self::placement_size(positions);
+            let mut game = Game::with_size(rows, columns);
             game.turn = turn;
+            game.set_pockets_from_fen(pockets);
+
             let mut x = 0; // horizontal <---->
             let mut y = 0; // up and down
-            for c in positions.chars() {
+            let mut chars = positions.chars().peekable();
+            while let Some(c) = chars.next() {
                 match c {
+                    // The piece before was promoted.
+                    '~' if x > 0 => game.promoted.push(Position::new(x - 1, y)),
                     '/' => {
                         y += 1;
                         x = 0
                     },
-                    '1' => x += 1,
-                    '2' => x += 2,
-                    '3' => x += 3,
-                    '4' => x += 4,
-                    '5' => x += 5,
-                    '6' => x += 6,
-                    '7' => x += 7,
-                    '8' => x += 8,
-                    'r' => match game.board.set(y, x, Piece::Rook + Color::Black) {
-                        Ok(_) => x += 1,
-                        Err(e) => println!("Couldnt set the board {e:?}")
-                    },
-                    'b' => match game.board.set(y, x, Piece::Bishop + Color::Black) {
-                        Ok(_) => x += 1,
-                        Err(e) => println!("Couldnt set the board {e:?}")
-                    },
-                    'n' => match game.board.set(y, x, Piece::Knight + Color::Black) {
-                        Ok(_) => x += 1,
-                        Err(e) => println!("Couldnt set the board {e:?}")
-                    },
-                    'k' => match game.board.set(y, x, Piece::King + Color::Black) {
-                        Ok(_) => x += 1,
-                        Err(e) => println!("Couldnt set the board {e:?}")
-                    },
-                    'q' => match game.board.set(y, x, Piece::Queen + Color::Black) {
-                        Ok(_) => x += 1,
-                        Err(e) => println!("Couldnt set the board {e:?}")
-                    },
-                    'p' => match game.board.set(y, x, Piece::Pawn + Color::Black) {
-                        Ok(_) => x += 1,
-                        Err(e) => println!("Couldnt set the board {e:?}"),
+                    // Empty squares, wide boards can have more than nine in a row.
+                    '0'..='9' => x += Self::read_number(c, &mut chars),
+                    _ => match piece_definition::from_letter(c) {
+                        Some((piece, color)) => match game.board.set(y, x, piece + color) {
+                            Ok(_) => x += 1,
+                            Err(e) => println!("Couldnt set the board {e:?}")
+                        },
+                        None => println!("piece not known.")
                     }
-                    'R' => match game.board.set(y, x, Piece::Rook + Color::White) {
-                        Ok(_) => x += 1,
-                        Err(e) => println!("Couldnt set the board {e:?}")
-                    }, //for rooks
-                    'B' => match game.board.set(y, x, Piece::Bishop + Color::White) {
-                        Ok(_) => x += 1,
-                        Err(e) => println!("Couldnt set the board {e:?}")
-                    },
-                    'N' => match game.board.set(y, x, Piece::Knight + Color::White) {
-                        Ok(_) => x += 1,
-                        Err(e) => println!("Couldnt set the board {e:?}")
-                    },
-                    'K' => match game.board.set(y, x, Piece::King + Color::White) {
-                        Ok(_) => x += 1,
-                        Err(e) => println!("Couldnt set the board {e:?}")
-                    },
-                    'Q' => match game.board.set(y, x, Piece::Queen + Color::White) {
-                        Ok(_) => x += 1,
-                        Err(e) => println!("Couldnt set the board {e:?}")
-                    },
-                    'P' => match game.board.set(y, x, Piece::Pawn + Color::White) {
-                        Ok(_) => x += 1,
-                        Err(e) => println!("Couldnt set the board {e:?}")
-                    },
-                    _ => println!("piece not known.")
                 }
             }
+            game.castling = game.standard_castling();
             return game
         }
 
+        fn read_number(first: char, chars: &mut Peekable<Chars>) -> usize {
+            let mut number = first.to_digit(10).unwrap_or(0) as usize;
+            while let Some(digit) = chars.peek().and_then(|next| next.to_digit(10)) {
+                number = number * 10 + digit as usize;
+                chars.next();
+            }
+            return number;
+        }
+
+        // Rows and columns of a FEN placement, the first rank decides the width.
+        fn placement_size(positions: &str) -> (usize, usize) {
+            let rows = positions.split('/').count();
+            let first_rank = positions.split('/').next().unwrap_or("");
+
+            let mut columns = 0;
+            let mut chars = first_rank.chars().peekable();
+            while let Some(c) = chars.next() {
+                match c {
+                    '~' => (),
+                    '0'..='9' => columns += Self::read_number(c, &mut chars),
+                    _ => columns += 1
+                }
+            }
+
+            return (rows.max(1), columns.max(1));
+        }
+
+        pub fn pocket_index(piece: &Piece) -> usize {
+            return piece.to_u8() as usize - 1;
+        }
+
+        fn set_pockets_from_fen(&mut self, field: &str) {
+            self.pockets = [[0; 6]; 2];
+
+            for c in field.chars() {
+                let piece = match c.to_ascii_lowercase() {
+                    'p' => Piece::Pawn,
+                    'n' => Piece::Knight,
+                    'b' => Piece::Bishop,
+                    'r' => Piece::Rook,
+                    'q' => Piece::Queen,
+                    _ => continue
+                };
+                let side = if c.is_ascii_uppercase() { 0 } else { 1 };
+                self.pockets[side][Self::pocket_index(&piece)] += 1;
+            }
+        }
+
+        // Pieces in hand as FEN letters, white ones first and the most valuable first.
+        pub fn pockets_to_fen(&self) -> String {
+            let mut field = String::new();
+
+            for side in 0..2 {
+                for (piece, letter) in [(Piece::Queen, 'q'), (Piece::Rook, 'r'), (Piece::Bishop, 'b'), (Piece::Knight, 'n'), (Piece::Pawn, 'p')] {
+                    for _ in 0..self.pockets[side][Self::pocket_index(&piece)] {
+                        field.push(if side == 0 { letter.to_ascii_uppercase() } else { letter });
+                    }
+                }
+            }
+
+            return field;
+        }
+
+        // The first rank of white or the last rank of black.
+        pub fn home_row(&self, color: &Color) -> usize {
+            return match color {
+                Color::White => self.board.num_rows() - 1,
+                Color::Black => 0
+            }
+        }
+
+        // Castling right i as its color and home row, in the order of Game::castling.
+        fn castling_side(&self, i: usize) -> (Color, usize) {
+            let color = if i < 2 { Color::White } else { Color::Black };
+            let row = self.home_row(&color);
+            return (color, row);
+        }
+
+        // Without a castling field a king in the middle of its home row and rooks in the corners may castle.
+        fn standard_castling(&self) -> [Option<usize>; 4] {
+            let mut castling = [None; 4];
+            let columns = self.board.num_columns();
+
+            for (i, color) in [Color::White, Color::Black].iter().enumerate() {
+                let row = &self.home_row(color);
+                if self.get_piece_from_position(&Position::new(columns / 2, *row)) != Some((Piece::King, color.clone())) {
+                    continue;
+                }
+                for (j, column) in [columns - 1, 0].iter().enumerate() {
+                    if self.get_piece_from_position(&Position::new(*column, *row)) == Some((Piece::Rook, color.clone())) {
+                        castling[i * 2 + j] = Some(*column);
+                    }
+                }
+            }
+
+            return castling;
+        }
+
+        //
+        // Reads the castling field of a FEN. K and Q stand for the outermost rook on that side
+        // of the king as in X-FEN, file letters name the rook as in Shredder-FEN.
+        pub fn set_castling_from_fen(&mut self, field: &str) {
+            self.castling = [None; 4];
+            let columns = self.board.num_columns();
+
+            for c in field.chars() {
+                let (color, first_right) = if c.is_ascii_uppercase() { (Color::White, 0) } else { (Color::Black, 2) };
+                let row = self.home_row(&color);
+                let king_column = match (0..columns).find(|column| self.get_piece_from_position(&Position::new(*column, row)) == Some((Piece::King, color.clone()))) {
+                    Some(column) => column,
+                    None => continue
+                };
+                let is_rook = |column: &usize| self.get_piece_from_position(&Position::new(*column, row)) == Some((Piece::Rook, color.clone()));
+
+                let rook_column = match c.to_ascii_lowercase() {
+                    'k' => (king_column + 1..columns).rev().find(is_rook),
+                    'q' => (0..king_column).find(is_rook),
+                    file @ 'a'..='z' if (file as usize - 'a' as usize) < columns => Some(file as usize - 'a' as usize).filter(is_rook),
+                    _ => None
+                };
+
+                match rook_column {
+                    Some(column) if column > king_column => self.castling[first_right] = Some(column),
+                    Some(column) if column < king_column => self.castling[first_right + 1] = Some(column),
+                    _ => ()
+                }
+            }
+        }
+
+        // The castling field in X-FEN, file letters are only used when another rook stands further out.
+        pub fn castling_to_fen(&self) -> String {
+            let mut field = String::new();
+
+            for (i, right) in self.castling.iter().enumerate() {
+                let column = match right {
+                    Some(column) => *column,
+                    None => continue
+                };
+                let (color, row) = self.castling_side(i);
+                let king_side = i % 2 == 0;
+
+                let outer_columns = if king_side { column + 1..self.board.num_columns() } else { 0..column };
+                let outermost = !outer_columns.into_iter().any(|other| self.get_piece_from_position(&Position::new(other, row)) == Some((Piece::Rook, color.clone())));
+
+                let letter = match (outermost, king_side) {
+                    (true, true) => 'k',
+                    (true, false) => 'q',
+                    (false, _) => (b'a' + column as u8) as char
+                };
+                field.push(if color == Color::White { letter.to_ascii_uppercase() } else { letter });
+            }
+
+            if field.is_empty() {
+                field.push('-');
+            }
+            return field;
+        }
+
         fn option_to_printable_string<T: Display>(option: &Option<T>) -> String {
             let mut string = String::new();
             match option {
@@ -135,6 +301,9 @@ pub mod game {
                     },
                     MoveType::Promote(piece) => {
                         string1.push_str(format!("Promote to {piece}: ").as_str())
+                    },
+                    MoveType::Drop(_piece) => {
+                        string1.push_str("Drop: ")
                     }
                 }
                 vec.push(string1);
@@ -178,7 +347,13 @@ pub mod game {
             return Color::Black
         }
 
-        fn is_check(&self, turn_color: &Color) -> bool {
+        // By the rules of the variant, the king of turn_color can be in danger without being attacked or the other way around.
+        pub fn is_check(&self, turn_color: &Color) -> bool {
+            return self.variant.is_check(self, turn_color);
+        }
+
+        // Whether a piece of the other side could take the king of turn_color.
+        pub fn is_king_attacked(&self, turn_color: &Color) -> bool {
             let checks = self.checks();
 
             for color in checks.iter() {
@@ -191,7 +366,7 @@ pub mod game {
         }
 
         fn checks(&self) -> Vec<Color> {
-            let all_moves = self.get_all_available_moves();
+            let all_moves = self.get_all_attacking_moves();
             let mut checks: Vec<Color> = vec![];
 
             for chess_move in all_moves {
@@ -223,17 +398,81 @@ pub mod game {
             return true
         }
 
+        // The variant decides which moves are legal, a game it has decided has none.
+        pub fn get_all_legal_moves(&self) -> Vec<ChessMove> {
+            if self.variant.outcome(self).is_some() {
+                return vec![];
+            }
+
+            return self.variant.legal_moves(self, self.get_all_turn_available_moves());
+        }
+
+        pub fn is_checkmate(&self) -> bool {
+            return self.is_check(&self.get_turn()) && self.get_all_legal_moves().len() == 0;
+        }
+
+        // The result once the game is over, by the rules of its variant.
+        pub fn outcome(&self) -> Option<Outcome> {
+            match self.variant.outcome(self) {
+                Some(outcome) => return Some(outcome),
+                None => ()
+            }
+
+            if self.get_all_legal_moves().is_empty() {
+                return Some(self.variant.no_moves_outcome(self));
+            }
+            return None;
+        }
+
+        // Castling rights as white king side, white queen side, black king side and black queen side.
+        pub fn castling_rights(&self) -> [bool; 4] {
+            return self.castling.map(|rook| rook.is_some());
+        }
+
+        // A king move loses both rights of its side, a rook leaving or taken on its castling square loses that one.
+        fn update_castling(&mut self, chess_move: &ChessMove) {
+            let sides: Vec<(Color, usize)> = (0..4).map(|i| self.castling_side(i)).collect();
+            for (right, (color, row)) in self.castling.iter_mut().zip(sides.into_iter()) {
+                let rook_square = match right {
+                    Some(column) => Position::new(*column, row),
+                    None => continue
+                };
+
+                if (chess_move.piece == Piece::King && chess_move.color == color) || chess_move.from == rook_square || chess_move.to == rook_square {
+                    *right = None;
+                }
+            }
+        }
+
         pub fn move_exists_in_list(available_moves: &Vec<ChessMove>, new_pos: &Position) -> Option<ChessMove> {
             for chess_move in available_moves {
                 if chess_move.to == *new_pos {
                     return Some(chess_move.clone())
                 }
             }
+            // The king can also castle by moving onto its own rook.
+            for chess_move in available_moves {
+                match chess_move.move_type {
+                    MoveType::Castle(rook_from, _) if rook_from == *new_pos => return Some(chess_move.clone()),
+                    _ => ()
+                }
+            }
             return None;
         }
 
         pub fn move_piece(&mut self, turn: &Color, chess_move: &ChessMove) -> Result<Game, ChessError> {
             if chess_move.color == *turn {
+                self.update_castling(chess_move);
+
+                // The rook is lifted first, in Chess960 the king may end on its square.
+                match chess_move.move_type {
+                    MoveType::Castle(rook_from, _) => match self.board.set(rook_from.row, rook_from.column, 0) {
+                        Ok(_) => (),
+                        Err(_) => panic!("board couldnt be set."),
+                    },
+                    _ => ()
+                }
+
                 match self.board.set(chess_move.from.row, chess_move.from.column, 0) {
                     Ok(_) => (),
                     Err(_) => panic!("board couldnt be set."),
@@ -251,11 +490,7 @@ pub mod game {
                             Err(_) => panic!("board couldnt be set."),
                         };
                     },
-                    MoveType::Castle(from, to) => {
-                        match self.board.set(from.row, from.column, 0) {
-                            Ok(_) => (),
-                            Err(_) => panic!("board couldnt be set."),
-                        };
+                    MoveType::Castle(_, to) => {
                         match self.board.set(to.row, to.column, Piece::Rook + chess_move.color.clone()) {
                             Ok(_) => (),
                             Err(_) => panic!("board couldnt be set."),
@@ -266,9 +501,19 @@ pub mod game {
                             Ok(_) => (),
                             Err(_) => panic!("board couldnt be set."),
                         };
-                    }
+                    },
+                    // The piece is already on its square, the variant takes it from the pocket.
+                    MoveType::Drop(_) => ()
+                }
+
+                let mut chess_move = chess_move.clone();
+                if self.variant.explodes() && chess_move.is_capture() {
+                    chess_move.exploded = self.explode(&chess_move);
                 }
 
+                let variant = self.variant.clone();
+                variant.after_move(self, &chess_move);
+
                 self.create_log(&chess_move);
                 return Ok(self.clone());
             }
@@ -276,6 +521,42 @@ pub mod game {
             return Err(ChessError::move_piece_error(&chess_move.from, &chess_move.to, &chess_move.piece, &chess_move.color));
         }
 
+        //
+        // Removes the capturing piece and every piece but pawns next to the square it took on.
+        // Castling rights of a rook or king that went up are lost. Returns the removed pieces.
+        fn explode(&mut self, chess_move: &ChessMove) -> Vec<(Position, Piece, Color)> {
+            let mut exploded = vec![];
+            let center = chess_move.to;
+
+            for row in center.row.saturating_sub(1)..=(center.row + 1).min(self.board.num_rows() - 1) {
+                for column in center.column.saturating_sub(1)..=(center.column + 1).min(self.board.num_columns() - 1) {
+                    let position = Position::new(column, row);
+                    match self.get_piece_from_position(&position) {
+                        Some((piece, color)) if position == center || piece != Piece::Pawn => {
+                            self.board.set(row, column, 0).unwrap();
+                            exploded.push((position, piece, color));
+                        },
+                        _ => ()
+                    }
+                }
+            }
+
+            let sides: Vec<(Color, usize)> = (0..4).map(|i| self.castling_side(i)).collect();
+            for (right, (color, row)) in self.castling.iter_mut().zip(sides.into_iter()) {
+                let lost = match right {
+                    Some(column) => exploded.iter().any(|(position, piece, piece_color)| {
+                        *position == Position::new(*column, row) || (*piece == Piece::King && *piece_color == color)
+                    }),
+                    None => false
+                };
+                if lost {
+                    *right = None;
+                }
+            }
+
+            return exploded;
+        }
+
         pub fn try_move_piece(&mut self, from: &Position, to: &Position) -> Result<Game, ChessError> {
             let piece_available_moves = self.get_available_moves(from);
 
@@ -285,7 +566,7 @@ pub mod game {
 
             match can_move {
                 Some(chess_move) => {
-                    if Self::is_valid_move(self.clone(), &chess_move)
+                    if self.get_all_legal_moves().contains(&chess_move)
                     {
                         self.move_piece(&turn, &chess_move).unwrap();
                         self.turn += 1;
@@ -304,50 +585,139 @@ pub mod game {
             }
         }
 
+        // Puts a piece from the pocket of the side to move on to, in variants with drops.
+        pub fn try_drop_piece(&mut self, piece: &Piece, to: &Position) -> Result<Game, ChessError> {
+            let turn = self.get_turn();
+            let drop = self.get_all_legal_moves().into_iter()
+                .find(|chess_move| chess_move.to == *to && chess_move.move_type == MoveType::Drop(*piece));
+
+            match drop {
+                Some(chess_move) => {
+                    self.move_piece(&turn, &chess_move).unwrap();
+                    self.turn += 1;
+                    return Ok(self.clone());
+                },
+                None => return Err(ChessError::invalid_move("Can not drop there".to_string(), to, to, piece, &turn))
+            }
+        }
+
         pub fn get_all_turn_available_moves(&self) -> Vec<ChessMove> {
-            let available_moves = self.get_all_available_moves();
             let turn = self.get_turn();
             let mut moves = vec![];
 
-            for chess_move in available_moves {
-                if turn == chess_move.color {
-
-                    moves.append(&mut vec![chess_move]);
+            // Only the pieces of the side to move, the other side might try to castle out of turn.
+            for x in 0..self.board.num_columns() {
+                for y in 0..self.board.num_rows() {
+                    let position = Position::new(x, y);
+                    match self.get_piece_from_position(&position) {
+                        Some((_, color)) if color == turn => moves.append(&mut self.get_available_moves(&position)),
+                        _ => ()
+                    }
                 }
             }
+            moves.append(&mut self.variant.special_moves(self));
             //println!("{}", moves.len());
             return moves;
         }
 
-        pub fn get_all_available_moves(&self) -> Vec<ChessMove> {
+        // Moves that can capture, castling is left out since it never captures and checking
+        // it would look for checks again.
+        fn get_all_attacking_moves(&self) -> Vec<ChessMove> {
             let mut moves = vec![];
             for x in 0..self.board.num_columns() {
                 for y in 0..self.board.num_rows() {
                     let position = Position::new(x, y);
-                    moves.append(&mut self.get_available_moves(&position))
+                    match self.get_piece_from_position(&position) {
+                        Some((Piece::King, color)) => moves.append(&mut self.definition_moves(&Piece::King, &color, &position)),
+                        _ => moves.append(&mut self.get_available_moves(&position))
+                    }
                 }
             }
 
             return moves
         }
 
-
         pub fn get_available_moves(&self, pos: &Position) -> Vec<ChessMove> {
             match self.get_piece_from_position(pos) {
                 None => vec![],
                 Some((piece, color)) => {
                     match piece {
-                        Piece::Rook => return self.available_rook_moves(&piece, &color, pos),
-                        Piece::Knight => return self.available_knight_moves(&piece, &color, pos),
                         Piece::King => return self.available_king_moves(&piece, &color, pos),
-                        Piece::Bishop => return self.available_bishop_moves(&piece, &color, pos),
-                        Piece::Queen => return self.available_queen_moves(&piece, &color, pos),
                         Piece::Pawn => return self.available_pawn_moves(&piece, &color, pos),
+                        _ => return self.definition_moves(&piece, &color, pos)
                     }
                 }
             }
         }
 
+        //
+        // Squares the piece on pos attacks, whatever stands on them apart from its own pieces.
+        // Pawns attack the two squares diagonally ahead and kings do not castle.
+        pub fn attacked_squares(&self, pos: &Position) -> Vec<Position> {
+            return match self.get_piece_from_position(pos) {
+                None => vec![],
+                Some((Piece::Pawn, color)) => {
+                    let ahead = if color == Color::White { -1 } else { 1 };
+                    [(-1, ahead), (1, ahead)].iter()
+                        .filter_map(|offset| self.offset_position(pos, offset))
+                        .filter(|square| match self.get_piece_from_position(square) {
+                            Some((_, other)) => other != color,
+                            None => true
+                        })
+                        .collect()
+                },
+                Some((piece, color)) => self.definition_moves(&piece, &color, pos).iter().map(|chess_move| chess_move.to).collect()
+            }
+        }
+
+        // The board as color sees it in Kriegspiel, its own pieces and where they attack or can move.
+        pub fn view_for(&self, color: Color) -> PlayerView {
+            let (rows, columns) = (self.board.num_rows(), self.board.num_columns());
+            let mut visible = Array2D::filled_with(false, rows, columns);
+
+            for row in 0..rows {
+                for column in 0..columns {
+                    let position = Position::new(column, row);
+                    match self.get_piece_from_position(&position) {
+                        Some((piece, piece_color)) if piece_color == color => {
+                            let mut squares = self.attacked_squares(&position);
+                            if piece == Piece::Pawn {
+                                squares.extend(self.available_pawn_moves(&piece, &color, &position).iter().map(|chess_move| chess_move.to));
+                            }
+                            squares.push(position);
+                            for square in squares.iter() {
+                                visible.set(square.row, square.column, true).unwrap();
+                            }
+                        },
+                        _ => ()
+                    }
+                }
+            }
+
+            let mut board = Array2D::filled_with(0, rows, columns);
+            for row in 0..rows {
+                for column in 0..columns {
+                    if visible[(row, column)] {
+                        board.set(row, column, self.board[(row, column)]).unwrap();
+                    }
+                }
+            }
+
+            return PlayerView {
+                color,
+                board,
+                visible
+            }
+        }
+
+        // An 8x8 board with only standard pieces, what tablebases and networks know.
+        pub fn is_standard_board(&self) -> bool {
+            if self.board.num_rows() != 8 || self.board.num_columns() != 8 {
+                return false;
+            }
+            return self.board.elements_row_major_iter().all(|value| *value == 0 || Piece::from_u8(*value).is_standard());
+        }
+
         pub fn get_piece_from_position(&self, pos: &Position) -> Option<(Piece, Color)> {
             match self.board.get(pos.row, pos.column) {
                 None => panic!("Couldnt get the position: ({}, {}).", pos.column, pos.row),
@@ -364,395 +734,119 @@ pub mod game {
             }
         }
 
-        fn create_iter(start: &usize, end: &usize, rev: &bool) -> Vec<usize> {
-            let mut moves: Vec<usize> = vec![];
-
-            for i in *start..*end {
-                moves.append(&mut vec![i])
-            }
-
-            if *rev {
-                moves.reverse();
-            }
-
-            return moves;
-        }
+        // The square offset from pos by (columns, rows), if it is on the board.
+        fn offset_position(&self, pos: &Position, offset: &(i8, i8)) -> Option<Position> {
+            let column = pos.column as i32 + offset.0 as i32;
+            let row = pos.row as i32 + offset.1 as i32;
 
-        fn rook_row_column(iter_value: &usize, val: &usize, direction: u8) -> Position {
-            if direction == 1 || direction == 3 {
-                return Position::new(*iter_value, *val)
-            } else {
-                return Position::new(*val, *iter_value)
+            if column < 0 || row < 0 || column >= self.board.num_columns() as i32 || row >= self.board.num_rows() as i32 {
+                return None;
             }
+            return Some(Position::new(column as usize, row as usize));
         }
 
-        fn array_moves(&self, pos: &Position, piece: &Piece, color: &Color, offset: Vec<(i8, i8, usize, usize)>) -> Vec<ChessMove> {
+        // The leaps and rides of the piece by its definition.
+        fn definition_moves(&self, piece: &Piece, color: &Color, pos: &Position) -> Vec<ChessMove> {
             let mut moves: Vec<ChessMove> = vec![];
+            let definition = piece_definition::definition(piece);
 
-            for (x, y, x_usize, y_usize) in offset.iter() {
-                let new_column =
-                    if *x > 0 { // *x = 1
-                        if pos.column > 7 - x_usize { continue } // 4 > 8 - 1
-                        pos.column + x_usize
-                    } else {
-                        if pos.column < *x_usize { continue }
-                        pos.column - x_usize
-                    };
-
-                let new_row =
-                    if *y > 0 { // *y = -2
-                        if pos.row > 7 - y_usize { continue }
-                        pos.row + y_usize
-                    } else {
-                        if pos.row < *y_usize { continue } // 4 < 2
-                        pos.row - y_usize
-                    };
-
-                let move_position = Position::new(new_column, new_row);
-
-                match self.get_piece_from_position(&move_position) {
-                    None => {
-                        moves.append(&mut vec![ChessMove::new(
-                            pos.clone(),
-                            move_position,
-                            piece.clone(),
-                            color.clone(),
-                            None,
-                            MoveType::Move
-                        )]);
-                    },
-                    Some((captured_piece, capture_color)) => {
-                        if *color != capture_color {
-                            moves.append(&mut vec![ChessMove::new(
-                                pos.clone(),
-                                move_position,
-                                piece.clone(),
-                                color.clone(),
-                                Some(captured_piece),
-                                MoveType::Move
-                            )]);
-                        } else { continue }
-                    }
-                }
-            }
-
-            return moves;
-        }
-
-        // Direction:
-// 0 = up
-// 1 = right
-// 2 = down
-// 3 = left
-        fn rook_moves(&self, pos: &Position, piece: &Piece, color: &Color, direction: u8) -> Vec<ChessMove> {
-            let mut moves: Vec<ChessMove> = vec![];
-            let iter =
-                if direction == 0 { Self::create_iter(&0, &pos.row, &true) } else if direction == 1 { Self::create_iter(&(pos.column + 1), &8, &false) } else if direction == 2 { Self::create_iter(&(pos.row + 1), &8, &false) } else { Self::create_iter(&0, &pos.column, &true) };
+            for leap in definition.leaps.iter() {
+                let move_position = match self.offset_position(pos, leap) {
+                    Some(position) => position,
+                    None => continue
+                };
 
-            for i in iter.iter() {
-                let value = if direction == 1 || direction == 3 { pos.row } else { pos.column };
-                let move_position = Self::rook_row_column(i, &value, direction);
                 match self.get_piece_from_position(&move_position) {
-                    None => {
-                        moves.append(&mut vec![ChessMove::new(
-                            pos.clone(),
-                            move_position,
-                            piece.clone(),
-                            color.clone(),
-                            None,
-                            MoveType::Move
-                        )]);
-                    },
-                    Some((captured_piece, capture_color)) => {
-                        if *color != capture_color {
-                            moves.append(&mut vec![ChessMove::new(
-                                pos.clone(),
-                                move_position,
-                                piece.clone(),
-                                color.clone(),
-                                Some(captured_piece),
-                                MoveType::Move
-                            )]);
-                        }
-
-                        break;
+                    None => moves.push(ChessMove::new(pos.clone(), move_position, piece.clone(), color.clone(), None, MoveType::Move)),
+                    Some((captured_piece, capture_color)) => if *color != capture_color {
+                        moves.push(ChessMove::new(pos.clone(), move_position, piece.clone(), color.clone(), Some(captured_piece), MoveType::Move));
                     }
                 }
             }
 
-            return moves;
-        }
-
-        // Direction
-// 0: diagonally up left - -
-// 1: diagonally up right + -
-// 2: diagonally down right + +
-// 3: diagonally down left - +
-        fn diagonal_moves(&self, pos: &Position, piece: &Piece, color: &Color, direction: u8) -> Vec<ChessMove> {
-            let mut moves: Vec<ChessMove> = vec![];
-
-            for i in 0..8 {
-                let move_position = if direction == 0 {
-                    if pos.column >= (i + 1) && pos.row >= (i + 1) {
-                        Position::new(pos.column - (i + 1), pos.row - (i + 1))
-                    } else {
-                        break;
-                    }
-                } else if direction == 1 {
-                    if pos.column < 8 - (i + 1) && pos.row >= (i + 1) {
-                        Position { column: pos.column + (i + 1), row: pos.row - (i + 1) }
-                    } else {
-                        break;
-                    }
-                } else if direction == 2 {
-                    if pos.column < 8 - (i + 1) && pos.row < 8 - (i + 1) {
-                        Position { column: pos.column + (i + 1), row: pos.row + (i + 1) }
-                    } else {
-                        break;
-                    }
-                } else {
-                    if pos.column >= (i + 1) && pos.row < 8 - (i + 1) {
-                        Position { column: pos.column - (i + 1), row: pos.row + (i + 1) }
-                    } else {
-                        break;
-                    }
-                };
-
-                match self.get_piece_from_position(&move_position) {
-                    None => {
-                        moves.append(&mut vec![ChessMove::new(
-                            pos.clone(),
-                            move_position,
-                            piece.clone(),
-                            color.clone(),
-                            None,
-                            MoveType::Move
-                        )]);
-                    },
-                    Some((captured_piece, capture_color)) => {
-                        if *color != capture_color {
-                            moves.append(&mut vec![ChessMove::new(
-                                pos.clone(),
-                                move_position,
-                                piece.clone(),
-                                color.clone(),
-                                Some(captured_piece),
-                                MoveType::Move
-                            )]);
+            for ride in definition.rides.iter() {
+                let mut current = *pos;
+                while let Some(move_position) = self.offset_position(&current, ride) {
+                    match self.get_piece_from_position(&move_position) {
+                        None => moves.push(ChessMove::new(pos.clone(), move_position, piece.clone(), color.clone(), None, MoveType::Move)),
+                        Some((captured_piece, capture_color)) => {
+                            if *color != capture_color {
+                                moves.push(ChessMove::new(pos.clone(), move_position, piece.clone(), color.clone(), Some(captured_piece), MoveType::Move));
+                            }
+                            break;
                         }
-
-                        break;
                     }
+                    current = move_position;
                 }
             }
 
             return moves;
         }
 
-        fn available_rook_moves(&self, piece: &Piece, color: &Color, pos: &Position) -> Vec<ChessMove> {
-            let mut moves: Vec<ChessMove> = vec![];
-
-            moves.append(&mut self.rook_moves(pos, piece, color, 0));
-            moves.append(&mut self.rook_moves(pos, piece, color, 1));
-            moves.append(&mut self.rook_moves(pos, piece, color, 2));
-            moves.append(&mut self.rook_moves(pos, piece, color, 3));
-
-            return moves;
-        }
-
-        fn available_knight_moves(&self, piece: &Piece, color: &Color, pos: &Position) -> Vec<ChessMove> {
+        fn available_king_moves(&self, piece: &Piece, color: &Color, pos: &Position) -> Vec<ChessMove> {
             let mut moves: Vec<ChessMove> = vec![];
 
-            let knight_offsets: Vec<(i8, i8, usize, usize)> = vec![
-                (-1, -2, 1, 2), (1, -2, 1, 2), (2, -1, 2, 1), (2, 1, 2, 1),
-                (1, 2, 1, 2), (-1, 2, 1, 2), (-2, 1, 2, 1), (-2, -1, 2, 1)
-            ];
-
-            moves.append(&mut self.array_moves(pos, piece, color, knight_offsets));
+            moves.append(&mut self.definition_moves(piece, color, pos));
+            moves.append(&mut self.castling_moves(color, pos));
 
             return moves;
         }
 
-        fn available_king_moves(&self, piece: &Piece, color: &Color, pos: &Position) -> Vec<ChessMove> {
+        //
+        // Castling by the Chess960 rules, which include the standard game. The king ends on the
+        // c file or the second file from the right, like g, with the rook next to it on the inner side. The squares either of them
+        // crosses have to be empty apart from the two of them, and the king may not castle out
+        // of check or cross an attacked square.
+        fn castling_moves(&self, color: &Color, pos: &Position) -> Vec<ChessMove> {
             let mut moves: Vec<ChessMove> = vec![];
+            let home_row = self.home_row(color);
+            let rights = match color {
+                Color::White => [0, 1],
+                Color::Black => [2, 3]
+            };
 
-            let king_offset: Vec<(i8, i8, usize, usize)> = vec![
-                (0, -1, 0, 1), (1, -1, 1, 1), (1, 0, 1, 0), (1, 1, 1, 1),
-                (0, 1, 0, 1), (-1, 1, 1, 1), (-1, 0, 1, 0), (-1, -1, 1, 1)
-            ];
-
-            moves.append(&mut self.array_moves(pos, piece, color, king_offset));
-
-            // castling
-            // TODO: if in check should not allow castling
-            // TODO: if king travel squares would result in check, castling shouldnt be allowed
-
-            /*if !self.is_check(color) {
+            if pos.row != home_row || rights.iter().all(|right| self.castling[*right].is_none()) {
                 return moves;
-            }*/
+            }
+            if self.is_check(color) {
+                return moves;
+            }
 
-            let king_starter_square =
-                match color {
-                    Color::White => Position::new(4, 7),
-                    Color::Black => Position::new(4, 0)
+            for right in rights.iter() {
+                let rook_from = match self.castling[*right] {
+                    Some(column) => Position::new(column, home_row),
+                    None => continue
                 };
-
-            let rook_left_starter_square = match color {
-                Color::White => Position::new(0, 7),
-                Color::Black => Position::new(0, 0)
-            };
-
-            let rook_right_starter_square = match color {
-                Color::White => Position::new(7, 7),
-                Color::Black => Position::new(7, 0)
-            };
-
-            match self.get_piece_from_position(&king_starter_square) {
-                None => return moves,
-                Some(_) => ()
-            }
-
-            // TODO: optimize performance
-            if !self.log.piece_has_moved_from_starting_square(&king_starter_square) {
-                if !self.log.piece_has_moved_from_starting_square(&rook_left_starter_square) {
-
-                    let space_left_free =
-                        match color {
-                            Color::White =>
-                                if  self.get_piece_from_position(&Position::new(1, 7)) == None &&
-                                    self.get_piece_from_position(&Position::new(2, 7)) == None &&
-                                    self.get_piece_from_position(&Position::new(3, 7)) == None {
-                                        true
-                                } else {false},
-                            Color::Black =>
-                                if  self.get_piece_from_position(&Position::new(3, 0)) == None &&
-                                    self.get_piece_from_position(&Position::new(2, 0)) == None &&
-                                    self.get_piece_from_position(&Position::new(1, 0)) == None {
-                                    true
-                                } else {false}
-                        };
-
-                    if space_left_free {
-                        let one_space_left = match color {
-                            Color::White => {
-                                Position::new(3, 7)
-                            },
-                            Color::Black => {
-                                Position::new(3,0)
-                            }
-                        };
-
-                        let two_spaces_left = match color {
-                            Color::White => {
-                                Position::new(2, 7)
-                            },
-                            Color::Black => {
-                                Position::new(2,0)
-                            }
-                        };
-
-                        if self.spaces_next_to_king_not_check(color, &king_starter_square, &one_space_left, &two_spaces_left) {
-                            let to = Position::new(pos.column - 2, pos.row);
-                            let rook_to = Position::new(to.column + 1, pos.row);
-                            let castle_left_move = ChessMove::new(pos.clone(), to, piece.clone(), color.clone(), None, MoveType::Castle(rook_left_starter_square, rook_to));
-                            moves.append(&mut vec![castle_left_move])
-                        }
-                    }
+                if self.get_piece_from_position(&rook_from) != Some((Piece::Rook, color.clone())) {
+                    continue;
                 }
 
-                if !self.log.piece_has_moved_from_starting_square(&rook_right_starter_square) {
-
-                    let space_right_free =
-                        match color {
-                            Color::White =>
-                                if  self.get_piece_from_position(&Position::new(5, 7)) == None &&
-                                    self.get_piece_from_position(&Position::new(6, 7)) == None {
-                                    true
-                                } else {false},
-                            Color::Black =>
-                                if  self.get_piece_from_position(&Position::new(5, 0)) == None &&
-                                    self.get_piece_from_position(&Position::new(6, 0)) == None {
-                                    true
-                                } else {false}
-                        };
-
-                    if space_right_free {
-                        let one_space_right = match color {
-                            Color::White => {
-                                Position::new(5, 7)
-                            },
-                            Color::Black => {
-                                Position::new(5,0)
-                            }
-                        };
-
-                        let two_spaces_right = match color {
-                            Color::White => {
-                                Position::new(6, 7)
-                            },
-                            Color::Black => {
-                                Position::new(6,0)
-                            }
-                        };
+                let columns = self.board.num_columns();
+                let (king_to, rook_to) = if rook_from.column > pos.column {
+                    (Position::new(columns - 2, home_row), Position::new(columns - 3, home_row))
+                } else {
+                    (Position::new(2, home_row), Position::new(3, home_row))
+                };
 
-                        if self.spaces_next_to_king_not_check(color, &king_starter_square, &one_space_right, &two_spaces_right) {
-                            let to = Position::new(pos.column + 2, pos.row);
-                            let rook_to = Position::new(to.column - 1, pos.row);
-                            let castle_left_move = ChessMove::new(pos.clone(), to, piece.clone(), color.clone(), None, MoveType::Castle(rook_right_starter_square, rook_to));
-                            moves.append(&mut vec![castle_left_move])
-                        }
-                    }
+                let crossed = |from: usize, to: usize| from.min(to)..=from.max(to);
+                let blocked = crossed(pos.column, king_to.column).chain(crossed(rook_from.column, rook_to.column))
+                    .filter(|column| *column != pos.column && *column != rook_from.column)
+                    .any(|column| self.get_piece_from_position(&Position::new(column, home_row)).is_some());
+                if blocked {
+                    continue;
                 }
-            }
-
-
-            return moves;
-        }
-
-        fn spaces_next_to_king_not_check(&self, color: &Color, starting_position: &Position, pos1: &Position, pos2: &Position) -> bool {
-                return Self::is_valid_move(self.clone(),
-                                           &ChessMove::new(
-                        starting_position.clone(),
-                        pos1.clone(),
-                        Piece::King,
-                        color.clone(),
-                        None,
-                        MoveType::Move)
-                ) &&
-                Self::is_valid_move(self.clone(),
-                                    &ChessMove::new(
-                                        starting_position.clone(),
-                                        pos2.clone(),
-                                        Piece::King,
-                                        color.clone(),
-                                        None,
-                                        MoveType::Move)
-                )
-        }
-
-        fn available_bishop_moves(&self, piece: &Piece, color: &Color, pos: &Position) -> Vec<ChessMove> {
-            let mut moves: Vec<ChessMove> = vec![];
 
-            moves.append(&mut self.diagonal_moves(pos, piece, color, 0));
-            moves.append(&mut self.diagonal_moves(pos, piece, color, 1));
-            moves.append(&mut self.diagonal_moves(pos, piece, color, 2));
-            moves.append(&mut self.diagonal_moves(pos, piece, color, 3));
-
-            return moves;
-        }
-
-        fn available_queen_moves(&self, piece: &Piece, color: &Color, pos: &Position) -> Vec<ChessMove> {
-            let mut moves: Vec<ChessMove> = vec![];
-
-            moves.append(&mut self.rook_moves(pos, piece, color, 0));
-            moves.append(&mut self.rook_moves(pos, piece, color, 1));
-            moves.append(&mut self.rook_moves(pos, piece, color, 2));
-            moves.append(&mut self.rook_moves(pos, piece, color, 3));
+                // Whether the king can stand on its square is decided with the rook moved, like
+                // any other move, as the rook can block a line to it.
+                let attacked = crossed(pos.column, king_to.column)
+                    .filter(|column| *column != pos.column && *column != king_to.column)
+                    .any(|column| !Self::is_valid_move(self.clone(), &ChessMove::new(pos.clone(), Position::new(column, home_row), Piece::King, color.clone(), None, MoveType::Move)));
+                if attacked {
+                    continue;
+                }
 
-            moves.append(&mut self.diagonal_moves(pos, piece, color, 0));
-            moves.append(&mut self.diagonal_moves(pos, piece, color, 1));
-            moves.append(&mut self.diagonal_moves(pos, piece, color, 2));
-            moves.append(&mut self.diagonal_moves(pos, piece, color, 3));
+                moves.push(ChessMove::new(pos.clone(), king_to, Piece::King, color.clone(), None, MoveType::Castle(rook_from, rook_to)));
+            }
 
             return moves;
         }
@@ -764,9 +858,10 @@ pub mod game {
                 Color::Black => false
             };
 
+            // The step ahead reaches the last rank.
             let is_promote = match is_white {
                 true => pos.row == 1,
-                false => pos.row == 6
+                false => pos.row + 2 == self.board.num_rows()
             };
 
             // Do single move
@@ -775,7 +870,7 @@ pub mod game {
             match self.get_piece_from_position(&move_position) {
                 None => {
                     if is_promote {
-                        let promote_pieces = [Piece::Queen, Piece::Bishop, Piece::Knight, Piece::Rook];
+                        let promote_pieces = self.variant.promotion_pieces();
                         for promote_piece in promote_pieces.iter() {
 
                             moves.append(&mut vec![ChessMove::new(
@@ -810,7 +905,7 @@ pub mod game {
                         let has_same_color = captured_color != *color;
                         if has_same_color {
                             if is_promote {
-                                let promote_pieces = [Piece::Queen, Piece::Bishop, Piece::Knight, Piece::Rook];
+                                let promote_pieces = self.variant.promotion_pieces();
                                 for promote_piece in promote_pieces.iter() {
 
                                     moves.append(&mut vec![ChessMove::new(
@@ -838,7 +933,7 @@ pub mod game {
                 }
             }
 
-            if pos.column < 7 {
+            if pos.column + 1 < self.board.num_columns() {
                 let move_position = Position::new(pos.column + 1, row_single);
                 match self.get_piece_from_position(&move_position) {
                     None => (),
@@ -846,7 +941,7 @@ pub mod game {
                         let has_same_color = captured_color != *color;
                         if has_same_color {
                             if is_promote {
-                                let promote_pieces = [Piece::Queen, Piece::Bishop, Piece::Knight, Piece::Rook];
+                                let promote_pieces = self.variant.promotion_pieces();
                                 for promote_piece in promote_pieces.iter() {
 
                                     moves.append(&mut vec![ChessMove::new(
@@ -874,12 +969,17 @@ pub mod game {
                 }
             }
 
-            if (is_white && pos.row == 6) || (!is_white && pos.row == 1) {
+            //
+            // From the second rank of its side the pawn may go two squares when both are empty,
+            // small boards leave the double step out.
+            let start_row = if is_white { self.board.num_rows() - 2 } else { 1 };
+            if self.board.num_rows() >= DOUBLE_STEP_MIN_RANKS && pos.row == start_row {
                 let row_double = if is_white { pos.row - 2 } else { pos.row + 2 };
                 let move_position = Position::new(pos.column, row_double);
+                let passed = Position::new(pos.column, row_single);
 
-                match self.get_piece_from_position(&move_position) {
-                    None => {
+                match (self.get_piece_from_position(&passed), self.get_piece_from_position(&move_position)) {
+                    (None, None) => {
                         moves.append(&mut vec![ChessMove::new(
                             pos.clone(),
                             move_position,
@@ -889,7 +989,7 @@ pub mod game {
                             MoveType::Move
                         )]);
                     },
-                    Some(_) => ()
+                    _ => ()
                 }
             }
 
@@ -933,7 +1033,7 @@ pub mod game {
                             moves.append(&mut vec![ein_peasant_left]);
                         }
                     }
-                    if pos.column != 7 {
+                    if pos.column + 1 != self.board.num_columns() {
                         let take_pos_right = match color {
                             Color::White => Position::new(pos.column + 1, pos.row - 1),
                             Color::Black => Position::new(pos.column + 1, pos.row + 1)
@@ -1,6 +1,6 @@
 pub mod log {
     use crate::chess::chess_move::chess_move::ChessMove;
-    use crate::chess::position::position::Position;
+    use crate::chess::piece::piece::Piece;
 
     #[derive(Clone)]
     pub struct Log {
@@ -19,24 +19,21 @@ pub mod log {
         }
 
         pub fn get_last_move(&self) -> Option<ChessMove> {
-            if self.log.len() > 1 {
-                match self.log.get(self.log.len() - 1) {
-                    None => panic!("Couldnt get the last log..."),
-                    Some(t) => return Some(t.clone())
-                }
-            }
-
-            return None
+            return self.log.last().cloned();
         }
 
-        pub fn piece_has_moved_from_starting_square(&self, pos: &Position) -> bool {
-            for chess_move in self.log.iter() {
-                if chess_move.from == *pos {
-                    return true;
+        // Number of half moves since the last capture or pawn move, used for the fifty-move rule.
+        pub fn half_move_clock(&self) -> u32 {
+            let mut count = 0;
+
+            for chess_move in self.log.iter().rev() {
+                if chess_move.piece == Piece::Pawn || chess_move.piece_at_position.is_some() {
+                    break;
                 }
+                count += 1;
             }
 
-            return false;
+            return count;
         }
     }
 }
\ No newline at end of file
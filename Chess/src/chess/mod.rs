@@ -4,4 +4,9 @@ pub mod color;
 pub mod log;
 pub mod chess_move;
 pub mod piece;
-pub mod position;
\ No newline at end of file
+pub mod piece_definition;
+pub mod position;
+pub mod chess960;
+pub mod variant;
+pub mod player_view;
+pub mod umpire;
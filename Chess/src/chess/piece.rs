@@ -2,33 +2,42 @@ pub mod piece {
     use std::{fmt, ops};
     use crate::chess::color::color::Color;
 
-    const LOW_3_BITS_MASK: u8 = 0b0000_0111;
+    const LOW_4_BITS_MASK: u8 = 0b0000_1111;
 
     #[derive(Debug, Copy, Clone)]
     pub enum Piece {
         Pawn = 1,
-        // 1 or 9
+        // 1 or 17
         Bishop = 2,
-        // 2 or 10
+        // 2 or 18
         Knight = 3,
-        // 3 or 11
+        // 3 or 19
         Rook = 4,
-        // 4 or 12
+        // 4 or 20
         Queen = 5,
-        // 5 or 13
-        King = 6
-        // 6 or 14
+        // 5 or 21
+        King = 6,
+        // 6 or 22
+        Archbishop = 7,
+        // 7 or 23, bishop and knight
+        Chancellor = 8,
+        // 8 or 24, rook and knight
+        Amazon = 9
+        // 9 or 25, queen and knight
     }
 
     impl Piece {
         pub fn from_u8(value: u8) -> Piece {
-            match value & LOW_3_BITS_MASK {
+            match value & LOW_4_BITS_MASK {
                 1 => Piece::Pawn,
                 2 => Piece::Bishop,
                 3 => Piece::Knight,
                 4 => Piece::Rook,
                 5 => Piece::Queen,
                 6 => Piece::King,
+                7 => Piece::Archbishop,
+                8 => Piece::Chancellor,
+                9 => Piece::Amazon,
                 _ => panic!("Unknown value: {}", value),
             }
         }
@@ -41,9 +50,17 @@ pub mod piece {
                 Piece::Rook => 4_u8,
                 Piece::Queen => 5_u8,
                 Piece::King => 6_u8,
+                Piece::Archbishop => 7_u8,
+                Piece::Chancellor => 8_u8,
+                Piece::Amazon => 9_u8,
             }
         }
 
+        // One of the six pieces of standard chess.
+        pub fn is_standard(&self) -> bool {
+            return self.to_u8() <= Piece::King.to_u8();
+        }
+
         pub fn get_piece_enum(input: u8) -> Piece {
             return Piece::from_u8(input)
         }
@@ -58,6 +75,9 @@ pub mod piece {
                 Piece::Rook => write!(f, "Rook"),
                 Piece::Queen => write!(f, "Queen"),
                 Piece::King => write!(f, "King"),
+                Piece::Archbishop => write!(f, "Archbishop"),
+                Piece::Chancellor => write!(f, "Chancellor"),
+                Piece::Amazon => write!(f, "Amazon"),
             }
         }
     }
@@ -0,0 +1,56 @@
+pub mod piece_definition {
+    use crate::chess::color::color::Color;
+    use crate::chess::piece::piece::Piece;
+
+    // Offsets are (columns, rows), row 0 is the eighth rank.
+    const KNIGHT: [(i8, i8); 8] = [(-1, -2), (1, -2), (2, -1), (2, 1), (1, 2), (-1, 2), (-2, 1), (-2, -1)];
+    const ORTHOGONAL: [(i8, i8); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+    const DIAGONAL: [(i8, i8); 4] = [(-1, -1), (1, -1), (1, 1), (-1, 1)];
+    const ALL_DIRECTIONS: [(i8, i8); 8] = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+
+    //
+    // How a piece is written and how it moves. A leaper jumps straight to each of its leaps,
+    // a rider slides along each of its rides until it hits a piece. Compound pieces have
+    // both, the Archbishop rides like a bishop and leaps like a knight. Pawns and castling
+    // are generated by Game since they depend on more than the board around the piece.
+    pub struct PieceDefinition {
+        pub piece: Piece,
+        // FEN letter of the white piece, black pieces use the lower case.
+        pub letter: char,
+        pub leaps: &'static [(i8, i8)],
+        pub rides: &'static [(i8, i8)]
+    }
+
+    // By Piece::to_u8 - 1.
+    pub const DEFINITIONS: [PieceDefinition; 9] = [
+        PieceDefinition { piece: Piece::Pawn, letter: 'P', leaps: &[], rides: &[] },
+        PieceDefinition { piece: Piece::Bishop, letter: 'B', leaps: &[], rides: &DIAGONAL },
+        PieceDefinition { piece: Piece::Knight, letter: 'N', leaps: &KNIGHT, rides: &[] },
+        PieceDefinition { piece: Piece::Rook, letter: 'R', leaps: &[], rides: &ORTHOGONAL },
+        PieceDefinition { piece: Piece::Queen, letter: 'Q', leaps: &[], rides: &ALL_DIRECTIONS },
+        PieceDefinition { piece: Piece::King, letter: 'K', leaps: &ALL_DIRECTIONS, rides: &[] },
+        PieceDefinition { piece: Piece::Archbishop, letter: 'A', leaps: &KNIGHT, rides: &DIAGONAL },
+        PieceDefinition { piece: Piece::Chancellor, letter: 'C', leaps: &KNIGHT, rides: &ORTHOGONAL },
+        PieceDefinition { piece: Piece::Amazon, letter: 'M', leaps: &KNIGHT, rides: &ALL_DIRECTIONS }
+    ];
+
+    pub fn definition(piece: &Piece) -> &'static PieceDefinition {
+        return &DEFINITIONS[piece.to_u8() as usize - 1];
+    }
+
+    // The piece and color of a FEN letter.
+    pub fn from_letter(letter: char) -> Option<(Piece, Color)> {
+        let color = if letter.is_ascii_uppercase() { Color::White } else { Color::Black };
+        return DEFINITIONS.iter()
+            .find(|definition| definition.letter == letter.to_ascii_uppercase())
+            .map(|definition| (definition.piece, color));
+    }
+
+    pub fn to_letter(piece: &Piece, color: &Color) -> char {
+        let letter = definition(piece).letter;
+        return match color {
+            Color::White => letter,
+            Color::Black => letter.to_ascii_lowercase()
+        }
+    }
+}
@@ -0,0 +1,31 @@
+pub mod player_view {
+    use array2d::Array2D;
+    use crate::chess::color::color::Color;
+    use crate::chess::piece::piece::Piece;
+    use crate::chess::position::position::Position;
+
+    //
+    // The board as one side sees it in Kriegspiel, made by Game::view_for. Squares with its
+    // own pieces and the squares they attack or can move to are visible, everything else is
+    // hidden and reads as empty.
+    #[derive(Clone)]
+    pub struct PlayerView {
+        pub color: Color,
+        // Piece values like Game::board, 0 on empty and hidden squares.
+        pub board: Array2D<u8>,
+        pub visible: Array2D<bool>
+    }
+
+    impl PlayerView {
+        pub fn is_visible(&self, pos: &Position) -> bool {
+            return *self.visible.get(pos.row, pos.column).unwrap_or(&false);
+        }
+
+        pub fn get_piece_from_position(&self, pos: &Position) -> Option<(Piece, Color)> {
+            return match self.board.get(pos.row, pos.column) {
+                Some(value) if *value != 0 => Some((Piece::get_piece_enum(*value), Color::get_piece_color(*value))),
+                _ => None
+            }
+        }
+    }
+}
@@ -14,6 +14,12 @@ pub mod position {
                 row
             }
         }
+
+        // Square name like e4 on a board with that many ranks, row 0 is the last rank.
+        pub fn to_algebraic(&self, ranks: usize) -> String {
+            let file = (b'a' + self.column as u8) as char;
+            return format!("{}{}", file, ranks - self.row);
+        }
     }
 
     impl PartialEq<Position> for Position {
@@ -0,0 +1,140 @@
+pub mod umpire {
+    use std::fmt;
+    use crate::chess::chess_move::chess_move::ChessMove;
+    use crate::chess::game::game::Game;
+    use crate::chess::piece::piece::Piece;
+    use crate::chess::position::position::Position;
+
+    //
+    // Where a check comes from, seen from the king. Of the two diagonals through the king's
+    // square the long one has more squares, on a square where both are alike it is taken as long.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum CheckDirection {
+        File,
+        Rank,
+        LongDiagonal,
+        ShortDiagonal,
+        Knight
+    }
+
+    // What the umpire tells both players in Kriegspiel, neither learns which piece did it.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Announcement {
+        // A move the pieces could make that the rules do not allow, the player tries again.
+        Illegal,
+        Capture {
+            square: Position,
+            ranks: usize
+        },
+        Check(CheckDirection)
+    }
+
+    impl fmt::Display for CheckDirection {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                CheckDirection::File => write!(f, "file"),
+                CheckDirection::Rank => write!(f, "rank"),
+                CheckDirection::LongDiagonal => write!(f, "long diagonal"),
+                CheckDirection::ShortDiagonal => write!(f, "short diagonal"),
+                CheckDirection::Knight => write!(f, "knight")
+            }
+        }
+    }
+
+    impl fmt::Display for Announcement {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Announcement::Illegal => write!(f, "illegal"),
+                Announcement::Capture { square, ranks } => write!(f, "capture on {}", square.to_algebraic(*ranks)),
+                Announcement::Check(CheckDirection::Knight) => write!(f, "check by a knight"),
+                Announcement::Check(direction) => write!(f, "check on the {}", direction)
+            }
+        }
+    }
+
+    //
+    // Illegal when the side to move tries a move its pieces could make but the rules do not
+    // allow, like moving into check. Tries that are impossible anyway are not announced.
+    pub fn announce_try(game: &Game, from: &Position, to: &Position) -> Option<Announcement> {
+        match game.get_piece_from_position(from) {
+            Some((_, color)) if color == game.get_turn() => (),
+            _ => return None
+        }
+
+        let chess_move = Game::move_exists_in_list(&game.get_available_moves(from), to)?;
+        if game.get_all_legal_moves().contains(&chess_move) {
+            return None;
+        }
+        return Some(Announcement::Illegal);
+    }
+
+    // The capture and checks of chess_move, game is the position after it.
+    pub fn announce_move(game: &Game, chess_move: &ChessMove) -> Vec<Announcement> {
+        let mut announcements = vec![];
+        if chess_move.is_capture() {
+            announcements.push(Announcement::Capture { square: chess_move.to, ranks: game.board.num_rows() });
+        }
+
+        let turn = game.get_turn();
+        let mut king = None;
+        let mut attackers = vec![];
+        for row in 0..game.board.num_rows() {
+            for column in 0..game.board.num_columns() {
+                let position = Position::new(column, row);
+                match game.get_piece_from_position(&position) {
+                    Some((Piece::King, color)) if color == turn => king = Some(position),
+                    Some((_, color)) if color != turn => attackers.push(position),
+                    _ => ()
+                }
+            }
+        }
+
+        let king = match king {
+            Some(king) => king,
+            None => return announcements
+        };
+        for attacker in attackers.iter() {
+            if game.attacked_squares(attacker).contains(&king) {
+                announcements.push(Announcement::Check(check_direction(game, &king, attacker)));
+            }
+        }
+
+        return announcements;
+    }
+
+    fn check_direction(game: &Game, king: &Position, attacker: &Position) -> CheckDirection {
+        let columns = attacker.column as i32 - king.column as i32;
+        let rows = attacker.row as i32 - king.row as i32;
+
+        if columns == 0 {
+            return CheckDirection::File;
+        }
+        if rows == 0 {
+            return CheckDirection::Rank;
+        }
+        if columns.abs() != rows.abs() {
+            return CheckDirection::Knight;
+        }
+
+        let step = (columns.signum(), rows.signum());
+        if diagonal_length(game, king, step) >= diagonal_length(game, king, (step.0, -step.1)) {
+            return CheckDirection::LongDiagonal;
+        }
+        return CheckDirection::ShortDiagonal;
+    }
+
+    // Squares of the diagonal through pos along step, both ways.
+    fn diagonal_length(game: &Game, pos: &Position, step: (i32, i32)) -> usize {
+        let mut length = 1;
+        for (column_step, row_step) in [step, (-step.0, -step.1)] {
+            let mut column = pos.column as i32 + column_step;
+            let mut row = pos.row as i32 + row_step;
+            while column >= 0 && row >= 0 && column < game.board.num_columns() as i32 && row < game.board.num_rows() as i32 {
+                length += 1;
+                column += column_step;
+                row += row_step;
+            }
+        }
+        return length;
+    }
+}
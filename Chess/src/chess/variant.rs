@@ -0,0 +1,637 @@
+pub mod variant {
+    use std::fmt;
+    use std::sync::Arc;
+    use crate::chess::chess_move::chess_move::{ChessMove, MoveType};
+    use crate::chess::color::color::Color;
+    use crate::chess::game::game::Game;
+    use crate::chess::piece::piece::Piece;
+    use crate::chess::position::position::Position;
+
+    pub const STANDARD_PLACEMENT: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR";
+
+    const STANDARD_PROMOTIONS: [Piece; 4] = [Piece::Queen, Piece::Bishop, Piece::Knight, Piece::Rook];
+
+    // In Antichess a pawn can become a king as well.
+    const ANTICHESS_PROMOTIONS: [Piece; 5] = [Piece::Queen, Piece::Bishop, Piece::Knight, Piece::Rook, Piece::King];
+
+    pub const CAPABLANCA_PLACEMENT: &str = "rnabqkbcnr/pppppppppp/10/10/10/10/PPPPPPPPPP/RNABQKBCNR";
+
+    const CAPABLANCA_PROMOTIONS: [Piece; 6] = [Piece::Queen, Piece::Chancellor, Piece::Archbishop, Piece::Rook, Piece::Bishop, Piece::Knight];
+
+    pub const GARDNER_PLACEMENT: &str = "rnbqk/ppppp/5/PPPPP/RNBQK";
+
+    pub const LOS_ALAMOS_PLACEMENT: &str = "rnqknr/pppppp/6/6/PPPPPP/RNQKNR";
+
+    // Los Alamos chess has no bishops to promote to.
+    const LOS_ALAMOS_PROMOTIONS: [Piece; 3] = [Piece::Queen, Piece::Rook, Piece::Knight];
+
+    // Checks that win a game of Three-check.
+    const CHECKS_TO_WIN: u32 = 3;
+
+    const CHECK_BONUS: i32 = 200;
+
+    // Bonus for a king by its distance to the nearest centre square, in King of the Hill.
+    const KING_CENTER_BONUS: [i32; 4] = [0, 80, 30, 10];
+
+    // Pieces in hand by Game::pocket_index, they are worth more than on the board since they can go anywhere.
+    const POCKET_VALUES: [i32; 6] = [150, 350, 350, 500, 950, 0];
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum Outcome {
+        Win(Color),
+        Draw
+    }
+
+    //
+    // The rules of a game that differ from standard chess. Game generates the pseudo-legal
+    // moves of the pieces and asks its variant which of them are legal, what else a move
+    // changes and when the game is over. Every method has the standard rules as default.
+    pub trait Variant: Send + Sync {
+        // Name in the Variant tag of a PGN.
+        fn name(&self) -> &'static str;
+
+        // Name in the UCI_Variant option.
+        fn uci_name(&self) -> &'static str;
+
+        fn start_placement(&self) -> &'static str {
+            return STANDARD_PLACEMENT;
+        }
+
+        fn is_legal(&self, game: &Game, chess_move: &ChessMove) -> bool {
+            return Game::is_valid_move(game.clone(), chess_move);
+        }
+
+        fn legal_moves(&self, game: &Game, moves: Vec<ChessMove>) -> Vec<ChessMove> {
+            return moves.into_iter().filter(|chess_move| self.is_legal(game, chess_move)).collect();
+        }
+
+        fn is_check(&self, game: &Game, color: &Color) -> bool {
+            return game.is_king_attacked(color);
+        }
+
+        fn has_castling(&self) -> bool {
+            return true;
+        }
+
+        fn promotion_pieces(&self) -> &'static [Piece] {
+            return &STANDARD_PROMOTIONS;
+        }
+
+        // Moves besides those of the pieces on the board, like drops.
+        fn special_moves(&self, _game: &Game) -> Vec<ChessMove> {
+            return vec![];
+        }
+
+        // Called when the pieces of a move are placed, before the turn passes.
+        fn after_move(&self, _game: &mut Game, _chess_move: &ChessMove) {}
+
+        // A result the variant decides whatever moves are left, like a king reaching the hill.
+        fn outcome(&self, _game: &Game) -> Option<Outcome> {
+            return None;
+        }
+
+        // The result when the side to move has no legal moves.
+        fn no_moves_outcome(&self, game: &Game) -> Outcome {
+            let turn = game.get_turn();
+            if game.is_check(&turn) {
+                return Outcome::Win(Color::to_opposite(turn));
+            }
+            return Outcome::Draw;
+        }
+
+        // Whether too little material to mate draws, in standard chess a lone king can not lose.
+        fn dead_material_draws(&self) -> bool {
+            return true;
+        }
+
+        // Whether a capture blows up the pieces around it, Game::move_piece removes them.
+        fn explodes(&self) -> bool {
+            return false;
+        }
+
+        // Whether pieces in hand are part of the position and its FEN.
+        fn uses_pockets(&self) -> bool {
+            return false;
+        }
+
+        // Whether each side only sees its own pieces and what they attack, see Game::view_for.
+        fn hides_pieces(&self) -> bool {
+            return false;
+        }
+
+        // Whether the standard rules apply in full, so that tablebases can be probed.
+        fn is_standard(&self) -> bool {
+            return false;
+        }
+
+        // A FEN field after the en passant square holding state only this variant has.
+        fn fen_state(&self, _game: &Game) -> Option<String> {
+            return None;
+        }
+
+        // Reads a field written by fen_state, returns whether the field was one.
+        fn read_fen_state(&self, _game: &mut Game, _field: &str) -> bool {
+            return false;
+        }
+
+        // Multiplies the evaluation of material and piece squares, -1 where losing pieces is the goal.
+        fn evaluation_sign(&self) -> i32 {
+            return 1;
+        }
+
+        // Added to the evaluation, in centipawns from whites point of view.
+        fn evaluate(&self, _game: &Game) -> i32 {
+            return 0;
+        }
+    }
+
+    impl fmt::Debug for dyn Variant {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.name())
+        }
+    }
+
+    pub struct Standard;
+
+    impl Variant for Standard {
+        fn name(&self) -> &'static str {
+            return "Standard";
+        }
+
+        fn uci_name(&self) -> &'static str {
+            return "chess";
+        }
+
+        fn is_standard(&self) -> bool {
+            return true;
+        }
+    }
+
+    // A player who gives check three times wins.
+    pub struct ThreeCheck;
+
+    impl Variant for ThreeCheck {
+        fn name(&self) -> &'static str {
+            return "Three-check";
+        }
+
+        fn uci_name(&self) -> &'static str {
+            return "3check";
+        }
+
+        fn after_move(&self, game: &mut Game, chess_move: &ChessMove) {
+            if game.is_check(&Color::to_opposite(chess_move.color.clone())) {
+                game.checks_given[color_index(&chess_move.color)] += 1;
+            }
+        }
+
+        fn outcome(&self, game: &Game) -> Option<Outcome> {
+            if game.checks_given[0] >= CHECKS_TO_WIN {
+                return Some(Outcome::Win(Color::White));
+            }
+            if game.checks_given[1] >= CHECKS_TO_WIN {
+                return Some(Outcome::Win(Color::Black));
+            }
+            return None;
+        }
+
+        // A knight alone can still give three checks.
+        fn dead_material_draws(&self) -> bool {
+            return false;
+        }
+
+        // Checks white and black still have to give, like 3+3 at the start.
+        fn fen_state(&self, game: &Game) -> Option<String> {
+            let remaining = game.checks_given.map(|given| CHECKS_TO_WIN.saturating_sub(given));
+            return Some(format!("{}+{}", remaining[0], remaining[1]));
+        }
+
+        fn read_fen_state(&self, game: &mut Game, field: &str) -> bool {
+            let remaining = match field.split_once('+') {
+                Some((white, black)) => (white.parse::<u32>(), black.parse::<u32>()),
+                None => return false
+            };
+
+            return match remaining {
+                (Ok(white), Ok(black)) if white <= CHECKS_TO_WIN && black <= CHECKS_TO_WIN => {
+                    game.checks_given = [CHECKS_TO_WIN - white, CHECKS_TO_WIN - black];
+                    true
+                },
+                _ => false
+            }
+        }
+
+        fn evaluate(&self, game: &Game) -> i32 {
+            return (game.checks_given[0] as i32 - game.checks_given[1] as i32) * CHECK_BONUS;
+        }
+    }
+
+    // A player whose king reaches one of the four centre squares wins.
+    pub struct KingOfTheHill;
+
+    impl KingOfTheHill {
+        const HILL: [(usize, usize); 4] = [(3, 3), (4, 3), (3, 4), (4, 4)];
+
+        fn distance_to_hill(position: &Position) -> usize {
+            return Self::HILL.iter()
+                .map(|(column, row)| position.column.abs_diff(*column).max(position.row.abs_diff(*row)))
+                .min()
+                .unwrap_or(0);
+        }
+    }
+
+    impl Variant for KingOfTheHill {
+        fn name(&self) -> &'static str {
+            return "King of the Hill";
+        }
+
+        fn uci_name(&self) -> &'static str {
+            return "kingofthehill";
+        }
+
+        fn outcome(&self, game: &Game) -> Option<Outcome> {
+            for (column, row) in Self::HILL.iter() {
+                match game.get_piece_from_position(&Position::new(*column, *row)) {
+                    Some((Piece::King, color)) => return Some(Outcome::Win(color)),
+                    _ => ()
+                }
+            }
+            return None;
+        }
+
+        // A lone king can still walk to the hill.
+        fn dead_material_draws(&self) -> bool {
+            return false;
+        }
+
+        fn evaluate(&self, game: &Game) -> i32 {
+            let mut score = 0;
+
+            for row in 0..game.board.num_rows() {
+                for column in 0..game.board.num_columns() {
+                    let position = Position::new(column, row);
+                    match game.get_piece_from_position(&position) {
+                        Some((Piece::King, color)) => {
+                            let bonus = KING_CENTER_BONUS[Self::distance_to_hill(&position).min(KING_CENTER_BONUS.len() - 1)];
+                            score += if color == Color::White { bonus } else { -bonus };
+                        },
+                        _ => ()
+                    }
+                }
+            }
+
+            return score;
+        }
+    }
+
+    //
+    // Captured pieces go into the pocket of the capturer, a promoted piece as a pawn. Moving
+    // a piece from the pocket to an empty square is a move of its own, pawns can not be
+    // dropped on the first or last rank.
+    pub struct Crazyhouse;
+
+    impl Variant for Crazyhouse {
+        fn name(&self) -> &'static str {
+            return "Crazyhouse";
+        }
+
+        fn uci_name(&self) -> &'static str {
+            return "crazyhouse";
+        }
+
+        fn special_moves(&self, game: &Game) -> Vec<ChessMove> {
+            let mut moves = vec![];
+            let turn = game.get_turn();
+            let pocket = game.pockets[color_index(&turn)];
+            let last_row = game.board.num_rows() - 1;
+
+            for piece in [Piece::Pawn, Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen] {
+                if pocket[Game::pocket_index(&piece)] == 0 {
+                    continue;
+                }
+
+                for row in 0..game.board.num_rows() {
+                    if piece == Piece::Pawn && (row == 0 || row == last_row) {
+                        continue;
+                    }
+                    for column in 0..game.board.num_columns() {
+                        let square = Position::new(column, row);
+                        if game.get_piece_from_position(&square).is_none() {
+                            moves.push(ChessMove::new(square, square, piece, turn.clone(), None, MoveType::Drop(piece)));
+                        }
+                    }
+                }
+            }
+
+            return moves;
+        }
+
+        fn after_move(&self, game: &mut Game, chess_move: &ChessMove) {
+            let side = color_index(&chess_move.color);
+
+            // The captured piece changes sides, a promoted one turns back into a pawn.
+            let captured = match chess_move.move_type {
+                MoveType::EnPassant(take_position) => Some((Piece::Pawn, take_position)),
+                _ => chess_move.piece_at_position.map(|piece| (piece, chess_move.to))
+            };
+            match captured {
+                Some((piece, square)) => {
+                    let piece = match game.promoted.iter().position(|promoted| *promoted == square) {
+                        Some(index) => {
+                            game.promoted.remove(index);
+                            Piece::Pawn
+                        },
+                        None => piece
+                    };
+                    game.pockets[side][Game::pocket_index(&piece)] += 1;
+                },
+                None => ()
+            }
+
+            match chess_move.move_type {
+                MoveType::Drop(piece) => {
+                    let count = &mut game.pockets[side][Game::pocket_index(&piece)];
+                    *count = count.saturating_sub(1);
+                },
+                MoveType::Promote(_) => game.promoted.push(chess_move.to),
+                _ => match game.promoted.iter().position(|promoted| *promoted == chess_move.from) {
+                    Some(index) => game.promoted[index] = chess_move.to,
+                    None => ()
+                }
+            }
+        }
+
+        // Material never leaves the game.
+        fn dead_material_draws(&self) -> bool {
+            return false;
+        }
+
+        fn uses_pockets(&self) -> bool {
+            return true;
+        }
+
+        fn evaluate(&self, game: &Game) -> i32 {
+            let value = |side: usize| -> i32 {
+                game.pockets[side].iter().zip(POCKET_VALUES.iter()).map(|(count, value)| *count as i32 * value).sum()
+            };
+            return value(0) - value(1);
+        }
+    }
+
+    //
+    // A capture explodes the capturing piece and every piece but pawns next to the square.
+    // Kings can not capture and may stand next to each other, a king touching the other one
+    // can not be checked since taking it would blow up both. Exploding the king wins.
+    pub struct Atomic;
+
+    impl Atomic {
+        fn king_position(game: &Game, color: &Color) -> Option<Position> {
+            for row in 0..game.board.num_rows() {
+                for column in 0..game.board.num_columns() {
+                    let position = Position::new(column, row);
+                    match game.get_piece_from_position(&position) {
+                        Some((Piece::King, king_color)) if king_color == *color => return Some(position),
+                        _ => ()
+                    }
+                }
+            }
+            return None;
+        }
+    }
+
+    impl Variant for Atomic {
+        fn name(&self) -> &'static str {
+            return "Atomic";
+        }
+
+        fn uci_name(&self) -> &'static str {
+            return "atomic";
+        }
+
+        fn is_legal(&self, game: &Game, chess_move: &ChessMove) -> bool {
+            if chess_move.piece == Piece::King && chess_move.is_capture() {
+                return false;
+            }
+
+            let turn = game.get_turn();
+            let mut after = game.clone();
+            match after.move_piece(&turn, chess_move) {
+                Ok(_) => (),
+                Err(err) => println!("{}", err)
+            }
+
+            // Blowing up the other king wins even out of check, but not together with the own one.
+            return match (Self::king_position(&after, &turn), Self::king_position(&after, &Color::to_opposite(turn.clone()))) {
+                (None, _) => false,
+                (Some(_), None) => true,
+                (Some(_), Some(_)) => !self.is_check(&after, &turn)
+            }
+        }
+
+        fn is_check(&self, game: &Game, color: &Color) -> bool {
+            let kings = (Self::king_position(game, color), Self::king_position(game, &Color::to_opposite(color.clone())));
+            return match kings {
+                (Some(king), Some(other)) if king.column.abs_diff(other.column) <= 1 && king.row.abs_diff(other.row) <= 1 => false,
+                (Some(_), _) => game.is_king_attacked(color),
+                (None, _) => false
+            }
+        }
+
+        fn outcome(&self, game: &Game) -> Option<Outcome> {
+            if Self::king_position(game, &Color::White).is_none() {
+                return Some(Outcome::Win(Color::Black));
+            }
+            if Self::king_position(game, &Color::Black).is_none() {
+                return Some(Outcome::Win(Color::White));
+            }
+            return None;
+        }
+
+        fn explodes(&self) -> bool {
+            return true;
+        }
+    }
+
+    //
+    // Losing chess, a player who loses all pieces or can not move wins. Captures are
+    // compulsory, there is no check or castling and the king is a piece like any other.
+    pub struct Antichess;
+
+    impl Antichess {
+        fn has_pieces(game: &Game, color: &Color) -> bool {
+            for row in 0..game.board.num_rows() {
+                for column in 0..game.board.num_columns() {
+                    match game.get_piece_from_position(&Position::new(column, row)) {
+                        Some((_, piece_color)) if piece_color == *color => return true,
+                        _ => ()
+                    }
+                }
+            }
+            return false;
+        }
+    }
+
+    impl Variant for Antichess {
+        fn name(&self) -> &'static str {
+            return "Antichess";
+        }
+
+        fn uci_name(&self) -> &'static str {
+            return "antichess";
+        }
+
+        fn is_legal(&self, _game: &Game, _chess_move: &ChessMove) -> bool {
+            return true;
+        }
+
+        // Only captures when there are any.
+        fn legal_moves(&self, _game: &Game, moves: Vec<ChessMove>) -> Vec<ChessMove> {
+            if moves.iter().any(|chess_move| chess_move.is_capture()) {
+                return moves.into_iter().filter(|chess_move| chess_move.is_capture()).collect();
+            }
+            return moves;
+        }
+
+        fn is_check(&self, _game: &Game, _color: &Color) -> bool {
+            return false;
+        }
+
+        fn has_castling(&self) -> bool {
+            return false;
+        }
+
+        fn promotion_pieces(&self) -> &'static [Piece] {
+            return &ANTICHESS_PROMOTIONS;
+        }
+
+        fn outcome(&self, game: &Game) -> Option<Outcome> {
+            for color in [Color::White, Color::Black] {
+                if !Self::has_pieces(game, &color) {
+                    return Some(Outcome::Win(color));
+                }
+            }
+            return None;
+        }
+
+        fn no_moves_outcome(&self, game: &Game) -> Outcome {
+            return Outcome::Win(game.get_turn());
+        }
+
+        fn dead_material_draws(&self) -> bool {
+            return false;
+        }
+
+        fn evaluation_sign(&self) -> i32 {
+            return -1;
+        }
+    }
+
+    // Chess on a 10x8 board with an Archbishop and a Chancellor, pawns can promote to either.
+    pub struct Capablanca;
+
+    impl Variant for Capablanca {
+        fn name(&self) -> &'static str {
+            return "Capablanca";
+        }
+
+        fn uci_name(&self) -> &'static str {
+            return "capablanca";
+        }
+
+        fn start_placement(&self) -> &'static str {
+            return CAPABLANCA_PLACEMENT;
+        }
+
+        fn promotion_pieces(&self) -> &'static [Piece] {
+            return &CAPABLANCA_PROMOTIONS;
+        }
+    }
+
+    // Gardner's minichess on 5x5, the standard rules without castling or double steps.
+    pub struct Gardner;
+
+    impl Variant for Gardner {
+        fn name(&self) -> &'static str {
+            return "Gardner";
+        }
+
+        fn uci_name(&self) -> &'static str {
+            return "gardner";
+        }
+
+        fn start_placement(&self) -> &'static str {
+            return GARDNER_PLACEMENT;
+        }
+
+        fn has_castling(&self) -> bool {
+            return false;
+        }
+    }
+
+    // Los Alamos chess on 6x6 without bishops, castling or double steps.
+    pub struct LosAlamos;
+
+    impl Variant for LosAlamos {
+        fn name(&self) -> &'static str {
+            return "Los Alamos";
+        }
+
+        fn uci_name(&self) -> &'static str {
+            return "losalamos";
+        }
+
+        fn start_placement(&self) -> &'static str {
+            return LOS_ALAMOS_PLACEMENT;
+        }
+
+        fn has_castling(&self) -> bool {
+            return false;
+        }
+
+        fn promotion_pieces(&self) -> &'static [Piece] {
+            return &LOS_ALAMOS_PROMOTIONS;
+        }
+    }
+
+    //
+    // Kriegspiel, the standard rules played without seeing the other side's pieces beyond
+    // the squares one's own pieces attack. An umpire announces illegal tries, captures and
+    // checks, see umpire.
+    pub struct Kriegspiel;
+
+    impl Variant for Kriegspiel {
+        fn name(&self) -> &'static str {
+            return "Kriegspiel";
+        }
+
+        fn uci_name(&self) -> &'static str {
+            return "kriegspiel";
+        }
+
+        fn hides_pieces(&self) -> bool {
+            return true;
+        }
+    }
+
+    pub fn color_index(color: &Color) -> usize {
+        return match color {
+            Color::White => 0,
+            Color::Black => 1
+        }
+    }
+
+    pub fn standard() -> Arc<dyn Variant> {
+        return Arc::new(Standard);
+    }
+
+    pub fn all() -> Vec<Arc<dyn Variant>> {
+        return vec![standard(), Arc::new(ThreeCheck), Arc::new(KingOfTheHill), Arc::new(Crazyhouse), Arc::new(Atomic), Arc::new(Antichess), Arc::new(Capablanca), Arc::new(Gardner), Arc::new(LosAlamos), Arc::new(Kriegspiel)];
+    }
+
+    // By its UCI or PGN name, in any case.
+    pub fn from_name(name: &str) -> Option<Arc<dyn Variant>> {
+        return all().into_iter().find(|variant| {
+            variant.uci_name().eq_ignore_ascii_case(name) || variant.name().eq_ignore_ascii_case(name)
+        });
+    }
+}
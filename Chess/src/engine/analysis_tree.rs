@@ -0,0 +1,327 @@
+pub mod analysis_tree {
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use crate::chess::chess_move::chess_move::ChessMove;
+    use crate::chess::color::color::Color;
+    use crate::chess::game::game::Game;
+    use crate::engine::evaluation::evaluation::{terminal_value, variant_evaluation, Evaluator, MaterialEvaluator};
+    use crate::engine::syzygy::syzygy::Tablebase;
+    use crate::engine::tree::game_tree::{GameTree, NodeId, ROOT};
+    use crate::engine::zobrist::zobrist;
+
+    //
+    // A game tree that is kept between moves. Playing a move keeps the subtree below it and
+    // every deepen call expands the leaves at the frontier by one ply. Leaves hold static
+    // evaluations from whites point of view. Once the tree is at its node budget, the
+    // branches that lose the most compared to the best line are cut back to leaves, so the
+    // promising lines keep getting deeper while the memory stays bounded.
+    #[derive(Clone)]
+    pub struct AnalysisTree {
+        tree: GameTree,
+        // Ply of the leaves the next deepen call expands.
+        depth: usize,
+        // Set once deepen finds nothing left to expand.
+        complete: bool,
+        // Set when leaves did not fit, the next deepen makes room first.
+        full: bool,
+        // Set when the leaves at depth that did not fit get a second call.
+        retrying: bool,
+        node_budget: usize,
+        evaluator: Arc<dyn Evaluator>,
+        tablebase: Option<Arc<Tablebase>>
+    }
+
+    // A node with how much worse than the best line it is, summed from the root.
+    type Candidate = (i32, NodeId);
+
+    impl AnalysisTree {
+        pub fn new(game: Game, node_budget: usize) -> AnalysisTree {
+            return AnalysisTree::with_evaluator(game, node_budget, Arc::new(MaterialEvaluator::new()));
+        }
+
+        pub fn with_evaluator(game: Game, node_budget: usize, evaluator: Arc<dyn Evaluator>) -> AnalysisTree {
+            let mut analysis = AnalysisTree {
+                tree: GameTree::with_transpositions(game.clone()),
+                depth: 0,
+                complete: false,
+                full: false,
+                retrying: false,
+                node_budget: node_budget.max(1),
+                evaluator,
+                tablebase: None
+            };
+            analysis.reset(game);
+            return analysis;
+        }
+
+        pub fn depth(&self) -> usize {
+            return self.depth;
+        }
+
+        // Whether every line ends in a finished game or a tablebase position.
+        pub fn is_complete(&self) -> bool {
+            return self.complete;
+        }
+
+        pub fn node_count(&self) -> usize {
+            return self.tree.node_count();
+        }
+
+        pub fn set_tablebase(&mut self, tablebase: Arc<Tablebase>) {
+            self.tablebase = Some(tablebase);
+        }
+
+        fn reset(&mut self, game: Game) {
+            let value = evaluate_leaf(&game, self.evaluator.as_ref(), self.tablebase.as_deref());
+            self.tree = GameTree::with_transpositions(game);
+            self.tree.set_score(ROOT, value);
+            self.depth = 0;
+            self.complete = false;
+            self.full = false;
+            self.retrying = false;
+        }
+
+        //
+        // Moves the root to the child for chess_move and keeps its subtree. When the move
+        // is not in the tree, because it was evicted or never expanded, the tree starts
+        // over from the new position.
+        pub fn play_move(&mut self, chess_move: &ChessMove) {
+            match self.tree.advance(chess_move) {
+                Some(subtree) if !subtree.is_leaf(ROOT) => {
+                    self.tree = subtree;
+                    self.depth = self.depth.saturating_sub(1);
+                },
+                _ => match GameTree::play(self.tree.game(), chess_move) {
+                    Some(after_move) => self.reset(after_move),
+                    // Not a legal move here, the tree stays as it is.
+                    None => ()
+                }
+            }
+        }
+
+        // Follows the game to its current position, keeping the tree when it is one move on.
+        pub fn set_position(&mut self, game: &Game) {
+            let hash = zobrist::hash(game);
+            if zobrist::hash(self.tree.game()) == hash {
+                return;
+            }
+
+            let mut played = None;
+            for edge in self.tree.children(ROOT) {
+                match GameTree::play(self.tree.game(), &edge.chess_move) {
+                    Some(after_move) if zobrist::hash(&after_move) == hash => {
+                        played = Some(edge.chess_move.clone());
+                        break;
+                    },
+                    _ => ()
+                }
+            }
+
+            match played {
+                Some(chess_move) => self.play_move(&chess_move),
+                None => self.reset(game.clone())
+            }
+        }
+
+        //
+        // Expands the frontier by one ply, most promising leaves first, and does not go over
+        // the budget. Leaves that did not fit get one more call after the worst branches
+        // were cut, then they are left behind as evicted. Returns false when there is
+        // nothing left to expand.
+        pub fn deepen(&mut self) -> bool {
+            if self.full {
+                self.evict(self.node_budget * 3 / 4);
+            }
+
+            let (mut frontier, _) = self.candidates();
+            if frontier.is_empty() {
+                self.complete = true;
+                return false;
+            }
+
+            frontier.sort_by(|a, b| a.0.cmp(&b.0));
+
+            self.full = false;
+            for (_, node, game) in frontier.iter() {
+                if self.node_count() >= self.node_budget {
+                    self.full = true;
+                    break;
+                }
+                if !self.expand(*node, game) {
+                    self.full = true;
+                }
+            }
+
+            let white_to_move = self.tree.game().get_turn() == Color::White;
+            back_up(&mut self.tree, ROOT, white_to_move, &mut HashSet::new());
+
+            if self.full && !self.retrying {
+                self.retrying = true;
+            } else {
+                self.retrying = false;
+                self.depth += 1;
+            }
+            return true;
+        }
+
+        // Returns false when the children of the leaf do not fit in the budget, the root always fits.
+        fn expand(&mut self, node: NodeId, game: &Game) -> bool {
+            // Tablebase leaves already hold the exact result.
+            match &self.tablebase {
+                Some(tablebase) if tablebase.can_probe(game) => return true,
+                _ => ()
+            }
+
+            let all_moves = game.get_all_legal_moves();
+            if all_moves.is_empty() {
+                self.tree.set_score(node, terminal_value(game));
+                return true;
+            }
+            if node != ROOT && self.node_count() + all_moves.len() > self.node_budget {
+                return false;
+            }
+
+            for (child, after_move) in self.tree.expand(node, game, &all_moves) {
+                let value = evaluate_leaf(&after_move, self.evaluator.as_ref(), self.tablebase.as_deref());
+                self.tree.set_score(child, value);
+            }
+            return true;
+        }
+
+        // The best root move with its backed up value, from whites point of view.
+        pub fn best_move(&self) -> Option<(ChessMove, i32)> {
+            let white_to_move = self.tree.game().get_turn() == Color::White;
+            let mut best: Option<(ChessMove, i32)> = None;
+
+            for edge in self.tree.children(ROOT) {
+                let value = self.tree.node(edge.child).score;
+                let better = match &best {
+                    None => true,
+                    Some((_, best_value)) => if white_to_move { value > *best_value } else { value < *best_value }
+                };
+
+                if better {
+                    best = Some((edge.chess_move.clone(), value));
+                }
+            }
+
+            return best;
+        }
+
+        //
+        // Cuts the worst branches back to leaves until the tree has at most target nodes.
+        // Branches are cut an eighth at a time since the size is only known after compacting.
+        fn evict(&mut self, target: usize) {
+            loop {
+                if self.node_count() <= target {
+                    return;
+                }
+
+                let (_, mut trees) = self.candidates();
+                if trees.is_empty() {
+                    return;
+                }
+
+                trees.sort_by(|a, b| b.0.cmp(&a.0));
+                for (_, node) in trees.iter().take((trees.len() / 8).max(1)) {
+                    self.tree.prune(*node);
+                }
+                self.tree.compact();
+            }
+        }
+
+        // The frontier leaves with their positions, and the inner nodes below the root, with their regret.
+        fn candidates(&self) -> (Vec<(i32, NodeId, Game)>, Vec<Candidate>) {
+            let mut frontier = vec![];
+            let mut trees = vec![];
+            let mut visited = HashSet::new();
+            collect(&self.tree, ROOT, self.tree.game(), 0, self.depth, 0, &mut visited, &mut frontier, &mut trees);
+            return (frontier, trees);
+        }
+    }
+
+    fn collect(tree: &GameTree, node: NodeId, game: &Game, ply: usize, depth: usize, regret: i32, visited: &mut HashSet<NodeId>, frontier: &mut Vec<(i32, NodeId, Game)>, trees: &mut Vec<Candidate>) {
+        // A shared node is only collected through the first line that reaches it.
+        if !visited.insert(node) {
+            return;
+        }
+
+        if tree.is_leaf(node) {
+            if ply == depth {
+                frontier.push((regret, node, game.clone()));
+            }
+            return;
+        }
+
+        if ply > 0 {
+            trees.push((regret, node));
+        }
+
+        let best = tree.node(node).score;
+        for edge in tree.children(node) {
+            match GameTree::play(game, &edge.chess_move) {
+                Some(after_move) => {
+                    let child_regret = regret.saturating_add((best - tree.node(edge.child).score).abs());
+                    collect(tree, edge.child, &after_move, ply + 1, depth, child_regret, visited, frontier, trees);
+                },
+                None => ()
+            }
+        }
+    }
+
+    // Minimax of the leaf values into the inner nodes, every shared node is done once.
+    fn back_up(tree: &mut GameTree, node: NodeId, white_to_move: bool, done: &mut HashSet<NodeId>) -> i32 {
+        if tree.is_leaf(node) || done.contains(&node) {
+            return tree.node(node).score;
+        }
+
+        let children: Vec<NodeId> = tree.children(node).iter().map(|edge| edge.child).collect();
+        let mut best = if white_to_move { i32::MIN } else { i32::MAX };
+        for child in children {
+            let value = back_up(tree, child, !white_to_move, done);
+            best = if white_to_move { best.max(value) } else { best.min(value) };
+        }
+
+        tree.set_score(node, best);
+        done.insert(node);
+        return best;
+    }
+
+    fn evaluate_leaf(game: &Game, evaluator: &dyn Evaluator, tablebase: Option<&Tablebase>) -> i32 {
+        match tablebase {
+            Some(tablebase) if tablebase.can_probe(game) => match tablebase.probe_wdl(game) {
+                Ok(wdl) => return wdl.to_value(&game.get_turn()),
+                // Without the table the leaf is evaluated like any other.
+                Err(_) => ()
+            },
+            _ => ()
+        }
+
+        return variant_evaluation(evaluator, game);
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::engine::uci::uci;
+
+        #[test]
+        fn stays_within_the_budget() {
+            let game = uci::game_from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+            let mut analysis = AnalysisTree::new(game.clone(), 300);
+
+            for _ in 0..6 {
+                analysis.deepen();
+                assert!(analysis.node_count() <= 300, "{} nodes", analysis.node_count());
+            }
+
+            let (best_move, _) = analysis.best_move().unwrap();
+            assert!(game.get_all_legal_moves().contains(&best_move));
+
+            // The subtree below the move is kept.
+            let depth = analysis.depth();
+            analysis.set_position(&GameTree::play(&game, &best_move).unwrap());
+            assert_eq!(analysis.depth(), depth - 1);
+        }
+    }
+}
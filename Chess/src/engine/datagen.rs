@@ -0,0 +1,240 @@
+pub mod datagen {
+    use std::collections::HashMap;
+    use crate::chess::chess_move::chess_move::{ChessMove, MoveType};
+    use crate::chess::color::color::Color;
+    use crate::chess::game::game::Game;
+    use crate::chess::piece::piece::Piece;
+    use crate::chess::position::position::Position;
+    use crate::engine::match_runner::match_runner::{self, GameResult, TimeControl};
+    use crate::engine::search::search::Search;
+    use crate::engine::skill::skill::SkillRandom;
+    use crate::engine::tree::game_tree::GameTree;
+    use crate::engine::uci::uci;
+    use crate::engine::zobrist::zobrist;
+
+    pub const BINARY_MAGIC: [u8; 4] = *b"CGTD";
+    pub const BINARY_VERSION: u8 = 1;
+
+    // Scores beyond this are mates or tablebase wins and are not recorded.
+    const DECISIVE_SCORE: i32 = 10_000;
+
+    // Tries at finding random opening moves that do not end the game.
+    const OPENING_ATTEMPTS: usize = 16;
+
+    const STARTPOS: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    #[derive(Debug, Clone)]
+    pub struct DataGenOptions {
+        pub games: usize,
+        pub control: TimeControl,
+        // Random moves played from the opening before the engines take over.
+        pub random_plies: usize,
+        // Start positions, the standard one when empty.
+        pub openings: Vec<String>,
+        pub max_plies: usize,
+        pub seed: u64,
+        // This process plays the games whose number modulo shards is shard.
+        pub shard: usize,
+        pub shards: usize
+    }
+
+    impl DataGenOptions {
+        pub fn new(games: usize, control: TimeControl) -> DataGenOptions {
+            return DataGenOptions {
+                games,
+                control,
+                random_plies: 8,
+                openings: vec![],
+                max_plies: 300,
+                seed: 1,
+                shard: 0,
+                shards: 1
+            }
+        }
+    }
+
+    // A quiet position from a self-play game, score and result are from whites point of view.
+    #[derive(Clone)]
+    pub struct TrainingPosition {
+        pub game: Game,
+        pub score: i32,
+        pub result: GameResult
+    }
+
+    impl TrainingPosition {
+        fn result_value(&self) -> f64 {
+            return match self.result {
+                GameResult::WhiteWins => 1.0,
+                GameResult::Draw => 0.5,
+                GameResult::BlackWins => 0.0
+            }
+        }
+
+        // One line of the text format, "fen | score | result" with the result as 1.0, 0.5 or 0.0.
+        pub fn to_text(&self) -> String {
+            return format!("{} | {} | {:.1}", uci::fen_from_game(&self.game), self.score, self.result_value());
+        }
+
+        //
+        // One record of the binary format. The occupied squares as a u64 with bit row * 8 + column,
+        // row 0 being the eighth rank, then the piece codes of those squares two to a byte, low
+        // nibble first. Codes are 1 to 6 as in Piece with 8 added for black. A flag byte holds the side to move in bit 0 and the castling rights KQkq in
+        // bits 1 to 4, then the en passant square or 255, the half move clock, the score as i16 and
+        // the result as 0 for a black win, 1 for a draw and 2 for a white win. Little endian.
+        pub fn write_binary(&self, data: &mut Vec<u8>) {
+            let mut occupied = 0_u64;
+            let mut codes = vec![];
+            for row in 0..8 {
+                for column in 0..8 {
+                    match self.game.get_piece_from_position(&Position::new(column, row)) {
+                        Some((piece, color)) => {
+                            occupied |= 1 << (row * 8 + column);
+                            codes.push(piece.to_u8() | if color == Color::Black { 8 } else { 0 });
+                        },
+                        None => ()
+                    }
+                }
+            }
+
+            data.extend_from_slice(&occupied.to_le_bytes());
+            for pair in codes.chunks(2) {
+                data.push(pair[0] | pair.get(1).map_or(0, |code| code << 4));
+            }
+
+            let mut flags = if self.game.get_turn() == Color::White { 0 } else { 1 };
+            for (i, right) in self.game.castling_rights().iter().enumerate() {
+                if *right {
+                    flags |= 2 << i;
+                }
+            }
+            data.push(flags);
+
+            let en_passant = match self.game.log.get_last_move() {
+                Some(last) if last.piece == Piece::Pawn && last.from.row.abs_diff(last.to.row) == 2 =>
+                    (((last.from.row + last.to.row) / 2) * 8 + last.from.column) as u8,
+                _ => 255
+            };
+            data.push(en_passant);
+            data.push(self.game.log.half_move_clock().min(255) as u8);
+            data.extend_from_slice(&(self.score.clamp(i16::MIN as i32, i16::MAX as i32) as i16).to_le_bytes());
+            data.push(match self.result {
+                GameResult::BlackWins => 0,
+                GameResult::Draw => 1,
+                GameResult::WhiteWins => 2
+            });
+        }
+    }
+
+    // Header of a binary file, the records follow.
+    pub fn binary_header() -> Vec<u8> {
+        let mut data = BINARY_MAGIC.to_vec();
+        data.push(BINARY_VERSION);
+        return data;
+    }
+
+    // The same seed and game number always give the same game, however the games are sharded.
+    fn game_seed(seed: u64, number: usize) -> u64 {
+        // Splitmix64 finaliser.
+        let mut x = seed.wrapping_add((number as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        return x ^ (x >> 31);
+    }
+
+    //
+    // Plays the games of this shard, every game starts with random moves from an opening
+    // and is then played by search against itself. on_game gets the number of the game,
+    // its result and the quiet positions it recorded.
+    pub fn generate(search: &Search, options: &DataGenOptions, on_game: &mut dyn FnMut(usize, GameResult, &Vec<TrainingPosition>)) {
+        let openings = if options.openings.is_empty() { vec![STARTPOS.to_string()] } else { options.openings.clone() };
+        let shards = options.shards.max(1);
+
+        for number in (0..options.games).filter(|number| number % shards == options.shard) {
+            let mut random = SkillRandom::with_seed(game_seed(options.seed, number));
+            let opening = &openings[(random.next() % openings.len() as u64) as usize];
+
+            let game = match random_opening(opening, options.random_plies, &mut random) {
+                Some(game) => game,
+                None => continue
+            };
+
+            let (result, positions) = play_game(search, game, options);
+            on_game(number, result, &positions);
+        }
+    }
+
+    fn random_opening(opening: &str, plies: usize, random: &mut SkillRandom) -> Option<Game> {
+        let start = uci::game_from_fen(opening)?;
+
+        for _ in 0..OPENING_ATTEMPTS {
+            let mut game = start.clone();
+            for _ in 0..plies {
+                let moves = game.get_all_legal_moves();
+                if moves.is_empty() {
+                    break;
+                }
+                game = GameTree::play(&game, &moves[(random.next() % moves.len() as u64) as usize])?;
+            }
+
+            if !game.get_all_legal_moves().is_empty() {
+                return Some(game);
+            }
+        }
+
+        return None;
+    }
+
+    fn play_game(search: &Search, mut game: Game, options: &DataGenOptions) -> (GameResult, Vec<TrainingPosition>) {
+        search.clear();
+        let limits = options.control.limits();
+        let mut seen: HashMap<u64, usize> = HashMap::new();
+        let mut positions = vec![];
+        let mut result = GameResult::Draw;
+
+        for _ in 0..options.max_plies {
+            let repetitions = seen.entry(zobrist::hash(&game)).or_insert(0);
+            *repetitions += 1;
+
+            match match_runner::adjudicate(&game, *repetitions) {
+                Some((game_result, _)) => {
+                    result = game_result;
+                    break;
+                },
+                None => ()
+            }
+
+            let search_result = search.run(&game, &limits, &mut |_info| ());
+            let chess_move = match search_result.best_move {
+                Some(chess_move) => chess_move,
+                None => break
+            };
+
+            // Positions in check or where the best move takes or promotes are not quiet.
+            let quiet = !game.is_check(&game.get_turn()) && !chess_move.is_capture() && !is_promotion(&chess_move);
+            if quiet && search_result.score.abs() < DECISIVE_SCORE {
+                positions.push(TrainingPosition {
+                    game: game.clone(),
+                    score: if game.get_turn() == Color::White { search_result.score } else { -search_result.score },
+                    result: GameResult::Draw
+                });
+            }
+
+            game = match GameTree::play(&game, &chess_move) {
+                Some(after_move) => after_move,
+                None => break
+            };
+        }
+
+        for position in positions.iter_mut() {
+            position.result = result;
+        }
+        return (result, positions);
+    }
+
+    fn is_promotion(chess_move: &ChessMove) -> bool {
+        return match chess_move.move_type {
+            MoveType::Promote(_) => true,
+            _ => false
+        }
+    }
+}
@@ -0,0 +1,983 @@
+pub mod endgame_table {
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::fs;
+    use std::io;
+    use std::path::{Path, PathBuf};
+    use crate::chess::chess_move::chess_move::{ChessMove, MoveType};
+    use crate::chess::color::color::Color;
+    use crate::chess::game::game::Game;
+    use crate::chess::piece::piece::Piece;
+    use crate::chess::piece_definition::piece_definition;
+    use crate::chess::position::position::Position;
+    use crate::engine::tree::game_tree::GameTree;
+
+    const MAGIC: [u8; 4] = [b'C', b'G', b'T', b'B'];
+    const VERSION: u8 = 1;
+    const EXTENSION: &str = "cgtb";
+
+    // Pieces next to the two kings a table can have.
+    const MAX_EXTRA_PIECES: usize = 2;
+
+    //
+    // Stored values: DRAW for draws and positions that were never reached, ILLEGAL for
+    // indices that are not a legal position and the plies to mate plus one for the rest.
+    const DRAW: u8 = 0;
+    const ILLEGAL: u8 = 255;
+
+    // King steps as (row, column), opposite steps are at i and 7 - i. A move mask has bit i set for step i.
+    const KING_STEPS: [(i32, i32); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+    const KNIGHT_JUMPS: [(i32, i32); 8] = [(-2, -1), (-2, 1), (-1, -2), (-1, 2), (1, -2), (1, 2), (2, -1), (2, 1)];
+    const ROOK_RAYS: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+    const BISHOP_RAYS: [(i32, i32); 4] = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
+
+    // Distance to mate in plies for the side to move.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Dtm {
+        Win(u32),
+        Draw,
+        Loss(u32)
+    }
+
+    impl Dtm {
+        // Orders results for the side to move, faster wins and slower losses are higher.
+        fn rank(self) -> i64 {
+            return match self {
+                Dtm::Win(plies) => 1000 - plies as i64,
+                Dtm::Draw => 0,
+                Dtm::Loss(plies) => -1000 + plies as i64
+            }
+        }
+
+        // The result one ply earlier, for the side that made the move.
+        fn before_move(self) -> Dtm {
+            return match self {
+                Dtm::Win(plies) => Dtm::Loss(plies + 1),
+                Dtm::Draw => Dtm::Draw,
+                Dtm::Loss(plies) => Dtm::Win(plies + 1)
+            }
+        }
+    }
+
+    impl fmt::Display for Dtm {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match *self {
+                Dtm::Win(plies) => write!(f, "Win, mate in {} plies", plies),
+                Dtm::Draw => write!(f, "Draw"),
+                Dtm::Loss(plies) => write!(f, "Loss, mated in {} plies", plies)
+            }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub enum EndgameTableError {
+        UnsupportedMaterial {
+            material: String
+        },
+        MissingTable {
+            material: String
+        },
+        IllegalPosition,
+        Format {
+            path: PathBuf,
+            message: String
+        },
+        Io {
+            path: PathBuf,
+            message: String
+        }
+    }
+
+    impl fmt::Display for EndgameTableError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                EndgameTableError::UnsupportedMaterial { material } =>
+                    write!(f, "Endgame tables can not be built for: {}", material),
+                EndgameTableError::MissingTable { material } =>
+                    write!(f, "Missing endgame table for: {}", material),
+                EndgameTableError::IllegalPosition =>
+                    write!(f, "The position is not legal"),
+                EndgameTableError::Format { path, message } =>
+                    write!(f, "Invalid endgame table file {}: {}", path.display(), message),
+                EndgameTableError::Io { path, message } =>
+                    write!(f, "Couldnt access endgame table file {}: {}", path.display(), message)
+            }
+        }
+    }
+
+    impl EndgameTableError {
+        fn format(path: &Path, message: &str) -> EndgameTableError {
+            return EndgameTableError::Format {
+                path: path.to_path_buf(),
+                message: message.to_string()
+            }
+        }
+
+        fn io(path: &Path, err: io::Error) -> EndgameTableError {
+            return EndgameTableError::Io {
+                path: path.to_path_buf(),
+                message: err.to_string()
+            }
+        }
+    }
+
+    fn piece_letter(piece: Piece) -> char {
+        return piece_definition::definition(&piece).letter;
+    }
+
+    // Pieces in the order they appear in table names.
+    fn name_order(piece: Piece) -> u8 {
+        return match piece {
+            Piece::King => 0,
+            Piece::Queen => 1,
+            Piece::Rook => 2,
+            Piece::Bishop => 3,
+            Piece::Knight => 4,
+            Piece::Pawn => 5,
+            _ => panic!("Endgame tables only hold standard pieces.")
+        }
+    }
+
+    //
+    // The pieces of the strong side next to its king, the other side only has its king.
+    // Tables are built with the strong side as white, black positions are mirrored.
+    #[derive(Debug, Clone, PartialEq)]
+    struct Material {
+        pieces: Vec<Piece>
+    }
+
+    impl Material {
+        fn new(mut pieces: Vec<Piece>) -> Material {
+            pieces.sort_by_key(|piece| name_order(*piece));
+            return Material { pieces }
+        }
+
+        // Names like KQK or KBNK.
+        fn from_name(name: &str) -> Option<Material> {
+            let letters: Vec<char> = name.trim().to_uppercase().chars().collect();
+            if letters.len() < 2 || letters[0] != 'K' || letters[letters.len() - 1] != 'K' {
+                return None;
+            }
+
+            let mut pieces = vec![];
+            for letter in letters[1..letters.len() - 1].iter() {
+                pieces.push(match letter {
+                    'Q' => Piece::Queen,
+                    'R' => Piece::Rook,
+                    'B' => Piece::Bishop,
+                    'N' => Piece::Knight,
+                    'P' => Piece::Pawn,
+                    _ => return None
+                });
+            }
+
+            if pieces.len() > MAX_EXTRA_PIECES {
+                return None;
+            }
+
+            return Some(Material::new(pieces));
+        }
+
+        fn name(&self) -> String {
+            let mut name = String::from("K");
+            for piece in self.pieces.iter() {
+                name.push(piece_letter(*piece));
+            }
+            name.push('K');
+            return name;
+        }
+
+        fn has_pawns(&self) -> bool {
+            return self.pieces.iter().any(|piece| *piece == Piece::Pawn);
+        }
+
+        // Nothing or a single minor piece can not mate, those tables are all draws.
+        fn is_insufficient(&self) -> bool {
+            return match self.pieces.as_slice() {
+                [] | [Piece::Bishop] | [Piece::Knight] => true,
+                _ => false
+            }
+        }
+
+        // The material after the pawn at index promotes.
+        fn promoted(&self, index: usize, piece: Piece) -> Material {
+            let mut pieces = self.pieces.clone();
+            pieces[index] = piece;
+            return Material::new(pieces);
+        }
+    }
+
+    fn square(row: i32, column: i32) -> Option<usize> {
+        if row < 0 || row > 7 || column < 0 || column > 7 {
+            return None;
+        }
+
+        return Some((row * 8 + column) as usize);
+    }
+
+    fn row_of(square: usize) -> i32 {
+        return (square / 8) as i32;
+    }
+
+    fn column_of(square: usize) -> i32 {
+        return (square % 8) as i32;
+    }
+
+    fn kings_touch(a: usize, b: usize) -> bool {
+        return (row_of(a) - row_of(b)).abs() <= 1 && (column_of(a) - column_of(b)).abs() <= 1;
+    }
+
+    //
+    // Board symmetries, bit 4 transposes, bit 2 flips the rows and bit 1 the columns.
+    // Steps are mapped the same way without the translation.
+    fn transform_step(step: (i32, i32), symmetry: u8) -> (i32, i32) {
+        let (mut row, mut column) = if symmetry & 4 != 0 { (step.1, step.0) } else { step };
+        if symmetry & 2 != 0 {
+            row = -row;
+        }
+        if symmetry & 1 != 0 {
+            column = -column;
+        }
+        return (row, column);
+    }
+
+    fn transform_square(square: usize, symmetry: u8) -> usize {
+        let (mut row, mut column) = if symmetry & 4 != 0 { (column_of(square), row_of(square)) } else { (row_of(square), column_of(square)) };
+        if symmetry & 2 != 0 {
+            row = 7 - row;
+        }
+        if symmetry & 1 != 0 {
+            column = 7 - column;
+        }
+        return (row * 8 + column) as usize;
+    }
+
+    fn step_index(step: (i32, i32)) -> Option<usize> {
+        return KING_STEPS.iter().position(|king_step| *king_step == step);
+    }
+
+    //
+    // Maps positions to table indices. Squares are listed as white king, the pieces in
+    // material order and the black king, with a8 = 0 and h1 = 63. The white king is moved
+    // into the a1-d1-d4 triangle by the board symmetries, or onto the a-d files when
+    // pawns fix the direction, and the smallest index among the symmetric boards is used.
+    struct Layout {
+        material: Material,
+        symmetries: Vec<u8>,
+        // Table slot of every square for the white king.
+        king_slots: [Option<usize>; 64],
+        slot_squares: Vec<usize>,
+        size: usize
+    }
+
+    impl Layout {
+        fn new(material: Material) -> Layout {
+            let has_pawns = material.has_pawns();
+            let symmetries = if has_pawns { vec![0, 1] } else { (0..8).collect() };
+
+            let mut king_slots = [None; 64];
+            let mut slot_squares = vec![];
+            for square in 0..64 {
+                let (row, column) = (row_of(square), column_of(square));
+                let in_region = if has_pawns {
+                    column <= 3
+                } else {
+                    column <= 3 && row >= 4 && 7 - row <= column
+                };
+
+                if in_region {
+                    king_slots[square] = Some(slot_squares.len());
+                    slot_squares.push(square);
+                }
+            }
+
+            let size = slot_squares.len() * 64_usize.pow(material.pieces.len() as u32 + 1);
+            return Layout {
+                material,
+                symmetries,
+                king_slots,
+                slot_squares,
+                size
+            }
+        }
+
+        fn encode(&self, squares: &[usize]) -> Option<usize> {
+            let mut index = self.king_slots[squares[0]]?;
+            for square in squares[1..].iter() {
+                index = index * 64 + square;
+            }
+            return Some(index);
+        }
+
+        fn decode(&self, mut index: usize) -> Vec<usize> {
+            let mut squares = vec![0; self.material.pieces.len() + 2];
+            for i in (1..squares.len()).rev() {
+                squares[i] = index % 64;
+                index /= 64;
+            }
+            squares[0] = self.slot_squares[index];
+            return squares;
+        }
+
+        // The index of the position and every symmetry that maps the squares onto it.
+        fn canonical(&self, squares: &[usize]) -> (usize, Vec<u8>) {
+            let mut best = usize::MAX;
+            let mut symmetries = vec![];
+
+            for symmetry in self.symmetries.iter() {
+                let mapped: Vec<usize> = squares.iter().map(|square| transform_square(*square, *symmetry)).collect();
+                match self.encode(&mapped) {
+                    Some(index) if index < best => {
+                        best = index;
+                        symmetries = vec![*symmetry];
+                    },
+                    Some(index) if index == best => symmetries.push(*symmetry),
+                    _ => ()
+                }
+            }
+
+            return (best, symmetries);
+        }
+
+        // Distinct squares, kings apart and no pawns on the first or last rank.
+        fn is_valid(&self, squares: &[usize]) -> bool {
+            for i in 0..squares.len() {
+                for j in i + 1..squares.len() {
+                    if squares[i] == squares[j] {
+                        return false;
+                    }
+                }
+            }
+
+            for (piece, square) in self.material.pieces.iter().zip(squares[1..].iter()) {
+                if *piece == Piece::Pawn && (row_of(*square) == 0 || row_of(*square) == 7) {
+                    return false;
+                }
+            }
+
+            return !kings_touch(squares[0], squares[squares.len() - 1]);
+        }
+
+        fn to_game(&self, squares: &[usize], white_to_move: bool) -> Game {
+            let mut game = Game::new();
+            game.turn = if white_to_move { 0 } else { 1 };
+
+            let place = |game: &mut Game, square: usize, value: u8| {
+                let _ = game.board.set(row_of(square) as usize, column_of(square) as usize, value);
+            };
+            place(&mut game, squares[0], Piece::King + Color::White);
+            for (piece, square) in self.material.pieces.iter().zip(squares[1..].iter()) {
+                place(&mut game, *square, *piece + Color::White);
+            }
+            place(&mut game, squares[squares.len() - 1], Piece::King + Color::Black);
+
+            return game;
+        }
+
+        //
+        // Positions with white to move that reach squares with one white move. Captures and
+        // promotions lead out of the table and are not taken back.
+        fn white_unmoves(&self, squares: &[usize]) -> Vec<Vec<usize>> {
+            let occupied = |square: usize| squares.contains(&square);
+            let mut positions = vec![];
+
+            for i in 0..squares.len() - 1 {
+                let piece = if i == 0 { Piece::King } else { self.material.pieces[i - 1] };
+                let (row, column) = (row_of(squares[i]), column_of(squares[i]));
+                let mut from_squares = vec![];
+
+                let mut add_steps = |steps: &[(i32, i32)], slide: bool| {
+                    for (row_step, column_step) in steps.iter() {
+                        let mut distance = 1;
+                        while let Some(from) = square(row + row_step * distance, column + column_step * distance) {
+                            if occupied(from) {
+                                break;
+                            }
+                            from_squares.push(from);
+                            if !slide {
+                                break;
+                            }
+                            distance += 1;
+                        }
+                    }
+                };
+
+                match piece {
+                    Piece::King => add_steps(&KING_STEPS, false),
+                    Piece::Knight => add_steps(&KNIGHT_JUMPS, false),
+                    Piece::Bishop => add_steps(&BISHOP_RAYS, true),
+                    Piece::Rook => add_steps(&ROOK_RAYS, true),
+                    Piece::Queen => {
+                        add_steps(&ROOK_RAYS, true);
+                        add_steps(&BISHOP_RAYS, true);
+                    },
+                    Piece::Pawn => {
+                        // White pawns move towards row 0 and start on row 6.
+                        match square(row + 1, column) {
+                            Some(from) if row + 1 <= 6 && !occupied(from) => {
+                                from_squares.push(from);
+                                match square(row + 2, column) {
+                                    Some(start) if row == 4 && !occupied(start) => from_squares.push(start),
+                                    _ => ()
+                                }
+                            },
+                            _ => ()
+                        }
+                    },
+                    _ => panic!("Endgame tables only hold standard pieces.")
+                }
+
+                for from in from_squares {
+                    let mut position = squares.to_vec();
+                    position[i] = from;
+                    positions.push(position);
+                }
+            }
+
+            return positions;
+        }
+
+        // Positions with black to move that reach squares with a king step, with the index of the step.
+        fn black_unmoves(&self, squares: &[usize]) -> Vec<(Vec<usize>, usize)> {
+            let king = squares.len() - 1;
+            let mut positions = vec![];
+
+            for (i, (row_step, column_step)) in KING_STEPS.iter().enumerate() {
+                match square(row_of(squares[king]) + row_step, column_of(squares[king]) + column_step) {
+                    Some(from) if !squares.contains(&from) => {
+                        let mut position = squares.to_vec();
+                        position[king] = from;
+                        positions.push((position, 7 - i));
+                    },
+                    _ => ()
+                }
+            }
+
+            return positions;
+        }
+    }
+
+    //
+    // Distance to mate for every position of one material signature, with the strong side
+    // as white. Built by retrograde analysis: starting from the positions where black is
+    // mated, white wins are found by taking back white moves and black losses by taking
+    // back black moves into positions where every black move has been shown to lose.
+    pub struct EndgameTable {
+        material: Material,
+        layout: Layout,
+        white_to_move: Vec<u8>,
+        black_to_move: Vec<u8>
+    }
+
+    impl EndgameTable {
+        // Tables for the promotions have to be in tables already.
+        fn generate(material: Material, tables: &EndgameTables) -> EndgameTable {
+            let layout = Layout::new(material.clone());
+            let mut white_to_move = vec![DRAW; layout.size];
+            let mut black_to_move = vec![DRAW; layout.size];
+            // King steps black can play and the ones shown to lose.
+            let mut legal_steps = vec![0_u8; layout.size];
+            let mut losing_steps = vec![0_u8; layout.size];
+            // White wins by promoting, by their length.
+            let mut promotions: HashMap<u32, Vec<usize>> = HashMap::new();
+            let mut frontier = vec![];
+
+            for index in 0..layout.size {
+                let squares = layout.decode(index);
+                if !layout.is_valid(&squares) || layout.canonical(&squares).0 != index {
+                    white_to_move[index] = ILLEGAL;
+                    black_to_move[index] = ILLEGAL;
+                    continue;
+                }
+
+                let game = layout.to_game(&squares, true);
+                if game.is_check(&Color::Black) {
+                    white_to_move[index] = ILLEGAL;
+                } else if material.has_pawns() {
+                    match EndgameTable::fastest_promotion(&game, tables) {
+                        Some(plies) => promotions.entry(plies).or_insert(vec![]).push(index),
+                        None => ()
+                    }
+                }
+
+                let game = layout.to_game(&squares, false);
+                if game.is_check(&Color::White) {
+                    black_to_move[index] = ILLEGAL;
+                    continue;
+                }
+
+                let moves = game.get_all_legal_moves();
+                if moves.is_empty() {
+                    if game.is_check(&Color::Black) {
+                        black_to_move[index] = 1;
+                        frontier.push(index);
+                    }
+                    continue;
+                }
+
+                for chess_move in moves.iter() {
+                    let step = (chess_move.to.row as i32 - chess_move.from.row as i32, chess_move.to.column as i32 - chess_move.from.column as i32);
+                    match step_index(step) {
+                        Some(i) => legal_steps[index] |= 1 << i,
+                        None => ()
+                    }
+                }
+            }
+
+            let last_promotion = promotions.keys().max().cloned().unwrap_or(0);
+            let mut plies: u32 = 0;
+
+            while !frontier.is_empty() || plies < last_promotion {
+                // White to move and win in plies + 1.
+                plies += 1;
+                let mut wins = promotions.remove(&plies).unwrap_or(vec![]);
+                wins.retain(|index| white_to_move[*index] == DRAW);
+                for index in wins.iter() {
+                    white_to_move[*index] = plies as u8 + 1;
+                }
+
+                for lost in frontier.iter() {
+                    for position in layout.white_unmoves(&layout.decode(*lost)) {
+                        let (index, _) = layout.canonical(&position);
+                        if white_to_move[index] == DRAW {
+                            white_to_move[index] = plies as u8 + 1;
+                            wins.push(index);
+                        }
+                    }
+                }
+
+                // Black to move and lose in plies + 1.
+                plies += 1;
+                frontier = vec![];
+                for won in wins.iter() {
+                    for (position, step) in layout.black_unmoves(&layout.decode(*won)) {
+                        let (index, symmetries) = layout.canonical(&position);
+                        if black_to_move[index] != DRAW || legal_steps[index] == 0 {
+                            continue;
+                        }
+
+                        for symmetry in symmetries {
+                            match step_index(transform_step(KING_STEPS[step], symmetry)) {
+                                Some(i) => losing_steps[index] |= 1 << i,
+                                None => ()
+                            }
+                        }
+
+                        if losing_steps[index] & legal_steps[index] == legal_steps[index] {
+                            black_to_move[index] = plies as u8 + 1;
+                            frontier.push(index);
+                        }
+                    }
+                }
+            }
+
+            return EndgameTable {
+                material,
+                layout,
+                white_to_move,
+                black_to_move
+            }
+        }
+
+        // Plies to mate through the best promotion, when one wins.
+        fn fastest_promotion(game: &Game, tables: &EndgameTables) -> Option<u32> {
+            let mut fastest = None;
+
+            for chess_move in game.get_all_legal_moves() {
+                match chess_move.move_type {
+                    MoveType::Promote(_) => (),
+                    _ => continue
+                }
+
+                let after_move = match GameTree::play(game, &chess_move) {
+                    Some(after_move) => after_move,
+                    None => continue
+                };
+
+                match tables.probe(&after_move) {
+                    Ok(Dtm::Loss(plies)) => fastest = Some(fastest.map_or(plies + 1, |fastest: u32| fastest.min(plies + 1))),
+                    _ => ()
+                }
+            }
+
+            return fastest;
+        }
+
+        pub fn name(&self) -> String {
+            return self.material.name();
+        }
+
+        // Legal positions with white to move that white wins.
+        pub fn win_count(&self) -> usize {
+            return self.white_to_move.iter().filter(|value| **value != DRAW && **value != ILLEGAL).count();
+        }
+
+        // The longest mate in the table in plies.
+        pub fn longest_mate(&self) -> u32 {
+            return self.white_to_move.iter().filter(|value| **value != ILLEGAL).map(|value| *value as u32).max().unwrap_or(1).saturating_sub(1);
+        }
+
+        fn probe_squares(&self, squares: &[usize], white_to_move: bool) -> Result<Dtm, EndgameTableError> {
+            let (index, _) = self.layout.canonical(squares);
+            let values = if white_to_move { &self.white_to_move } else { &self.black_to_move };
+
+            return match values.get(index) {
+                None | Some(&ILLEGAL) => Err(EndgameTableError::IllegalPosition),
+                Some(&DRAW) => Ok(Dtm::Draw),
+                Some(value) if white_to_move => Ok(Dtm::Win(*value as u32 - 1)),
+                Some(value) => Ok(Dtm::Loss(*value as u32 - 1))
+            }
+        }
+
+        //
+        // File layout: magic, version, length of the name and the name, the number of
+        // entries per side as u32 little endian, then both sides run length encoded as
+        // pairs of a LEB128 run length and the value.
+        pub fn save(&self, path: &Path) -> Result<(), EndgameTableError> {
+            let name = self.name();
+            let mut data = vec![];
+            data.extend_from_slice(&MAGIC);
+            data.push(VERSION);
+            data.push(name.len() as u8);
+            data.extend_from_slice(name.as_bytes());
+            data.extend_from_slice(&(self.layout.size as u32).to_le_bytes());
+            encode_runs(&self.white_to_move, &mut data);
+            encode_runs(&self.black_to_move, &mut data);
+
+            return fs::write(path, data).map_err(|err| EndgameTableError::io(path, err));
+        }
+
+        pub fn load(path: &Path) -> Result<EndgameTable, EndgameTableError> {
+            let data = fs::read(path).map_err(|err| EndgameTableError::io(path, err))?;
+            if data.len() < 6 || data[0..4] != MAGIC {
+                return Err(EndgameTableError::format(path, "not an endgame table"));
+            }
+            if data[4] != VERSION {
+                return Err(EndgameTableError::format(path, &format!("unknown version {}", data[4])));
+            }
+
+            let name_end = 6 + data[5] as usize;
+            let name = String::from_utf8_lossy(data.get(6..name_end).unwrap_or(&[])).to_string();
+            let material = match Material::from_name(&name) {
+                Some(material) => material,
+                None => return Err(EndgameTableError::format(path, &format!("unknown material {}", name)))
+            };
+
+            let size = match data.get(name_end..name_end + 4) {
+                Some(bytes) => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize,
+                None => return Err(EndgameTableError::format(path, "truncated header"))
+            };
+            let layout = Layout::new(material.clone());
+            if size != layout.size {
+                return Err(EndgameTableError::format(path, &format!("{} entries instead of {}", size, layout.size)));
+            }
+
+            let mut position = name_end + 4;
+            let white_to_move = decode_runs(&data, &mut position, size).ok_or(EndgameTableError::format(path, "truncated white table"))?;
+            let black_to_move = decode_runs(&data, &mut position, size).ok_or(EndgameTableError::format(path, "truncated black table"))?;
+
+            return Ok(EndgameTable {
+                material,
+                layout,
+                white_to_move,
+                black_to_move
+            })
+        }
+    }
+
+    fn encode_runs(values: &Vec<u8>, data: &mut Vec<u8>) {
+        let mut i = 0;
+        while i < values.len() {
+            let value = values[i];
+            let mut run = 1;
+            while i + run < values.len() && values[i + run] == value {
+                run += 1;
+            }
+
+            let mut length = run;
+            loop {
+                let byte = (length & 0x7f) as u8;
+                length >>= 7;
+                if length == 0 {
+                    data.push(byte);
+                    break;
+                }
+                data.push(byte | 0x80);
+            }
+            data.push(value);
+            i += run;
+        }
+    }
+
+    fn decode_runs(data: &Vec<u8>, position: &mut usize, size: usize) -> Option<Vec<u8>> {
+        let mut values = Vec::with_capacity(size);
+
+        while values.len() < size {
+            let mut length = 0_usize;
+            let mut shift = 0;
+            loop {
+                let byte = *data.get(*position)?;
+                *position += 1;
+                length |= ((byte & 0x7f) as usize) << shift;
+                shift += 7;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+
+            let value = *data.get(*position)?;
+            *position += 1;
+            if values.len() + length > size {
+                return None;
+            }
+            values.resize(values.len() + length, value);
+        }
+
+        return Some(values);
+    }
+
+    //
+    // The generated tables by material. Positions where one side only has its king are
+    // probed from the table of the other side's pieces.
+    pub struct EndgameTables {
+        tables: HashMap<String, EndgameTable>
+    }
+
+    impl EndgameTables {
+        pub fn new() -> EndgameTables {
+            return EndgameTables {
+                tables: HashMap::new()
+            }
+        }
+
+        pub fn contains(&self, material: &str) -> bool {
+            return match Material::from_name(material) {
+                Some(material) => self.tables.contains_key(&material.name()),
+                None => false
+            }
+        }
+
+        pub fn table(&self, material: &str) -> Option<&EndgameTable> {
+            return self.tables.get(&Material::from_name(material)?.name());
+        }
+
+        // Builds the table for a material like KQK, KRK, KPK or KBNK and the ones its promotions need.
+        pub fn generate(&mut self, material: &str) -> Result<(), EndgameTableError> {
+            let material = match Material::from_name(material) {
+                Some(material) => material,
+                None => return Err(EndgameTableError::UnsupportedMaterial { material: material.to_string() })
+            };
+
+            self.generate_material(material);
+            return Ok(());
+        }
+
+        fn generate_material(&mut self, material: Material) {
+            if self.tables.contains_key(&material.name()) {
+                return;
+            }
+
+            for (index, piece) in material.pieces.iter().enumerate() {
+                if *piece == Piece::Pawn {
+                    for promotion in [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight] {
+                        self.generate_material(material.promoted(index, promotion));
+                    }
+                }
+            }
+
+            let table = EndgameTable::generate(material.clone(), self);
+            self.tables.insert(material.name(), table);
+        }
+
+        // Writes every table to directory, named after its material. Returns the number of files.
+        pub fn save_directory<P: AsRef<Path>>(&self, directory: P) -> Result<usize, EndgameTableError> {
+            let directory = directory.as_ref();
+            fs::create_dir_all(directory).map_err(|err| EndgameTableError::io(directory, err))?;
+
+            for (name, table) in self.tables.iter() {
+                table.save(&directory.join(format!("{}.{}", name, EXTENSION)))?;
+            }
+
+            return Ok(self.tables.len());
+        }
+
+        // Loads every table file in directory. Returns the number of tables loaded.
+        pub fn load_directory<P: AsRef<Path>>(&mut self, directory: P) -> Result<usize, EndgameTableError> {
+            let directory = directory.as_ref();
+            let entries = fs::read_dir(directory).map_err(|err| EndgameTableError::io(directory, err))?;
+            let mut count = 0;
+
+            for entry in entries {
+                let path = match entry {
+                    Ok(entry) => entry.path(),
+                    Err(_) => continue
+                };
+                if path.extension().map_or(true, |extension| extension != EXTENSION) {
+                    continue;
+                }
+
+                let table = EndgameTable::load(&path)?;
+                self.tables.insert(table.name(), table);
+                count += 1;
+            }
+
+            return Ok(count);
+        }
+
+        //
+        // The squares of a position in table order with the strong side as white. Black's
+        // pieces are mirrored across the middle of the board, which keeps the pawns moving up.
+        fn position(game: &Game) -> Option<(Material, Vec<usize>, bool)> {
+            let mut pieces = [vec![], vec![]];
+            let mut kings = [None, None];
+
+            for row in 0..8 {
+                for column in 0..8 {
+                    match game.get_piece_from_position(&Position::new(column, row)) {
+                        None => (),
+                        Some((piece, color)) => {
+                            let side = if color == Color::White { 0 } else { 1 };
+                            let square = row * 8 + column;
+                            if piece == Piece::King {
+                                kings[side] = Some(square);
+                            } else {
+                                pieces[side].push((piece, square));
+                            }
+                        }
+                    }
+                }
+            }
+
+            let strong = if pieces[1].is_empty() { 0 } else { 1 };
+            if !pieces[1 - strong].is_empty() || pieces[strong].len() > MAX_EXTRA_PIECES {
+                return None;
+            }
+
+            let flip = |square: usize| if strong == 1 { transform_square(square, 2) } else { square };
+            let mut strong_pieces = pieces[strong].clone();
+            strong_pieces.sort_by_key(|(piece, square)| (name_order(*piece), *square));
+
+            let mut squares = vec![flip(kings[strong]?)];
+            squares.extend(strong_pieces.iter().map(|(_, square)| flip(*square)));
+            squares.push(flip(kings[1 - strong]?));
+
+            let material = Material::new(strong_pieces.iter().map(|(piece, _)| *piece).collect());
+            let strong_to_move = (game.get_turn() == Color::White) == (strong == 0);
+            return Some((material, squares, strong_to_move));
+        }
+
+        pub fn can_probe(&self, game: &Game) -> bool {
+            if !game.variant.is_standard() || !game.is_standard_board() {
+                return false;
+            }
+
+            return match EndgameTables::position(game) {
+                Some((material, _, _)) => material.is_insufficient() || self.tables.contains_key(&material.name()),
+                None => false
+            }
+        }
+
+        // Distance to mate for the side to move.
+        pub fn probe(&self, game: &Game) -> Result<Dtm, EndgameTableError> {
+            let (material, squares, strong_to_move) = match EndgameTables::position(game) {
+                Some(position) => position,
+                None => return Err(EndgameTableError::UnsupportedMaterial { material: "position".to_string() })
+            };
+
+            let dtm = match self.tables.get(&material.name()) {
+                Some(table) => table.probe_squares(&squares, strong_to_move)?,
+                None if material.is_insufficient() => Dtm::Draw,
+                None => return Err(EndgameTableError::MissingTable { material: material.name() })
+            };
+
+            return Ok(dtm);
+        }
+
+        // The result chess_move gives the side playing it.
+        pub fn probe_move(&self, game: &Game, chess_move: &ChessMove) -> Result<Dtm, EndgameTableError> {
+            let after_move = match GameTree::play(game, chess_move) {
+                Some(after_move) => after_move,
+                None => return Err(EndgameTableError::IllegalPosition)
+            };
+
+            return Ok(self.probe(&after_move)?.before_move());
+        }
+
+        // A move that keeps the best result, the fastest mate when winning and the slowest when losing.
+        pub fn best_move(&self, game: &Game) -> Result<Option<(ChessMove, Dtm)>, EndgameTableError> {
+            let mut best: Option<(ChessMove, Dtm)> = None;
+
+            for chess_move in game.get_all_legal_moves() {
+                let dtm = self.probe_move(game, &chess_move)?;
+                let better = match &best {
+                    None => true,
+                    Some((_, best_dtm)) => dtm.rank() > best_dtm.rank()
+                };
+
+                if better {
+                    best = Some((chess_move, dtm));
+                }
+            }
+
+            return Ok(best);
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::env;
+        use std::process;
+        use crate::engine::uci::uci;
+
+        fn longest_mate(material: &str) -> u32 {
+            let mut tables = EndgameTables::new();
+            tables.generate(material).unwrap();
+            return tables.table(material).unwrap().longest_mate();
+        }
+
+        // The longest mates are the published ones, 10, 16, 28 and 33 moves.
+        #[test]
+        fn longest_mates() {
+            assert_eq!(longest_mate("KQK"), 19);
+            assert_eq!(longest_mate("KRK"), 31);
+            assert_eq!(longest_mate("KPK"), 55);
+        }
+
+        #[test]
+        #[ignore = "takes half a minute in a release build"]
+        fn longest_bishop_and_knight_mate() {
+            assert_eq!(longest_mate("KBNK"), 65);
+        }
+
+        #[test]
+        fn saves_and_loads() {
+            let mut tables = EndgameTables::new();
+            tables.generate("KRK").unwrap();
+
+            let directory = env::temp_dir().join(format!("chess-endgame-tables-{}", process::id()));
+            let saved = tables.save_directory(&directory);
+            let mut loaded = EndgameTables::new();
+            let count = loaded.load_directory(&directory);
+            fs::remove_dir_all(&directory).unwrap();
+
+            assert_eq!(saved.unwrap(), 1);
+            assert_eq!(count.unwrap(), 1);
+            let (table, loaded_table) = (tables.table("KRK").unwrap(), loaded.table("KRK").unwrap());
+            assert_eq!(loaded_table.white_to_move, table.white_to_move);
+            assert_eq!(loaded_table.black_to_move, table.black_to_move);
+        }
+
+        #[test]
+        fn finds_the_mate() {
+            let mut tables = EndgameTables::new();
+            tables.generate("KQK").unwrap();
+            let game = uci::game_from_fen("k7/8/1K6/8/8/8/7Q/8 w - - 0 1").unwrap();
+
+            assert_eq!(tables.probe(&game).unwrap(), Dtm::Win(1));
+            let (chess_move, dtm) = tables.best_move(&game).unwrap().unwrap();
+            assert_eq!(dtm, Dtm::Win(1));
+            assert!(GameTree::play(&game, &chess_move).unwrap().is_checkmate());
+        }
+    }
+}
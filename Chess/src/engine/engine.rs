@@ -1,125 +1,122 @@
 pub mod engine {
-    use std::path::Component::RootDir;
-    use crate::chess::chess_move::chess_move::ChessMove;
     use crate::chess::game::game::Game;
     use crate::engine::engine_moves::engine_moves::EngineMoves;
-    use crate::engine::tree::game_tree::GameTree;
-    use crate::engine::tree::game_tree::GameTree::Leaf;
+    use crate::engine::mate_solver::mate_solver::{self, MateSolution};
+    use crate::engine::search::search::{EngineOptions, PvLine, Search, SearchLimits};
+    use crate::engine::syzygy::syzygy::Tablebase;
+    use crate::engine::tree::game_tree::{GameTree, NodeId, ROOT};
 
     pub fn tree_init(game: Game, depth: usize) -> GameTree {
-        if depth < 1 {
-            panic!("Depth have to start at 1.")
-        }
-
-        let all_moves = game.get_all_turn_available_moves();
-
-        let mut root = GameTree::init_tree(game.clone());
+        return build_tree(GameTree::new(game), depth, None);
+    }
 
-        for chess_move in all_moves.iter() {
-            let mut new_game = game.clone();
-            let mut after_move = new_game.move_piece(&new_game.get_turn(), &chess_move).unwrap();
-            after_move.turn += 1;
-            let x = generate_tree(&after_move, chess_move, depth - 1);
-            root = GameTree::append(root, x);
-        }
+    // Same as tree_init, but positions reached by different move orders share one node.
+    pub fn tree_init_with_transpositions(game: Game, depth: usize) -> GameTree {
+        return build_tree(GameTree::with_transpositions(game), depth, None);
+    }
 
-        return root;
+    // Same as tree_init, but positions covered by the tablebase are scored from it instead of searched.
+    pub fn tree_init_with_tablebase(game: Game, depth: usize, tablebase: &Tablebase) -> GameTree {
+        return build_tree(GameTree::new(game), depth, Some(tablebase));
     }
 
+    fn build_tree(mut tree: GameTree, depth: usize, tablebase: Option<&Tablebase>) -> GameTree {
+        if depth < 1 {
+            panic!("Depth have to start at 1.")
+        }
 
-    pub fn generate_tree(game: &Game, move_to_here: &ChessMove, depth: usize) -> GameTree {
-        let all_moves = game.get_all_turn_available_moves();
+        let game = tree.game().clone();
+        let mut all_moves = game.get_all_legal_moves();
 
-        if all_moves.len() == 0 || depth == 0 {
-            let game_tree = GameTree::new_leaf(game.clone(),move_to_here.clone());
-            return game_tree;
+        // At the root only the moves that keep the best result under the fifty-move rule are kept.
+        match tablebase {
+            Some(tablebase) => if tablebase.can_probe(&game) {
+                match tablebase.best_root_moves(&game) {
+                    Ok(moves) => all_moves = moves,
+                    // A missing or unreadable table leaves the position to the search.
+                    Err(_) => ()
+                }
+            },
+            None => ()
         }
 
-        let mut game_tree = GameTree::new_tree(game.clone(), move_to_here.clone());
-
-        for chess_move in all_moves.iter() {
-            if Game::is_valid_move(game.clone(), chess_move) {
-                let mut new_game = game.clone();
-                let mut after_move = new_game.move_piece(&new_game.get_turn(), &chess_move).unwrap();
-                after_move.turn += 1;
-                game_tree = GameTree::append(game_tree, generate_tree(&after_move, chess_move, depth-1));
-            }
+        for (child, after_move) in tree.expand(ROOT, &game, &all_moves) {
+            generate_tree(&mut tree, child, &after_move, depth - 1, tablebase);
         }
 
-        return game_tree;
+        return tree;
     }
 
-    pub fn show_all_moves<'a>(game_tree: &'a GameTree) -> Vec<EngineMoves> {
-        match game_tree {
-            GameTree::Leaf(game, chess_move_option, value) => {
-                match chess_move_option {
-                    Some(chess_move) => {
-                        let engine_moves = EngineMoves {
-                            moves: vec![chess_move.clone()]
-                        };
-                        return vec![engine_moves]
+    pub fn generate_tree(tree: &mut GameTree, node: NodeId, game: &Game, depth: usize, tablebase: Option<&Tablebase>) {
+        match tablebase {
+            Some(tablebase) => if tablebase.can_probe(game) {
+                match tablebase.probe_wdl(game) {
+                    Ok(wdl) => {
+                        tree.set_tablebase_score(node, wdl.to_value(&game.get_turn()));
+                        return;
                     },
-                    None => panic!("Leafes should always have a move")
+                    Err(_) => ()
                 }
             },
-            GameTree::Tree(game, chess_move, alpha, beta, children) => {
-                match chess_move {
-                    Some(chess_move) => {
-                        println!("Sub tree");
-                        let mut possibilities = vec![];
-
-                        for tree in children {
-                            let mut engine_moves = show_all_moves(tree);
-                            for mut x in engine_moves {
-                                x.add_move(chess_move.clone());
-                                possibilities.append(&mut vec![x])
-                            }
-                        }
-
-                        return possibilities
-                    },
-                    None => {
-                        // For root
-                        println!("Root");
-                        let mut from_root = vec![];
-                        for i in children {
-                            let mut engine_move = show_all_moves(i);
-                            from_root.append(&mut engine_move)
-                        }
-                        return from_root;
-                    }
-                }
-            }
+            None => ()
+        }
+
+        if depth == 0 {
+            return;
+        }
+
+        let all_moves = game.get_all_legal_moves();
+        for (child, after_move) in tree.expand(node, game, &all_moves) {
+            generate_tree(tree, child, &after_move, depth - 1, tablebase);
         }
     }
 
-    pub fn count_nodes(game_tree: &GameTree) -> usize {
-        match game_tree {
-            GameTree::Leaf(x,y,z) => return 1,
-            GameTree::Tree(x,y,z,t,f) => {
-                let mut sum = 0;
+    // Every line from the root to a leaf.
+    pub fn show_all_moves(game_tree: &GameTree) -> Vec<EngineMoves> {
+        return lines_below(game_tree, ROOT);
+    }
 
-                for tree in f.iter() {
-                    sum += count_nodes(tree)
-                }
+    fn lines_below(game_tree: &GameTree, node: NodeId) -> Vec<EngineMoves> {
+        let mut possibilities = vec![];
+
+        for edge in game_tree.children(node) {
+            let lines = if game_tree.is_leaf(edge.child) {
+                vec![EngineMoves::new()]
+            } else {
+                lines_below(game_tree, edge.child)
+            };
 
-                return 1 + sum;
+            // Moves are added from the leaf up, the way EngineMoves stores them.
+            for mut line in lines {
+                line.add_move(edge.chess_move.clone());
+                possibilities.push(line);
             }
         }
+
+        return possibilities;
     }
 
-    pub fn count_leaves(game_tree: &GameTree) -> usize {
-        match game_tree {
-            GameTree::Leaf(x,y,z) => return 1,
-            GameTree::Tree(x,y,z,t,f) => {
-                let mut sum = 0;
+    // The count best root moves with their scores and principal variations, best first.
+    pub fn best_lines(game: &Game, depth: usize, count: usize) -> Vec<PvLine> {
+        let mut options = EngineOptions::default();
+        options.multi_pv = count;
 
-                for tree in f.iter() {
-                    sum += count_leaves(tree)
-                }
+        let search = Search::new(options);
+        let result = search.run(game, &SearchLimits::depth(depth), &mut |_info| ());
+        return result.lines;
+    }
 
-                return sum
-            }
-        }
+    // Proves or disproves a forced mate in n moves for the side to move, see MateSolution.
+    pub fn solve_mate(game: &Game, n: usize) -> MateSolution {
+        return mate_solver::solve(game, n);
+    }
+
+    // Nodes in the tree, shared transpositions are counted once.
+    pub fn count_nodes(game_tree: &GameTree) -> usize {
+        return game_tree.node_count();
+    }
+
+    pub fn count_leaves(game_tree: &GameTree) -> usize {
+        return (0..game_tree.node_count()).filter(|node| game_tree.is_leaf(*node)).count();
     }
-}
\ No newline at end of file
+}
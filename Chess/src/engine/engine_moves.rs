@@ -6,7 +6,38 @@ pub mod engine_moves {
         pub(crate) moves: Vec<ChessMove>
     }
 
+    // Moves are stored from the last move to the first, the way the tree is walked back up.
     impl EngineMoves {
+        pub fn new() -> EngineMoves {
+            return EngineMoves {
+                moves: vec![]
+            }
+        }
+
+        pub fn from_line(line: &Vec<ChessMove>) -> EngineMoves {
+            let mut moves = line.clone();
+            moves.reverse();
+            return EngineMoves {
+                moves
+            }
+        }
+
+        // The moves in the order they are played.
+        pub fn line(&self) -> Vec<ChessMove> {
+            let mut line = self.moves.clone();
+            line.reverse();
+            return line;
+        }
+
+        pub fn first_move(&self) -> Option<ChessMove> {
+            return self.moves.last().cloned();
+        }
+
+        pub fn to_uci_string(&self, ranks: usize) -> String {
+            let moves: Vec<String> = self.moves.iter().rev().map(|chess_move| chess_move.to_uci(ranks)).collect();
+            return moves.join(" ");
+        }
+
         pub fn add_move(&mut self, chess_move: ChessMove) {
             self.moves.append(&mut vec![chess_move.clone()])
         }
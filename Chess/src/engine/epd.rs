@@ -0,0 +1,342 @@
+pub mod epd {
+    use std::fmt;
+    use crate::chess::chess_move::chess_move::ChessMove;
+    use crate::chess::game::game::Game;
+    use crate::engine::search::search::{Search, SearchLimits};
+    use crate::engine::uci::uci;
+
+    #[derive(Debug, Clone)]
+    pub enum EpdError {
+        MissingFields {
+            line: String
+        },
+        InvalidPosition {
+            fen: String
+        },
+        UnknownMove {
+            id: String,
+            chess_move: String
+        }
+    }
+
+    impl fmt::Display for EpdError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                EpdError::MissingFields { line } =>
+                    write!(f, "EPD line needs four position fields: {}", line),
+                EpdError::InvalidPosition { fen } =>
+                    write!(f, "Invalid EPD position: {}", fen),
+                EpdError::UnknownMove { id, chess_move } =>
+                    write!(f, "{}: {} is not a legal move", id, chess_move)
+            }
+        }
+    }
+
+    //
+    // One test position. Best and avoid moves come from the bm and am opcodes, points
+    // from a c0 comment in the STS form "Qd2=10, Rc1=5" when there is one.
+    #[derive(Clone)]
+    pub struct EpdPosition {
+        pub id: String,
+        pub fen: String,
+        pub game: Game,
+        pub best_moves: Vec<ChessMove>,
+        pub avoid_moves: Vec<ChessMove>,
+        pub comment: Option<String>,
+        pub points: Vec<(ChessMove, u32)>
+    }
+
+    impl EpdPosition {
+        pub fn parse(line: &str) -> Result<EpdPosition, EpdError> {
+            let fields: Vec<&str> = line.split_whitespace().take(4).collect();
+            if fields.len() < 4 {
+                return Err(EpdError::MissingFields { line: line.to_string() });
+            }
+
+            let fen = fields.join(" ");
+            let game = match uci::game_from_fen(&fen) {
+                Some(game) => game,
+                None => return Err(EpdError::InvalidPosition { fen })
+            };
+
+            let mut position = EpdPosition {
+                id: String::new(),
+                fen,
+                game,
+                best_moves: vec![],
+                avoid_moves: vec![],
+                comment: None,
+                points: vec![]
+            };
+
+            // Skip the four position fields, the operations follow.
+            let mut rest = line.trim_start();
+            for _ in 0..4 {
+                rest = rest.trim_start();
+                rest = &rest[rest.find(char::is_whitespace).unwrap_or(rest.len())..];
+            }
+
+            let operations = split_operations(rest);
+            for (opcode, operands) in operations.iter() {
+                if opcode == "id" {
+                    position.id = operands.join(" ");
+                }
+            }
+            if position.id.is_empty() {
+                position.id = position.fen.clone();
+            }
+
+            for (opcode, operands) in operations.iter() {
+                match opcode.as_str() {
+                    "bm" => position.best_moves = position.find_moves(operands)?,
+                    "am" => position.avoid_moves = position.find_moves(operands)?,
+                    "c0" => {
+                        let comment = operands.join(" ");
+                        position.points = position.parse_points(&comment)?;
+                        position.comment = Some(comment);
+                    },
+                    _ => ()
+                }
+            }
+
+            return Ok(position);
+        }
+
+        fn find_moves(&self, operands: &Vec<String>) -> Result<Vec<ChessMove>, EpdError> {
+            let mut moves = vec![];
+            for operand in operands.iter() {
+                match find_move(&self.game, operand) {
+                    Some(chess_move) => moves.push(chess_move),
+                    None => return Err(EpdError::UnknownMove { id: self.id.clone(), chess_move: operand.clone() })
+                }
+            }
+            return Ok(moves);
+        }
+
+        // Comments that are not a list of move=points pairs give no points.
+        fn parse_points(&self, comment: &str) -> Result<Vec<(ChessMove, u32)>, EpdError> {
+            let mut points = vec![];
+
+            for entry in comment.split(',') {
+                let (chess_move, value) = match entry.trim().split_once('=') {
+                    Some(pair) => pair,
+                    None => return Ok(vec![])
+                };
+                let value = match value.trim().parse::<u32>() {
+                    Ok(value) => value,
+                    Err(_) => return Ok(vec![])
+                };
+
+                match find_move(&self.game, chess_move.trim()) {
+                    Some(chess_move) => points.push((chess_move, value)),
+                    None => return Err(EpdError::UnknownMove { id: self.id.clone(), chess_move: chess_move.trim().to_string() })
+                }
+            }
+
+            return Ok(points);
+        }
+
+        // Points for playing chess_move and the most the position gives.
+        pub fn score(&self, chess_move: &ChessMove) -> (u32, u32) {
+            if !self.points.is_empty() {
+                let value = self.points.iter().find(|(scored, _)| scored == chess_move).map_or(0, |(_, value)| *value);
+                return (value, self.max_points());
+            }
+
+            return (if self.is_solved_by(chess_move) { 1 } else { 0 }, 1);
+        }
+
+        pub fn max_points(&self) -> u32 {
+            if self.points.is_empty() {
+                return 1;
+            }
+            return self.points.iter().map(|(_, value)| *value).max().unwrap_or(0);
+        }
+
+        // A move solves the position when it is one of the best moves and none of the moves to avoid.
+        pub fn is_solved_by(&self, chess_move: &ChessMove) -> bool {
+            if !self.best_moves.is_empty() && !self.best_moves.contains(chess_move) {
+                return false;
+            }
+            if self.avoid_moves.contains(chess_move) {
+                return false;
+            }
+            if self.best_moves.is_empty() && self.avoid_moves.is_empty() {
+                return self.points.iter().any(|(scored, value)| scored == chess_move && *value > 0);
+            }
+            return true;
+        }
+
+        // What the position asks for, like "bm Qg6" or "am Bxh7".
+        pub fn expected(&self) -> String {
+            let mut expected = vec![];
+            if !self.best_moves.is_empty() {
+                let moves: Vec<String> = self.best_moves.iter().map(|chess_move| chess_move.to_san(&self.game)).collect();
+                expected.push(format!("bm {}", moves.join(" ")));
+            }
+            if !self.avoid_moves.is_empty() {
+                let moves: Vec<String> = self.avoid_moves.iter().map(|chess_move| chess_move.to_san(&self.game)).collect();
+                expected.push(format!("am {}", moves.join(" ")));
+            }
+            return expected.join(", ");
+        }
+    }
+
+    //
+    // Splits "bm Qg6; id \"WAC.001\";" into opcodes with their operands. Semicolons
+    // inside quotes do not end an operation and the quotes are removed.
+    fn split_operations(text: &str) -> Vec<(String, Vec<String>)> {
+        let mut operations = vec![];
+        let mut tokens: Vec<String> = vec![];
+        let mut token = String::new();
+        let mut quoted = false;
+        let mut has_token = false;
+
+        for c in text.chars() {
+            match c {
+                '"' => {
+                    quoted = !quoted;
+                    has_token = true;
+                },
+                ';' if !quoted => {
+                    if has_token {
+                        tokens.push(token.clone());
+                    }
+                    token.clear();
+                    has_token = false;
+
+                    if !tokens.is_empty() {
+                        operations.push((tokens[0].clone(), tokens[1..].to_vec()));
+                    }
+                    tokens.clear();
+                },
+                c if c.is_whitespace() && !quoted => {
+                    if has_token {
+                        tokens.push(token.clone());
+                    }
+                    token.clear();
+                    has_token = false;
+                },
+                c => {
+                    token.push(c);
+                    has_token = true;
+                }
+            }
+        }
+
+        if has_token {
+            tokens.push(token);
+        }
+        if !tokens.is_empty() {
+            operations.push((tokens[0].clone(), tokens[1..].to_vec()));
+        }
+
+        return operations;
+    }
+
+    // A legal move given in SAN, with or without check marks and annotations, or in UCI notation.
+    fn find_move(game: &Game, text: &str) -> Option<ChessMove> {
+        let strip = |san: &str| san.trim_end_matches(|c| c == '+' || c == '#' || c == '!' || c == '?').replace("0-0", "O-O").to_string();
+        let wanted = strip(text);
+
+        for chess_move in game.get_all_legal_moves() {
+            if strip(&chess_move.to_san(game)) == wanted || chess_move.to_uci(game.board.num_rows()) == text {
+                return Some(chess_move);
+            }
+        }
+
+        return None;
+    }
+
+    // Positions of an EPD file, lines that can not be read are returned as errors.
+    pub fn parse_suite(text: &str) -> Vec<Result<EpdPosition, EpdError>> {
+        return text.lines()
+            .filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .map(|line| EpdPosition::parse(line))
+            .collect();
+    }
+
+    pub struct EpdOutcome {
+        pub id: String,
+        pub played: Option<String>,
+        pub expected: String,
+        pub solved: bool,
+        // The first depth from which the search kept a solving move.
+        pub solved_at: Option<usize>,
+        pub points: u32,
+        pub max_points: u32
+    }
+
+    pub struct EpdReport {
+        pub outcomes: Vec<EpdOutcome>
+    }
+
+    impl EpdReport {
+        pub fn solved(&self) -> usize {
+            return self.outcomes.iter().filter(|outcome| outcome.solved).count();
+        }
+
+        pub fn points(&self) -> (u32, u32) {
+            let points = self.outcomes.iter().map(|outcome| outcome.points).sum();
+            let max_points = self.outcomes.iter().map(|outcome| outcome.max_points).sum();
+            return (points, max_points);
+        }
+    }
+
+    impl fmt::Display for EpdReport {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let (points, max_points) = self.points();
+            write!(f, "Solved {} of {}, score {} of {}", self.solved(), self.outcomes.len(), points, max_points)
+        }
+    }
+
+    // Searches every position with limits and reports each outcome as it is known.
+    pub fn run_suite(search: &Search, positions: &Vec<EpdPosition>, limits: &SearchLimits, on_outcome: &mut dyn FnMut(&EpdOutcome)) -> EpdReport {
+        let mut outcomes = vec![];
+
+        for position in positions.iter() {
+            search.clear();
+
+            let mut solved_at = None;
+            let result = search.run(&position.game, limits, &mut |info| {
+                if info.multi_pv != 1 {
+                    return;
+                }
+                match info.pv.first_move() {
+                    Some(chess_move) if position.is_solved_by(&chess_move) => solved_at = solved_at.or(Some(info.depth)),
+                    _ => solved_at = None
+                }
+            });
+
+            let outcome = match result.best_move {
+                Some(chess_move) => {
+                    let (points, max_points) = position.score(&chess_move);
+                    let solved = position.is_solved_by(&chess_move);
+                    EpdOutcome {
+                        id: position.id.clone(),
+                        played: Some(chess_move.to_san(&position.game)),
+                        expected: position.expected(),
+                        solved,
+                        solved_at: if solved { solved_at.or(Some(result.depth)) } else { None },
+                        points,
+                        max_points
+                    }
+                },
+                None => EpdOutcome {
+                    id: position.id.clone(),
+                    played: None,
+                    expected: position.expected(),
+                    solved: false,
+                    solved_at: None,
+                    points: 0,
+                    max_points: position.max_points()
+                }
+            };
+
+            on_outcome(&outcome);
+            outcomes.push(outcome);
+        }
+
+        return EpdReport { outcomes };
+    }
+}
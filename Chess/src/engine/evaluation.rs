@@ -0,0 +1,330 @@
+pub mod evaluation {
+    use std::fmt;
+    use std::fs;
+    use crate::chess::color::color::Color;
+    use crate::chess::game::game::Game;
+    use crate::chess::piece::piece::Piece;
+    use crate::chess::position::position::Position;
+    use crate::chess::variant::variant::Outcome;
+    use crate::engine::search::search::MATE_VALUE;
+
+    // Scores are in centipawns from whites point of view.
+    pub trait Evaluator: Send + Sync {
+        fn evaluate(&self, game: &Game) -> i32;
+    }
+
+    // Mate, stalemate or a result of the variant, for a position without legal moves.
+    pub fn terminal_value(game: &Game) -> i32 {
+        return match game.variant.outcome(game) {
+            Some(outcome) => outcome_value(&outcome),
+            None => outcome_value(&game.variant.no_moves_outcome(game))
+        }
+    }
+
+    // The evaluation by the rules of the game's variant, in centipawns from whites point of view.
+    pub fn variant_evaluation(evaluator: &dyn Evaluator, game: &Game) -> i32 {
+        return game.variant.evaluation_sign() * evaluator.evaluate(game) + game.variant.evaluate(game);
+    }
+
+    pub fn outcome_value(outcome: &Outcome) -> i32 {
+        return match outcome {
+            Outcome::Win(Color::White) => MATE_VALUE,
+            Outcome::Win(Color::Black) => -MATE_VALUE,
+            Outcome::Draw => 0
+        }
+    }
+
+    pub const PAWN_VALUE: i32 = 100;
+    pub const KNIGHT_VALUE: i32 = 320;
+    pub const BISHOP_VALUE: i32 = 330;
+    pub const ROOK_VALUE: i32 = 500;
+    pub const QUEEN_VALUE: i32 = 900;
+    pub const ARCHBISHOP_VALUE: i32 = 825;
+    pub const CHANCELLOR_VALUE: i32 = 875;
+    pub const AMAZON_VALUE: i32 = 1300;
+
+    // Tables are seen from white, row 0 is the eighth rank.
+    const PAWN_TABLE: [i32; 64] = [
+         0,  0,  0,  0,  0,  0,  0,  0,
+        50, 50, 50, 50, 50, 50, 50, 50,
+        10, 10, 20, 30, 30, 20, 10, 10,
+         5,  5, 10, 25, 25, 10,  5,  5,
+         0,  0,  0, 20, 20,  0,  0,  0,
+         5, -5,-10,  0,  0,-10, -5,  5,
+         5, 10, 10,-20,-20, 10, 10,  5,
+         0,  0,  0,  0,  0,  0,  0,  0,
+    ];
+
+    const KNIGHT_TABLE: [i32; 64] = [
+        -50,-40,-30,-30,-30,-30,-40,-50,
+        -40,-20,  0,  0,  0,  0,-20,-40,
+        -30,  0, 10, 15, 15, 10,  0,-30,
+        -30,  5, 15, 20, 20, 15,  5,-30,
+        -30,  0, 15, 20, 20, 15,  0,-30,
+        -30,  5, 10, 15, 15, 10,  5,-30,
+        -40,-20,  0,  5,  5,  0,-20,-40,
+        -50,-40,-30,-30,-30,-30,-40,-50,
+    ];
+
+    const BISHOP_TABLE: [i32; 64] = [
+        -20,-10,-10,-10,-10,-10,-10,-20,
+        -10,  0,  0,  0,  0,  0,  0,-10,
+        -10,  0,  5, 10, 10,  5,  0,-10,
+        -10,  5,  5, 10, 10,  5,  5,-10,
+        -10,  0, 10, 10, 10, 10,  0,-10,
+        -10, 10, 10, 10, 10, 10, 10,-10,
+        -10,  5,  0,  0,  0,  0,  5,-10,
+        -20,-10,-10,-10,-10,-10,-10,-20,
+    ];
+
+    const KING_TABLE: [i32; 64] = [
+        -30,-40,-40,-50,-50,-40,-40,-30,
+        -30,-40,-40,-50,-50,-40,-40,-30,
+        -30,-40,-40,-50,-50,-40,-40,-30,
+        -30,-40,-40,-50,-50,-40,-40,-30,
+        -20,-30,-30,-40,-40,-30,-30,-20,
+        -10,-20,-20,-20,-20,-20,-20,-10,
+         20, 20,  0,  0,  0,  0, 20, 20,
+         20, 30, 10,  0,  0, 10, 30, 20,
+    ];
+
+    const PIECES: [Piece; 6] = [Piece::Pawn, Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen, Piece::King];
+
+    #[derive(Debug)]
+    pub enum EvalParamsError {
+        Io {
+            path: String,
+            message: String
+        },
+        UnknownName {
+            name: String
+        },
+        MissingNumbers {
+            name: String
+        }
+    }
+
+    impl fmt::Display for EvalParamsError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                EvalParamsError::Io { path, message } =>
+                    write!(f, "Couldnt read {}: {}", path, message),
+                EvalParamsError::UnknownName { name } =>
+                    write!(f, "Unknown evaluation parameter {}", name),
+                EvalParamsError::MissingNumbers { name } =>
+                    write!(f, "Evaluation parameter {} is missing numbers", name)
+            }
+        }
+    }
+
+    //
+    // Everything the evaluator can be tuned on, a value and a square table for every piece.
+    // As a vector the six values come first, then the tables in the same piece order.
+    #[derive(Clone, PartialEq)]
+    pub struct EvalParams {
+        pub values: [i32; 6],
+        pub tables: [[i32; 64]; 6]
+    }
+
+    impl EvalParams {
+        pub fn default() -> EvalParams {
+            return EvalParams {
+                values: [PAWN_VALUE, KNIGHT_VALUE, BISHOP_VALUE, ROOK_VALUE, QUEEN_VALUE, 0],
+                tables: [PAWN_TABLE, KNIGHT_TABLE, BISHOP_TABLE, [0; 64], [0; 64], KING_TABLE]
+            }
+        }
+
+        pub fn len() -> usize {
+            return PIECES.len() * 65;
+        }
+
+        fn piece_index(piece: &Piece) -> usize {
+            return PIECES.iter().position(|other| other == piece).unwrap();
+        }
+
+        pub fn value_index(piece: &Piece) -> usize {
+            return EvalParams::piece_index(piece);
+        }
+
+        pub fn square_index(piece: &Piece, color: &Color, position: &Position) -> usize {
+            return PIECES.len() + EvalParams::piece_index(piece) * 64 + EvalParams::table_square(color, position);
+        }
+
+        // Boards of other sizes are stretched over the 8x8 tables.
+        pub fn table_position(game: &Game, position: &Position) -> Position {
+            return Position::new(position.column * 8 / game.board.num_columns(), position.row * 8 / game.board.num_rows());
+        }
+
+        fn table_square(color: &Color, position: &Position) -> usize {
+            // Black reads the tables upside down.
+            let row = match color {
+                Color::White => position.row,
+                Color::Black => 7 - position.row
+            };
+
+            return row * 8 + position.column;
+        }
+
+        // Fairy pieces are not tuned, they only have their material value.
+        pub fn piece_square_value(&self, piece: &Piece, color: &Color, position: &Position) -> i32 {
+            if !piece.is_standard() {
+                return MaterialEvaluator::piece_value(piece);
+            }
+            let index = EvalParams::piece_index(piece);
+            return self.values[index] + self.tables[index][EvalParams::table_square(color, position)];
+        }
+
+        pub fn to_vector(&self) -> Vec<i32> {
+            let mut vector = self.values.to_vec();
+            for table in self.tables.iter() {
+                vector.extend_from_slice(table);
+            }
+            return vector;
+        }
+
+        pub fn from_vector(vector: &Vec<i32>) -> EvalParams {
+            let mut params = EvalParams::default();
+            params.values.copy_from_slice(&vector[..PIECES.len()]);
+            for (index, table) in params.tables.iter_mut().enumerate() {
+                let start = PIECES.len() + index * 64;
+                table.copy_from_slice(&vector[start..start + 64]);
+            }
+            return params;
+        }
+
+        fn piece_name(piece: &Piece) -> &'static str {
+            return match piece {
+                Piece::Pawn => "pawn",
+                Piece::Knight => "knight",
+                Piece::Bishop => "bishop",
+                Piece::Rook => "rook",
+                Piece::Queen => "queen",
+                Piece::King => "king",
+                Piece::Archbishop => "archbishop",
+                Piece::Chancellor => "chancellor",
+                Piece::Amazon => "amazon"
+            }
+        }
+
+        //
+        // Reads the text written by to_text, a name followed by its numbers like "knight_value 320"
+        // or "pawn_table" and 64 numbers. Names that are left out keep their default.
+        pub fn from_text(text: &str) -> Result<EvalParams, EvalParamsError> {
+            let mut params = EvalParams::default();
+            let mut tokens = text.lines()
+                .map(|line| line.split('#').next().unwrap_or(""))
+                .flat_map(|line| line.split_whitespace());
+
+            while let Some(name) = tokens.next() {
+                let (piece_name, kind) = match name.rsplit_once('_') {
+                    Some(split) => split,
+                    None => return Err(EvalParamsError::UnknownName { name: name.to_string() })
+                };
+                let piece = match PIECES.iter().find(|piece| EvalParams::piece_name(piece) == piece_name) {
+                    Some(piece) => EvalParams::piece_index(piece),
+                    None => return Err(EvalParamsError::UnknownName { name: name.to_string() })
+                };
+
+                let count = match kind {
+                    "value" => 1,
+                    "table" => 64,
+                    _ => return Err(EvalParamsError::UnknownName { name: name.to_string() })
+                };
+                let mut numbers = vec![];
+                for _ in 0..count {
+                    match tokens.next().map(|token| token.parse::<i32>()) {
+                        Some(Ok(number)) => numbers.push(number),
+                        _ => return Err(EvalParamsError::MissingNumbers { name: name.to_string() })
+                    }
+                }
+
+                match kind {
+                    "value" => params.values[piece] = numbers[0],
+                    _ => params.tables[piece].copy_from_slice(&numbers)
+                }
+            }
+
+            return Ok(params);
+        }
+
+        pub fn load(path: &str) -> Result<EvalParams, EvalParamsError> {
+            return match fs::read_to_string(path) {
+                Ok(text) => EvalParams::from_text(&text),
+                Err(err) => Err(EvalParamsError::Io { path: path.to_string(), message: err.to_string() })
+            }
+        }
+
+        pub fn to_text(&self) -> String {
+            let mut text = String::from("# Evaluation parameters in centipawns, tables are seen from white with the eighth rank first.\n");
+            for (index, piece) in PIECES.iter().enumerate() {
+                text.push_str(&format!("{}_value {}\n", EvalParams::piece_name(piece), self.values[index]));
+            }
+            for (index, piece) in PIECES.iter().enumerate() {
+                text.push_str(&format!("{}_table\n", EvalParams::piece_name(piece)));
+                for row in self.tables[index].chunks(8) {
+                    let numbers: Vec<String> = row.iter().map(|number| format!("{:4}", number)).collect();
+                    text.push_str(&numbers.join(" "));
+                    text.push('\n');
+                }
+            }
+            return text;
+        }
+    }
+
+    // Material with piece square tables.
+    pub struct MaterialEvaluator {
+        params: EvalParams
+    }
+
+    impl MaterialEvaluator {
+        pub fn new() -> MaterialEvaluator {
+            return MaterialEvaluator::with_params(EvalParams::default());
+        }
+
+        pub fn with_params(params: EvalParams) -> MaterialEvaluator {
+            return MaterialEvaluator {
+                params
+            }
+        }
+
+        pub fn piece_value(piece: &Piece) -> i32 {
+            return match piece {
+                Piece::Pawn => PAWN_VALUE,
+                Piece::Knight => KNIGHT_VALUE,
+                Piece::Bishop => BISHOP_VALUE,
+                Piece::Rook => ROOK_VALUE,
+                Piece::Queen => QUEEN_VALUE,
+                Piece::King => 0,
+                Piece::Archbishop => ARCHBISHOP_VALUE,
+                Piece::Chancellor => CHANCELLOR_VALUE,
+                Piece::Amazon => AMAZON_VALUE
+            }
+        }
+    }
+
+    impl Evaluator for MaterialEvaluator {
+        fn evaluate(&self, game: &Game) -> i32 {
+            let mut score = 0;
+
+            for row in 0..game.board.num_rows() {
+                for column in 0..game.board.num_columns() {
+                    let position = Position::new(column, row);
+                    let table_position = EvalParams::table_position(game, &position);
+
+                    match game.get_piece_from_position(&position) {
+                        None => (),
+                        Some((piece, color)) => {
+                            let value = self.params.piece_square_value(&piece, &color, &table_position);
+                            match color {
+                                Color::White => score += value,
+                                Color::Black => score -= value
+                            }
+                        }
+                    }
+                }
+            }
+
+            return score;
+        }
+    }
+}
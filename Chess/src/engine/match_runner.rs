@@ -0,0 +1,456 @@
+pub mod match_runner {
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use crate::chess::color::color::Color;
+    use crate::chess::game::game::Game;
+    use crate::chess::piece::piece::Piece;
+    use crate::chess::position::position::Position;
+    use crate::chess::variant::variant::{self, Outcome, Variant};
+    use crate::engine::search::search::{Search, SearchLimits};
+    use crate::engine::tree::game_tree::GameTree;
+    use crate::engine::uci::uci;
+    use crate::engine::zobrist::zobrist;
+
+    // Games that reach this many plies are adjudicated as draws.
+    const MAX_PLIES: usize = 300;
+
+    // Width of the 95% confidence interval in standard errors.
+    const CONFIDENCE: f64 = 1.96;
+
+    #[derive(Debug, Clone, Copy)]
+    pub enum TimeControl {
+        MoveTime(Duration),
+        Nodes(u64),
+        Depth(usize)
+    }
+
+    impl TimeControl {
+        pub fn limits(&self) -> SearchLimits {
+            let mut limits = SearchLimits::new();
+            match self {
+                TimeControl::MoveTime(move_time) => limits.move_time = Some(*move_time),
+                TimeControl::Nodes(nodes) => limits.nodes = Some(*nodes),
+                TimeControl::Depth(depth) => limits.depth = Some(*depth)
+            }
+            return limits;
+        }
+    }
+
+    // An engine configuration taking part in a match, its search should not share a transposition table with the opponent.
+    pub struct MatchPlayer {
+        pub name: String,
+        pub search: Search
+    }
+
+    //
+    // Sequential probability ratio test of H0: the Elo difference is elo0 against
+    // H1: it is elo1. Alpha and beta are the chances of accepting the wrong hypothesis.
+    #[derive(Debug, Clone, Copy)]
+    pub struct SprtOptions {
+        pub elo0: f64,
+        pub elo1: f64,
+        pub alpha: f64,
+        pub beta: f64
+    }
+
+    impl SprtOptions {
+        pub fn default() -> SprtOptions {
+            return SprtOptions {
+                elo0: 0.0,
+                elo1: 5.0,
+                alpha: 0.05,
+                beta: 0.05
+            }
+        }
+
+        fn bounds(&self) -> (f64, f64) {
+            return ((self.beta / (1.0 - self.alpha)).ln(), ((1.0 - self.beta) / self.alpha).ln());
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct MatchOptions {
+        pub games: usize,
+        // Every opening is played twice, once with each engine as white. The start position when empty.
+        pub openings: Vec<String>,
+        pub control: TimeControl,
+        pub max_plies: usize,
+        pub sprt: SprtOptions,
+        // Ends the match as soon as the SPRT accepts a hypothesis.
+        pub stop_on_sprt: bool,
+        pub variant: Arc<dyn Variant>
+    }
+
+    impl MatchOptions {
+        pub fn new(games: usize, control: TimeControl) -> MatchOptions {
+            return MatchOptions {
+                games,
+                openings: vec![],
+                control,
+                max_plies: MAX_PLIES,
+                sprt: SprtOptions::default(),
+                stop_on_sprt: false,
+                variant: variant::standard()
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum GameResult {
+        WhiteWins,
+        BlackWins,
+        Draw
+    }
+
+    impl fmt::Display for GameResult {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match *self {
+                GameResult::WhiteWins => write!(f, "1-0"),
+                GameResult::BlackWins => write!(f, "0-1"),
+                GameResult::Draw => write!(f, "1/2-1/2")
+            }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct GameRecord {
+        pub round: usize,
+        pub white: String,
+        pub black: String,
+        pub variant: Arc<dyn Variant>,
+        pub opening: String,
+        // In SAN.
+        pub moves: Vec<String>,
+        pub result: GameResult,
+        pub reason: String
+    }
+
+    impl GameRecord {
+        pub fn to_pgn(&self) -> String {
+            let mut pgn = String::new();
+            pgn.push_str("[Event \"Engine match\"]\n");
+            pgn.push_str("[Site \"?\"]\n");
+            pgn.push_str("[Date \"????.??.??\"]\n");
+            pgn.push_str(&format!("[Round \"{}\"]\n", self.round));
+            pgn.push_str(&format!("[White \"{}\"]\n", self.white));
+            pgn.push_str(&format!("[Black \"{}\"]\n", self.black));
+            pgn.push_str(&format!("[Result \"{}\"]\n", self.result));
+            if self.variant.name() != variant::standard().name() {
+                pgn.push_str(&format!("[Variant \"{}\"]\n", self.variant.name()));
+            }
+            if self.opening != start_fen(&self.variant) {
+                pgn.push_str("[SetUp \"1\"]\n");
+                pgn.push_str(&format!("[FEN \"{}\"]\n", self.opening));
+            }
+            let termination = if self.reason == "adjudication" { "adjudication" } else { "normal" };
+            pgn.push_str(&format!("[Termination \"{}\"]\n\n", termination));
+
+            let fields: Vec<&str> = self.opening.split_whitespace().collect();
+            let black_starts = fields.get(1) == Some(&"b");
+            // The full move number is the last field, variants may add fields before it.
+            let mut move_number = if fields.len() >= 6 { fields.last().and_then(|number| number.parse::<usize>().ok()).unwrap_or(1) } else { 1 };
+
+            let mut tokens = vec![];
+            for (i, san) in self.moves.iter().enumerate() {
+                let white_move = (i % 2 == 0) != black_starts;
+                if white_move {
+                    tokens.push(format!("{}.", move_number));
+                } else if i == 0 {
+                    tokens.push(format!("{}...", move_number));
+                }
+                tokens.push(san.clone());
+                if !white_move {
+                    move_number += 1;
+                }
+            }
+            tokens.push(format!("{{{}}}", self.reason));
+            tokens.push(self.result.to_string());
+
+            // Movetext lines are kept under 80 characters.
+            let mut line = String::new();
+            for token in tokens {
+                if !line.is_empty() && line.len() + token.len() + 1 > 79 {
+                    pgn.push_str(&line);
+                    pgn.push('\n');
+                    line.clear();
+                }
+                if !line.is_empty() {
+                    line.push(' ');
+                }
+                line.push_str(&token);
+            }
+            pgn.push_str(&line);
+            pgn.push_str("\n\n");
+            return pgn;
+        }
+    }
+
+    // Results from the point of view of the first engine.
+    #[derive(Debug, Clone, Copy)]
+    pub struct MatchScore {
+        pub wins: usize,
+        pub draws: usize,
+        pub losses: usize
+    }
+
+    impl MatchScore {
+        pub fn games(&self) -> usize {
+            return self.wins + self.draws + self.losses;
+        }
+
+        // The average points per game.
+        pub fn score(&self) -> f64 {
+            if self.games() == 0 {
+                return 0.5;
+            }
+            return (self.wins as f64 + self.draws as f64 / 2.0) / self.games() as f64;
+        }
+
+        // Variance of the points of a single game.
+        fn variance(&self) -> f64 {
+            if self.games() == 0 {
+                return 0.0;
+            }
+
+            let score = self.score();
+            let sum = self.wins as f64 * (1.0 - score).powi(2)
+                + self.draws as f64 * (0.5 - score).powi(2)
+                + self.losses as f64 * score.powi(2);
+            return sum / self.games() as f64;
+        }
+
+        // Elo difference and the half width of its 95% confidence interval.
+        pub fn elo(&self) -> (f64, f64) {
+            let score = self.score();
+            let error = CONFIDENCE * (self.variance() / self.games().max(1) as f64).sqrt();
+            let lower = score_to_elo(score - error);
+            let upper = score_to_elo(score + error);
+            return (score_to_elo(score), (upper - lower) / 2.0);
+        }
+
+        //
+        // Log likelihood ratio of the SPRT in the normal approximation, which compares how
+        // close the score is to the expected scores under both hypotheses.
+        pub fn llr(&self, sprt: &SprtOptions) -> f64 {
+            let variance = self.variance();
+            if variance == 0.0 {
+                return 0.0;
+            }
+
+            let score0 = elo_to_score(sprt.elo0);
+            let score1 = elo_to_score(sprt.elo1);
+            return self.games() as f64 * (score1 - score0) * (2.0 * self.score() - score0 - score1) / (2.0 * variance);
+        }
+
+        pub fn verdict(&self, sprt: &SprtOptions) -> SprtVerdict {
+            let llr = self.llr(sprt);
+            let (lower, upper) = sprt.bounds();
+
+            if llr >= upper {
+                return SprtVerdict::AcceptH1;
+            }
+            if llr <= lower {
+                return SprtVerdict::AcceptH0;
+            }
+            return SprtVerdict::Continue;
+        }
+    }
+
+    impl fmt::Display for MatchScore {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "+{} ={} -{}", self.wins, self.draws, self.losses)
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum SprtVerdict {
+        // The Elo difference is elo0 rather than elo1, a patch tested this way fails.
+        AcceptH0,
+        // The Elo difference is elo1 rather than elo0, a patch tested this way passes.
+        AcceptH1,
+        Continue
+    }
+
+    impl fmt::Display for SprtVerdict {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match *self {
+                SprtVerdict::AcceptH0 => write!(f, "H0 accepted"),
+                SprtVerdict::AcceptH1 => write!(f, "H1 accepted"),
+                SprtVerdict::Continue => write!(f, "inconclusive")
+            }
+        }
+    }
+
+    fn score_to_elo(score: f64) -> f64 {
+        let score = score.clamp(0.001, 0.999);
+        return -400.0 * (1.0 / score - 1.0).log10();
+    }
+
+    fn elo_to_score(elo: f64) -> f64 {
+        return 1.0 / (1.0 + 10.0_f64.powf(-elo / 400.0));
+    }
+
+    pub struct MatchResult {
+        pub score: MatchScore,
+        pub games: Vec<GameRecord>,
+        pub llr: f64,
+        pub verdict: SprtVerdict
+    }
+
+    impl MatchResult {
+        pub fn to_pgn(&self) -> String {
+            return self.games.iter().map(|game| game.to_pgn()).collect();
+        }
+    }
+
+    //
+    // Plays first against second. Game 2k and 2k + 1 start from the same opening with the
+    // colours swapped. on_game is called after every game with the score so far.
+    pub fn run_match(first: &MatchPlayer, second: &MatchPlayer, options: &MatchOptions, on_game: &mut dyn FnMut(&GameRecord, &MatchScore)) -> MatchResult {
+        let openings = if options.openings.is_empty() { vec![start_fen(&options.variant)] } else { options.openings.clone() };
+        let mut score = MatchScore { wins: 0, draws: 0, losses: 0 };
+        let mut games = vec![];
+
+        for round in 0..options.games {
+            let opening = &openings[(round / 2) % openings.len()];
+            let first_is_white = round % 2 == 0;
+            let (white, black) = if first_is_white { (first, second) } else { (second, first) };
+
+            let record = play_game(white, black, opening, round + 1, options);
+            match (record.result, first_is_white) {
+                (GameResult::Draw, _) => score.draws += 1,
+                (GameResult::WhiteWins, true) | (GameResult::BlackWins, false) => score.wins += 1,
+                _ => score.losses += 1
+            }
+
+            on_game(&record, &score);
+            games.push(record);
+
+            if options.stop_on_sprt && score.verdict(&options.sprt) != SprtVerdict::Continue {
+                break;
+            }
+        }
+
+        return MatchResult {
+            score,
+            games,
+            llr: score.llr(&options.sprt),
+            verdict: score.verdict(&options.sprt)
+        }
+    }
+
+    fn play_game(white: &MatchPlayer, black: &MatchPlayer, opening: &str, round: usize, options: &MatchOptions) -> GameRecord {
+        let mut record = GameRecord {
+            round,
+            white: white.name.clone(),
+            black: black.name.clone(),
+            variant: options.variant.clone(),
+            opening: opening.to_string(),
+            moves: vec![],
+            result: GameResult::Draw,
+            reason: "adjudication".to_string()
+        };
+
+        let mut game = match uci::game_from_variant_fen(opening, &options.variant) {
+            Some(game) => game,
+            None => {
+                record.reason = "invalid opening".to_string();
+                return record;
+            }
+        };
+
+        white.search.clear();
+        black.search.clear();
+        let limits = options.control.limits();
+        let mut seen: HashMap<u64, usize> = HashMap::new();
+
+        while record.moves.len() < options.max_plies {
+            let repetitions = seen.entry(zobrist::hash(&game)).or_insert(0);
+            *repetitions += 1;
+
+            match adjudicate(&game, *repetitions) {
+                Some((result, reason)) => {
+                    record.result = result;
+                    record.reason = reason.to_string();
+                    return record;
+                },
+                None => ()
+            }
+
+            let player = if game.get_turn() == Color::White { white } else { black };
+            let chess_move = match player.search.run(&game, &limits, &mut |_info| ()).best_move {
+                Some(chess_move) => chess_move,
+                None => {
+                    record.reason = format!("{} returned no move", player.name);
+                    return record;
+                }
+            };
+
+            record.moves.push(chess_move.to_san(&game));
+            game = match GameTree::play(&game, &chess_move) {
+                Some(after_move) => after_move,
+                None => {
+                    record.reason = format!("{} played an illegal move", player.name);
+                    return record;
+                }
+            };
+        }
+
+        return record;
+    }
+
+    // The FEN of the start position of a variant.
+    pub fn start_fen(variant: &Arc<dyn Variant>) -> String {
+        return uci::fen_from_game(&Game::with_variant(variant.clone()));
+    }
+
+    fn game_result(outcome: &Outcome) -> GameResult {
+        return match outcome {
+            Outcome::Win(Color::White) => GameResult::WhiteWins,
+            Outcome::Win(Color::Black) => GameResult::BlackWins,
+            Outcome::Draw => GameResult::Draw
+        }
+    }
+
+    // The result when the game is over, repetitions is how often the position has been seen.
+    pub fn adjudicate(game: &Game, repetitions: usize) -> Option<(GameResult, &'static str)> {
+        match game.variant.outcome(game) {
+            Some(outcome) => return Some((game_result(&outcome), "variant rule")),
+            None => ()
+        }
+        if game.get_all_legal_moves().is_empty() {
+            let reason = if game.is_check(&game.get_turn()) { "checkmate" } else { "stalemate" };
+            return Some((game_result(&game.variant.no_moves_outcome(game)), reason));
+        }
+        if repetitions >= 3 {
+            return Some((GameResult::Draw, "threefold repetition"));
+        }
+        if game.log.half_move_clock() >= 100 {
+            return Some((GameResult::Draw, "fifty move rule"));
+        }
+        if game.variant.dead_material_draws() && is_insufficient_material(game) {
+            return Some((GameResult::Draw, "insufficient material"));
+        }
+
+        return None;
+    }
+
+    // Only kings, or kings and a single bishop or knight.
+    fn is_insufficient_material(game: &Game) -> bool {
+        let mut minors = 0;
+
+        for row in 0..game.board.num_rows() {
+            for column in 0..game.board.num_columns() {
+                match game.get_piece_from_position(&Position::new(column, row)) {
+                    Some((Piece::King, _)) | None => (),
+                    Some((Piece::Bishop, _)) | Some((Piece::Knight, _)) => minors += 1,
+                    Some(_) => return false
+                }
+            }
+        }
+
+        return minors <= 1;
+    }
+}
@@ -0,0 +1,363 @@
+pub mod mate_solver {
+    use std::collections::HashMap;
+    use crate::chess::chess_move::chess_move::ChessMove;
+    use crate::chess::game::game::Game;
+    use crate::engine::tree::game_tree::{GameTree, NodeId, ROOT};
+    use crate::engine::zobrist::zobrist;
+
+    // Mates up to this length are solved depth first, longer ones with proof-number search.
+    const DEPTH_FIRST_MAX: usize = 2;
+
+    // Nodes proof-number search may create for every key move candidate.
+    const PNS_NODE_BUDGET: usize = 100_000;
+
+    const INFINITE: u32 = u32::MAX;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum MateProof {
+        Proven,
+        Disproven,
+        // The node budget ran out before the search was decided.
+        Unknown
+    }
+
+    //
+    // Result of solve_mate. The tree holds every key move, all the defences against it
+    // and one mating answer to each defence, node scores are the number of moves the
+    // attacker still needs to mate from there.
+    pub struct MateSolution {
+        pub n: usize,
+        // Every first move that forces mate in n or fewer.
+        pub key_moves: Vec<ChessMove>,
+        // First moves proof-number search could not decide within its budget.
+        pub undecided: Vec<ChessMove>,
+        pub tree: GameTree,
+        pub nodes: u64
+    }
+
+    impl MateSolution {
+        pub fn is_mate(&self) -> bool {
+            return !self.key_moves.is_empty();
+        }
+
+        // A composition is sound when exactly one key move works and nothing is left undecided.
+        pub fn has_unique_key(&self) -> bool {
+            return self.key_moves.len() == 1 && self.undecided.is_empty();
+        }
+
+        // Length of the fastest mate, 0 when there is none.
+        pub fn mate_in(&self) -> usize {
+            if !self.is_mate() {
+                return 0;
+            }
+
+            return self.tree.node(ROOT).score as usize;
+        }
+    }
+
+    //
+    // Proves or disproves a mate in n for the side to move. Every legal first move is
+    // tried on its own, so puzzles with more than one key move are recognised.
+    pub fn solve(game: &Game, n: usize) -> MateSolution {
+        let mut solver = DepthFirst {
+            proven: HashMap::new(),
+            nodes: 0
+        };
+        let mut solution = MateSolution {
+            n,
+            key_moves: vec![],
+            undecided: vec![],
+            tree: GameTree::new(game.clone()),
+            nodes: 0
+        };
+
+        if n == 0 {
+            return solution;
+        }
+
+        let mut proofs = vec![];
+        for chess_move in game.get_all_legal_moves() {
+            let after_move = match GameTree::play(game, &chess_move) {
+                Some(after_move) => after_move,
+                None => continue
+            };
+
+            if n <= DEPTH_FIRST_MAX {
+                if solver.defender_loses(&after_move, n - 1) {
+                    solution.key_moves.push(chess_move);
+                    proofs.push(None);
+                }
+                continue;
+            }
+
+            let mut search = ProofNumberSearch::new(after_move, false, n - 1);
+            let proof = search.run(PNS_NODE_BUDGET);
+            solution.nodes += search.tree.node_count() as u64;
+
+            match proof {
+                MateProof::Proven => {
+                    solution.key_moves.push(chess_move);
+                    proofs.push(Some(search));
+                },
+                MateProof::Disproven => (),
+                MateProof::Unknown => solution.undecided.push(chess_move)
+            }
+        }
+
+        let added = solution.tree.expand(ROOT, game, &solution.key_moves);
+        let mut fastest = None;
+        for ((child, after_move), proof) in added.into_iter().zip(proofs.iter()) {
+            let mate_in = match proof {
+                Some(search) => search.extract(ROOT, &after_move, &mut solution.tree, child),
+                None => solver.build_defence(&mut solution.tree, child, &after_move, n - 1)
+            };
+
+            let length = mate_in + 1;
+            fastest = Some(fastest.map_or(length, |fastest: usize| fastest.min(length)));
+        }
+
+        solution.tree.set_score(ROOT, fastest.unwrap_or(0) as i32);
+        solution.nodes += solver.nodes;
+        return solution;
+    }
+
+    // Mates are found by trying every attacking move against every defence, with a table of the positions already decided.
+    struct DepthFirst {
+        // Zobrist key and attacking moves left, to whether the attacker mates.
+        proven: HashMap<(u64, usize), bool>,
+        nodes: u64
+    }
+
+    impl DepthFirst {
+        // The attacker is to move and mates within moves_left moves.
+        fn attacker_mates(&mut self, game: &Game, moves_left: usize) -> bool {
+            if moves_left == 0 {
+                return false;
+            }
+
+            let key = (zobrist::hash(game), moves_left);
+            match self.proven.get(&key) {
+                Some(mates) => return *mates,
+                None => ()
+            }
+
+            self.nodes += 1;
+            let mut mates = false;
+            for chess_move in game.get_all_legal_moves() {
+                match GameTree::play(game, &chess_move) {
+                    Some(after_move) => if self.defender_loses(&after_move, moves_left - 1) {
+                        mates = true;
+                        break;
+                    },
+                    None => ()
+                }
+            }
+
+            self.proven.insert(key, mates);
+            return mates;
+        }
+
+        // The defender is to move and every reply still loses within moves_left attacking moves.
+        fn defender_loses(&mut self, game: &Game, moves_left: usize) -> bool {
+            self.nodes += 1;
+            let replies = game.get_all_legal_moves();
+
+            if replies.is_empty() {
+                return game.is_check(&game.get_turn());
+            }
+            if moves_left == 0 {
+                return false;
+            }
+
+            for reply in replies.iter() {
+                match GameTree::play(game, reply) {
+                    Some(after_move) => if !self.attacker_mates(&after_move, moves_left) {
+                        return false;
+                    },
+                    None => ()
+                }
+            }
+
+            return true;
+        }
+
+        // Adds the fastest mating move below an attacker node, returns the moves to mate.
+        fn build_attack(&mut self, tree: &mut GameTree, node: NodeId, game: &Game, moves_left: usize) -> usize {
+            let moves = game.get_all_legal_moves();
+
+            for length in 1..=moves_left {
+                for chess_move in moves.iter() {
+                    let after_move = match GameTree::play(game, chess_move) {
+                        Some(after_move) => after_move,
+                        None => continue
+                    };
+
+                    if self.defender_loses(&after_move, length - 1) {
+                        for (child, after_move) in tree.expand(node, game, &vec![chess_move.clone()]) {
+                            self.build_defence(tree, child, &after_move, length - 1);
+                        }
+                        tree.set_score(node, length as i32);
+                        return length;
+                    }
+                }
+            }
+
+            return 0;
+        }
+
+        // Adds every defence below a defender node, returns the moves to mate against the best one.
+        fn build_defence(&mut self, tree: &mut GameTree, node: NodeId, game: &Game, moves_left: usize) -> usize {
+            let replies = game.get_all_legal_moves();
+            let mut longest = 0;
+
+            for (child, after_move) in tree.expand(node, game, &replies) {
+                longest = longest.max(self.build_attack(tree, child, &after_move, moves_left));
+            }
+
+            tree.set_score(node, longest as i32);
+            return longest;
+        }
+    }
+
+    //
+    // Proof-number search over the moves of both sides. Attacker nodes are OR nodes that
+    // need one mating move, defender nodes are AND nodes where every reply has to lose.
+    // The most proving leaf is expanded until the root is proven or disproven.
+    struct ProofNumberSearch {
+        tree: GameTree,
+        // Proof and disproof numbers of every node.
+        numbers: Vec<(u32, u32)>,
+        parents: Vec<Option<NodeId>>,
+        attacker_to_move: Vec<bool>,
+        // Attacking moves still allowed from the node on.
+        moves_left: Vec<usize>
+    }
+
+    impl ProofNumberSearch {
+        fn new(game: Game, attacker_to_move: bool, moves_left: usize) -> ProofNumberSearch {
+            let numbers = ProofNumberSearch::initial_numbers(&game, attacker_to_move, moves_left);
+
+            return ProofNumberSearch {
+                tree: GameTree::new(game),
+                numbers: vec![numbers],
+                parents: vec![None],
+                attacker_to_move: vec![attacker_to_move],
+                moves_left: vec![moves_left]
+            }
+        }
+
+        fn initial_numbers(game: &Game, attacker_to_move: bool, moves_left: usize) -> (u32, u32) {
+            let moves = game.get_all_legal_moves();
+
+            if moves.is_empty() {
+                // A mated or stalemated attacker has lost the puzzle, a mated defender has lost the game.
+                if !attacker_to_move && game.is_check(&game.get_turn()) {
+                    return (0, INFINITE);
+                }
+                return (INFINITE, 0);
+            }
+
+            if moves_left == 0 {
+                return (INFINITE, 0);
+            }
+
+            return (1, 1);
+        }
+
+        fn run(&mut self, node_budget: usize) -> MateProof {
+            loop {
+                match self.numbers[ROOT] {
+                    (0, _) => return MateProof::Proven,
+                    (_, 0) => return MateProof::Disproven,
+                    _ => ()
+                }
+
+                if self.tree.node_count() >= node_budget {
+                    return MateProof::Unknown;
+                }
+
+                let (node, game) = self.most_proving_node();
+                self.expand(node, &game);
+
+                let mut current = Some(node);
+                while let Some(id) = current {
+                    self.update(id);
+                    current = self.parents[id];
+                }
+            }
+        }
+
+        fn most_proving_node(&self) -> (NodeId, Game) {
+            let mut node = ROOT;
+            let mut game = self.tree.game().clone();
+
+            while !self.tree.is_leaf(node) {
+                let attacker_to_move = self.attacker_to_move[node];
+                let edge = self.tree.children(node).iter()
+                    .min_by_key(|edge| {
+                        let (proof, disproof) = self.numbers[edge.child];
+                        if attacker_to_move { proof } else { disproof }
+                    })
+                    .unwrap();
+
+                game = GameTree::play(&game, &edge.chess_move).unwrap();
+                node = edge.child;
+            }
+
+            return (node, game);
+        }
+
+        fn expand(&mut self, node: NodeId, game: &Game) {
+            let attacker_to_move = self.attacker_to_move[node];
+            let moves_left = if attacker_to_move { self.moves_left[node] - 1 } else { self.moves_left[node] };
+
+            // The tree has no shared nodes, so the children are added in id order.
+            for (_, after_move) in self.tree.expand(node, game, &game.get_all_legal_moves()) {
+                self.numbers.push(ProofNumberSearch::initial_numbers(&after_move, !attacker_to_move, moves_left));
+                self.parents.push(Some(node));
+                self.attacker_to_move.push(!attacker_to_move);
+                self.moves_left.push(moves_left);
+            }
+        }
+
+        fn update(&mut self, node: NodeId) {
+            if self.tree.is_leaf(node) {
+                return;
+            }
+
+            let children: Vec<(u32, u32)> = self.tree.children(node).iter().map(|edge| self.numbers[edge.child]).collect();
+            let min_proof = children.iter().map(|numbers| numbers.0).min().unwrap_or(INFINITE);
+            let min_disproof = children.iter().map(|numbers| numbers.1).min().unwrap_or(INFINITE);
+            let sum_proof = children.iter().fold(0_u32, |sum, numbers| sum.saturating_add(numbers.0));
+            let sum_disproof = children.iter().fold(0_u32, |sum, numbers| sum.saturating_add(numbers.1));
+
+            self.numbers[node] = if self.attacker_to_move[node] {
+                (min_proof, sum_disproof)
+            } else {
+                (sum_proof, min_disproof)
+            };
+        }
+
+        //
+        // Copies the proof below a proven node into the solution tree, one proven move at
+        // attacker nodes and every reply at defender nodes. Returns the moves to mate.
+        fn extract(&self, node: NodeId, game: &Game, out: &mut GameTree, out_node: NodeId) -> usize {
+            let edges: Vec<_> = if self.attacker_to_move[node] {
+                self.tree.children(node).iter().filter(|edge| self.numbers[edge.child].0 == 0).take(1).collect()
+            } else {
+                self.tree.children(node).iter().collect()
+            };
+
+            let moves: Vec<ChessMove> = edges.iter().map(|edge| edge.chess_move.clone()).collect();
+            let mut mate_in = 0;
+
+            for ((out_child, after_move), edge) in out.expand(out_node, game, &moves).into_iter().zip(edges.iter()) {
+                let length = self.extract(edge.child, &after_move, out, out_child);
+                mate_in = mate_in.max(if self.attacker_to_move[node] { length + 1 } else { length });
+            }
+
+            out.set_score(out_node, mate_in as i32);
+            return mate_in;
+        }
+    }
+}
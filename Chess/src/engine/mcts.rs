@@ -0,0 +1,303 @@
+pub mod mcts {
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use crate::chess::chess_move::chess_move::ChessMove;
+    use crate::chess::color::color::Color;
+    use crate::chess::game::game::Game;
+    use crate::engine::engine_moves::engine_moves::EngineMoves;
+    use crate::engine::evaluation::evaluation::{terminal_value, variant_evaluation, Evaluator, MaterialEvaluator, PAWN_VALUE};
+    use crate::engine::search::search::{PvLine, SearchInfo, SearchResult};
+    use crate::engine::skill::skill::SkillRandom;
+    use crate::engine::tree::game_tree::{GameTree, NodeId, ROOT};
+
+    // Iterations between two info reports.
+    const REPORT_INTERVAL: u64 = 500;
+
+    // Playouts are cut off after this many moves by default and scored by the evaluator.
+    const PLAYOUT_DEPTH: usize = 12;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum PlayoutPolicy {
+        // Uniformly random legal moves.
+        Random,
+        // The capture of the most valuable piece when there is one, a random move otherwise.
+        CapturesFirst
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct MctsOptions {
+        // The c in UCT, higher values try more of the less visited moves.
+        pub exploration: f64,
+        // Iterations per search when the limits do not ask for another amount.
+        pub iterations: u64,
+        pub playout: PlayoutPolicy,
+        pub playout_depth: usize
+    }
+
+    impl MctsOptions {
+        pub fn default() -> MctsOptions {
+            return MctsOptions {
+                exploration: 2.0_f64.sqrt(),
+                iterations: 2000,
+                playout: PlayoutPolicy::Random,
+                playout_depth: PLAYOUT_DEPTH
+            }
+        }
+    }
+
+    // Visits and summed results from whites point of view, 1 is a white win and 0 a black win.
+    #[derive(Debug, Clone, Copy)]
+    struct Stats {
+        visits: u32,
+        white_reward: f64
+    }
+
+    //
+    // Monte Carlo tree search with UCT selection. Every iteration walks down the tree by
+    // the UCT formula, expands the leaf it ends on once it has been visited before, plays
+    // the position out with the playout policy and adds the result to every node on the way.
+    // The most visited root move is played.
+    pub struct Mcts {
+        options: MctsOptions,
+        evaluator: Arc<dyn Evaluator>,
+        random: SkillRandom
+    }
+
+    impl Mcts {
+        pub fn new(options: MctsOptions, evaluator: Arc<dyn Evaluator>) -> Mcts {
+            return Mcts {
+                options,
+                evaluator,
+                random: SkillRandom::new()
+            }
+        }
+
+        //
+        // Searches until stop returns true, it is asked before every iteration with the
+        // number of iterations done. Reports the best lines every REPORT_INTERVAL iterations.
+        pub fn run(&mut self, game: &Game, multi_pv: usize, stop: &mut dyn FnMut(u64) -> bool, report: &mut dyn FnMut(&SearchInfo)) -> SearchResult {
+            let start = Instant::now();
+            let mut tree = GameTree::new(game.clone());
+            let mut stats = vec![Stats { visits: 0, white_reward: 0.0 }];
+            let mut iterations = 0;
+
+            let root_moves = game.get_all_legal_moves();
+            if root_moves.is_empty() {
+                let score = if game.get_turn() == Color::White { terminal_value(game) } else { -terminal_value(game) };
+                return self.result(&tree, &stats, multi_pv, 0, score);
+            }
+
+            while !stop(iterations) {
+                self.iterate(&mut tree, &mut stats);
+                iterations += 1;
+
+                if iterations % REPORT_INTERVAL == 0 {
+                    self.report(&tree, &stats, multi_pv, iterations, start.elapsed(), report);
+                }
+            }
+
+            if iterations % REPORT_INTERVAL != 0 {
+                self.report(&tree, &stats, multi_pv, iterations, start.elapsed(), report);
+            }
+            return self.result(&tree, &stats, multi_pv, iterations, 0);
+        }
+
+        fn iterate(&mut self, tree: &mut GameTree, stats: &mut Vec<Stats>) {
+            let mut node = ROOT;
+            let mut game = tree.game().clone();
+            let mut path = vec![ROOT];
+
+            // Selection
+            while !tree.is_leaf(node) {
+                let white_to_move = game.get_turn() == Color::White;
+                let parent_visits = stats[node].visits.max(1) as f64;
+
+                let mut best: Option<(f64, ChessMove, NodeId)> = None;
+                for edge in tree.children(node) {
+                    let child = stats[edge.child];
+                    let uct = if child.visits == 0 {
+                        f64::INFINITY
+                    } else {
+                        let white_score = child.white_reward / child.visits as f64;
+                        let score = if white_to_move { white_score } else { 1.0 - white_score };
+                        score + self.options.exploration * (parent_visits.ln() / child.visits as f64).sqrt()
+                    };
+
+                    match &best {
+                        Some((best_uct, _, _)) if *best_uct >= uct => (),
+                        _ => best = Some((uct, edge.chess_move.clone(), edge.child))
+                    }
+                }
+
+                let (_, chess_move, child) = best.unwrap();
+                game = match GameTree::play(&game, &chess_move) {
+                    Some(after_move) => after_move,
+                    None => break
+                };
+                node = child;
+                path.push(node);
+            }
+
+            // Expansion, a leaf gets its children on its second visit.
+            if node == ROOT || stats[node].visits > 0 {
+                let moves = game.get_all_legal_moves();
+                let added = tree.expand(node, &game, &moves);
+                stats.resize(tree.node_count(), Stats { visits: 0, white_reward: 0.0 });
+
+                match added.into_iter().next() {
+                    Some((child, after_move)) => {
+                        node = child;
+                        game = after_move;
+                        path.push(node);
+                    },
+                    None => ()
+                }
+            }
+
+            // Simulation and backpropagation
+            let reward = self.playout(&game);
+            for node in path {
+                stats[node].visits += 1;
+                stats[node].white_reward += reward;
+            }
+        }
+
+        // Plays the position out and gives the result for white.
+        fn playout(&mut self, game: &Game) -> f64 {
+            let mut game = game.clone();
+
+            for _ in 0..self.options.playout_depth {
+                let moves = game.get_all_legal_moves();
+                if moves.is_empty() {
+                    return terminal_reward(&game);
+                }
+
+                let chess_move = self.pick_move(&moves);
+                game = match GameTree::play(&game, &chess_move) {
+                    Some(after_move) => after_move,
+                    None => break
+                };
+            }
+
+            return win_probability(variant_evaluation(self.evaluator.as_ref(), &game));
+        }
+
+        fn pick_move(&mut self, moves: &Vec<ChessMove>) -> ChessMove {
+            let candidates: Vec<&ChessMove> = match self.options.playout {
+                PlayoutPolicy::Random => moves.iter().collect(),
+                PlayoutPolicy::CapturesFirst => {
+                    let victim_value = |chess_move: &ChessMove| match chess_move.piece_at_position {
+                        Some(piece) => MaterialEvaluator::piece_value(&piece),
+                        None => 0
+                    };
+                    let best_victim = moves.iter().map(victim_value).max().unwrap_or(0);
+
+                    if best_victim > 0 {
+                        moves.iter().filter(|chess_move| victim_value(chess_move) == best_victim).collect()
+                    } else {
+                        moves.iter().collect()
+                    }
+                }
+            };
+
+            let index = (self.random.next() % candidates.len() as u64) as usize;
+            return candidates[index].clone();
+        }
+
+        fn report(&self, tree: &GameTree, stats: &Vec<Stats>, multi_pv: usize, iterations: u64, time: Duration, report: &mut dyn FnMut(&SearchInfo)) {
+            let nps = iterations * 1000 / (time.as_millis() as u64).max(1);
+
+            for (i, line) in self.lines(tree, stats, multi_pv).iter().enumerate() {
+                report(&SearchInfo {
+                    depth: line.pv.moves.len(),
+                    multi_pv: i + 1,
+                    score: line.score,
+                    nodes: iterations,
+                    nps,
+                    time,
+                    pv: line.pv.clone(),
+                    ranks: tree.game().board.num_rows()
+                });
+            }
+        }
+
+        fn result(&self, tree: &GameTree, stats: &Vec<Stats>, multi_pv: usize, iterations: u64, score: i32) -> SearchResult {
+            let lines = self.lines(tree, stats, multi_pv);
+
+            return match lines.first() {
+                Some(best) => SearchResult {
+                    best_move: best.pv.first_move(),
+                    score: best.score,
+                    pv: best.pv.clone(),
+                    depth: best.pv.moves.len(),
+                    lines: lines.clone(),
+                    nodes: iterations
+                },
+                None => SearchResult {
+                    best_move: None,
+                    score,
+                    pv: EngineMoves::new(),
+                    lines: vec![],
+                    depth: 0,
+                    nodes: iterations
+                }
+            }
+        }
+
+        // The most visited root moves, each followed by the most visited line below it.
+        fn lines(&self, tree: &GameTree, stats: &Vec<Stats>, multi_pv: usize) -> Vec<PvLine> {
+            let white_to_move = tree.game().get_turn() == Color::White;
+
+            let mut root_edges: Vec<_> = tree.children(ROOT).iter().filter(|edge| stats[edge.child].visits > 0).collect();
+            root_edges.sort_by(|a, b| stats[b.child].visits.cmp(&stats[a.child].visits));
+
+            let mut lines = vec![];
+            for edge in root_edges.into_iter().take(multi_pv.max(1)) {
+                let child = stats[edge.child];
+                let white_score = child.white_reward / child.visits as f64;
+                let score = if white_to_move { white_score } else { 1.0 - white_score };
+
+                let mut pv = vec![edge.chess_move.clone()];
+                let mut node = edge.child;
+                loop {
+                    let next = tree.children(node).iter()
+                        .filter(|edge| stats[edge.child].visits > 0)
+                        .max_by(|a, b| stats[a.child].visits.cmp(&stats[b.child].visits));
+                    match next {
+                        Some(edge) => {
+                            pv.push(edge.chess_move.clone());
+                            node = edge.child;
+                        },
+                        None => break
+                    }
+                }
+
+                lines.push(PvLine {
+                    score: centipawns(score),
+                    pv: EngineMoves::from_line(&pv)
+                });
+            }
+
+            return lines;
+        }
+    }
+
+    fn terminal_reward(game: &Game) -> f64 {
+        let value = terminal_value(game);
+        if value == 0 {
+            return 0.5;
+        }
+
+        return if value > 0 { 1.0 } else { 0.0 };
+    }
+
+    // Logistic mapping between centipawns and the expected result, 4 pawns is about a 91% score.
+    fn win_probability(score: i32) -> f64 {
+        return 1.0 / (1.0 + 10.0_f64.powf(-score as f64 / (4 * PAWN_VALUE) as f64));
+    }
+
+    fn centipawns(probability: f64) -> i32 {
+        let probability = probability.clamp(0.001, 0.999);
+        return (-(4 * PAWN_VALUE) as f64 * (1.0 / probability - 1.0).log10()).round() as i32;
+    }
+}
@@ -1,3 +1,21 @@
 pub mod engine;
 pub mod tree;
-pub mod engine_moves;
\ No newline at end of file
+pub mod analysis_tree;
+pub mod tree_export;
+pub mod mate_solver;
+pub mod engine_moves;
+pub mod syzygy;
+pub mod endgame_table;
+pub mod evaluation;
+pub mod zobrist;
+pub mod transposition_table;
+pub mod search;
+pub mod uci;
+pub mod skill;
+pub mod mcts;
+pub mod match_runner;
+pub mod epd;
+pub mod tuning;
+pub mod nnue;
+pub mod datagen;
+pub mod perft;
\ No newline at end of file
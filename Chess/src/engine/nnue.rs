@@ -0,0 +1,438 @@
+pub mod nnue {
+    use std::cell::RefCell;
+    use std::fmt;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use crate::chess::color::color::Color;
+    use crate::chess::game::game::Game;
+    use crate::chess::piece::piece::Piece;
+    use crate::engine::evaluation::evaluation::Evaluator;
+    use crate::engine::skill::skill::SkillRandom;
+    use crate::engine::tree::game_tree::GameTree;
+
+    const MAGIC: [u8; 4] = *b"CGNN";
+    const VERSION: u8 = 1;
+
+    // Two colors, six pieces and 64 squares, seen from one side.
+    const FEATURES: usize = 2 * 6 * 64;
+
+    // Quantisation of the clipped accumulator and of the output weights.
+    const QA: i32 = 255;
+    const QB: i32 = 64;
+    // Output units to centipawns.
+    const SCALE: i32 = 400;
+
+    // Every loaded network gets its own id, so an accumulator is never reused for another network.
+    static NEXT_NETWORK_ID: AtomicUsize = AtomicUsize::new(0);
+
+    // With more changed squares than this the accumulator is computed again from scratch.
+    const MAX_CHANGED_SQUARES: usize = 6;
+
+    #[derive(Debug)]
+    pub enum NnueError {
+        Format {
+            path: PathBuf,
+            message: String
+        },
+        Io {
+            path: PathBuf,
+            message: String
+        },
+        Mismatch {
+            ply: usize,
+            incremental: i32,
+            refreshed: i32
+        }
+    }
+
+    impl fmt::Display for NnueError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                NnueError::Format { path, message } =>
+                    write!(f, "Invalid network file {}: {}", path.display(), message),
+                NnueError::Io { path, message } =>
+                    write!(f, "Couldnt access network file {}: {}", path.display(), message),
+                NnueError::Mismatch { ply, incremental, refreshed } =>
+                    write!(f, "Incremental evaluation {} differs from refreshed {} at ply {}", incremental, refreshed, ply)
+            }
+        }
+    }
+
+    impl NnueError {
+        fn format(path: &Path, message: &str) -> NnueError {
+            return NnueError::Format {
+                path: path.to_path_buf(),
+                message: message.to_string()
+            }
+        }
+
+        fn io(path: &Path, err: std::io::Error) -> NnueError {
+            return NnueError::Io {
+                path: path.to_path_buf(),
+                message: err.to_string()
+            }
+        }
+    }
+
+    //
+    // The hidden layer of both sides for one board. cells holds the board the sums were
+    // made for, so the next board only needs the squares that changed.
+    #[derive(Clone, PartialEq)]
+    pub struct Accumulator {
+        cells: Vec<u8>,
+        white: Vec<i16>,
+        black: Vec<i16>
+    }
+
+    //
+    // A 768 to hidden layer shared by both sides, then one output from the clipped hidden layers
+    // of the side to move and the other side. Hidden weights are int16 and output weights int8.
+    pub struct Network {
+        id: usize,
+        hidden: usize,
+        // Hidden weights of one feature after another.
+        feature_weights: Vec<i16>,
+        feature_bias: Vec<i16>,
+        // The side to move first, then the other side.
+        output_weights: Vec<i8>,
+        output_bias: i32
+    }
+
+    impl Network {
+        pub fn hidden_size(&self) -> usize {
+            return self.hidden;
+        }
+
+        //
+        // File layout: magic, version, the hidden size as u32, then the feature weights, the
+        // feature bias as i16, the output weights as i8 and the output bias as i32, all little
+        // endian. Features are ordered by own and other color, piece from pawn to king and square
+        // from a8, black reads the board upside down.
+        pub fn load(path: &Path) -> Result<Network, NnueError> {
+            let data = fs::read(path).map_err(|err| NnueError::io(path, err))?;
+            if data.len() < 9 || data[0..4] != MAGIC {
+                return Err(NnueError::format(path, "not a network"));
+            }
+            if data[4] != VERSION {
+                return Err(NnueError::format(path, &format!("unknown version {}", data[4])));
+            }
+
+            let hidden = u32::from_le_bytes([data[5], data[6], data[7], data[8]]) as usize;
+            if hidden == 0 {
+                return Err(NnueError::format(path, "no hidden neurons"));
+            }
+            let expected = 9 + FEATURES * hidden * 2 + hidden * 2 + hidden * 2 + 4;
+            if data.len() != expected {
+                return Err(NnueError::format(path, &format!("{} bytes instead of {}", data.len(), expected)));
+            }
+
+            let read_i16 = |start: usize, count: usize| -> Vec<i16> {
+                return data[start..start + count * 2].chunks(2).map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]])).collect();
+            };
+
+            let mut position = 9;
+            let feature_weights = read_i16(position, FEATURES * hidden);
+            position += FEATURES * hidden * 2;
+            let feature_bias = read_i16(position, hidden);
+            position += hidden * 2;
+            let output_weights = data[position..position + hidden * 2].iter().map(|byte| *byte as i8).collect();
+            position += hidden * 2;
+            let output_bias = i32::from_le_bytes([data[position], data[position + 1], data[position + 2], data[position + 3]]);
+
+            return Ok(Network {
+                id: NEXT_NETWORK_ID.fetch_add(1, Ordering::Relaxed),
+                hidden,
+                feature_weights,
+                feature_bias,
+                output_weights,
+                output_bias
+            })
+        }
+
+        pub fn supports(game: &Game) -> bool {
+            return game.is_standard_board();
+        }
+
+        fn cells(game: &Game) -> Vec<u8> {
+            let mut cells = Vec::with_capacity(64);
+            for row in 0..8 {
+                for column in 0..8 {
+                    cells.push(*game.board.get(row, column).unwrap_or(&0));
+                }
+            }
+            return cells;
+        }
+
+        fn feature(perspective: &Color, cell: u8, square: usize) -> usize {
+            let piece = Piece::to_u8(Piece::get_piece_enum(cell)) as usize - 1;
+            let color = Color::get_piece_color(cell);
+            let (row, column) = (square / 8, square % 8);
+
+            return match perspective {
+                Color::White => (if color == Color::White { 0 } else { 6 } + piece) * 64 + row * 8 + column,
+                Color::Black => (if color == Color::Black { 0 } else { 6 } + piece) * 64 + (7 - row) * 8 + column
+            }
+        }
+
+        fn add_feature(&self, values: &mut Vec<i16>, feature: usize) {
+            let weights = &self.feature_weights[feature * self.hidden..(feature + 1) * self.hidden];
+            for (value, weight) in values.iter_mut().zip(weights.iter()) {
+                *value = value.wrapping_add(*weight);
+            }
+        }
+
+        fn remove_feature(&self, values: &mut Vec<i16>, feature: usize) {
+            let weights = &self.feature_weights[feature * self.hidden..(feature + 1) * self.hidden];
+            for (value, weight) in values.iter_mut().zip(weights.iter()) {
+                *value = value.wrapping_sub(*weight);
+            }
+        }
+
+        // Sums every piece on the board from scratch.
+        pub fn refresh(&self, game: &Game) -> Accumulator {
+            let cells = Network::cells(game);
+            let mut white = self.feature_bias.clone();
+            let mut black = self.feature_bias.clone();
+
+            for (square, cell) in cells.iter().enumerate() {
+                if *cell != 0 {
+                    self.add_feature(&mut white, Network::feature(&Color::White, *cell, square));
+                    self.add_feature(&mut black, Network::feature(&Color::Black, *cell, square));
+                }
+            }
+
+            return Accumulator {
+                cells,
+                white,
+                black
+            }
+        }
+
+        // Brings the accumulator to the board of game, only the squares that changed are added and removed.
+        pub fn update(&self, accumulator: &mut Accumulator, game: &Game) {
+            let cells = Network::cells(game);
+            let changed: Vec<usize> = (0..64).filter(|square| cells[*square] != accumulator.cells[*square]).collect();
+
+            if changed.len() > MAX_CHANGED_SQUARES {
+                *accumulator = self.refresh(game);
+                return;
+            }
+
+            for square in changed {
+                let old = accumulator.cells[square];
+                if old != 0 {
+                    self.remove_feature(&mut accumulator.white, Network::feature(&Color::White, old, square));
+                    self.remove_feature(&mut accumulator.black, Network::feature(&Color::Black, old, square));
+                }
+                if cells[square] != 0 {
+                    self.add_feature(&mut accumulator.white, Network::feature(&Color::White, cells[square], square));
+                    self.add_feature(&mut accumulator.black, Network::feature(&Color::Black, cells[square], square));
+                }
+            }
+            accumulator.cells = cells;
+        }
+
+        // Centipawns from whites point of view.
+        pub fn output(&self, accumulator: &Accumulator, turn: &Color) -> i32 {
+            let (us, them) = match turn {
+                Color::White => (&accumulator.white, &accumulator.black),
+                Color::Black => (&accumulator.black, &accumulator.white)
+            };
+
+            let sum = output_sum(us, &self.output_weights[..self.hidden])
+                + output_sum(them, &self.output_weights[self.hidden..])
+                + self.output_bias;
+            let score = (sum as i64 * SCALE as i64 / (QA * QB) as i64) as i32;
+
+            return match turn {
+                Color::White => score,
+                Color::Black => -score
+            }
+        }
+
+        pub fn evaluate(&self, game: &Game) -> i32 {
+            return self.output(&self.refresh(game), &game.get_turn());
+        }
+
+        //
+        // Plays plies random legal moves from game, updating one accumulator move by move, and
+        // checks it against a refreshed one after every move. Returns the plies played.
+        pub fn check_updates(&self, game: &Game, plies: usize, seed: u64) -> Result<usize, NnueError> {
+            let mut random = SkillRandom::with_seed(seed);
+            let mut game = game.clone();
+            let mut accumulator = self.refresh(&game);
+
+            for ply in 0..plies {
+                let moves = game.get_all_legal_moves();
+                if moves.is_empty() {
+                    return Ok(ply);
+                }
+                game = match GameTree::play(&game, &moves[(random.next() % moves.len() as u64) as usize]) {
+                    Some(after_move) => after_move,
+                    None => return Ok(ply)
+                };
+
+                self.update(&mut accumulator, &game);
+                let refreshed = self.refresh(&game);
+                if accumulator != refreshed {
+                    return Err(NnueError::Mismatch {
+                        ply: ply + 1,
+                        incremental: self.output(&accumulator, &game.get_turn()),
+                        refreshed: self.output(&refreshed, &game.get_turn())
+                    });
+                }
+            }
+
+            return Ok(plies);
+        }
+    }
+
+    fn output_sum(accumulator: &[i16], weights: &[i8]) -> i32 {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if accumulator.len() % 16 == 0 && is_x86_feature_detected!("avx2") {
+                return unsafe { output_sum_avx2(accumulator, weights) };
+            }
+        }
+
+        return output_sum_scalar(accumulator, weights);
+    }
+
+    fn output_sum_scalar(accumulator: &[i16], weights: &[i8]) -> i32 {
+        return accumulator.iter().zip(weights.iter())
+            .map(|(value, weight)| (*value as i32).clamp(0, QA) * *weight as i32)
+            .sum();
+    }
+
+    // Sixteen neurons at a time, the length of accumulator has to be a multiple of 16.
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2")]
+    unsafe fn output_sum_avx2(accumulator: &[i16], weights: &[i8]) -> i32 {
+        use std::arch::x86_64::*;
+
+        let zero = _mm256_setzero_si256();
+        let limit = _mm256_set1_epi16(QA as i16);
+        let mut sum = _mm256_setzero_si256();
+
+        for start in (0..accumulator.len()).step_by(16) {
+            let values = _mm256_loadu_si256(accumulator.as_ptr().add(start) as *const __m256i);
+            let clipped = _mm256_min_epi16(_mm256_max_epi16(values, zero), limit);
+            let widened = _mm256_cvtepi8_epi16(_mm_loadu_si128(weights.as_ptr().add(start) as *const __m128i));
+            sum = _mm256_add_epi32(sum, _mm256_madd_epi16(clipped, widened));
+        }
+
+        let mut lanes = [0_i32; 8];
+        _mm256_storeu_si256(lanes.as_mut_ptr() as *mut __m256i, sum);
+        return lanes.iter().sum();
+    }
+
+    thread_local! {
+        // The last accumulator of this thread and the network it belongs to, consecutive
+        // positions of a search mostly differ by one move.
+        static LAST_ACCUMULATOR: RefCell<Option<(usize, Accumulator)>> = RefCell::new(None);
+    }
+
+    // Evaluates with the network and leaves boards it was not made for to the fallback.
+    pub struct NnueEvaluator {
+        network: Arc<Network>,
+        fallback: Arc<dyn Evaluator>
+    }
+
+    impl NnueEvaluator {
+        pub fn new(network: Arc<Network>, fallback: Arc<dyn Evaluator>) -> NnueEvaluator {
+            return NnueEvaluator {
+                network,
+                fallback
+            }
+        }
+    }
+
+    impl Evaluator for NnueEvaluator {
+        fn evaluate(&self, game: &Game) -> i32 {
+            if !Network::supports(game) {
+                return self.fallback.evaluate(game);
+            }
+
+            let id = self.network.id;
+            return LAST_ACCUMULATOR.with(|last| {
+                let mut last = last.borrow_mut();
+                match last.as_mut() {
+                    Some((network, accumulator)) if *network == id => self.network.update(accumulator, game),
+                    _ => *last = Some((id, self.network.refresh(game)))
+                }
+
+                let (_, accumulator) = last.as_ref().unwrap();
+                return self.network.output(accumulator, &game.get_turn());
+            });
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::engine::uci::uci;
+
+        // Small weights from a fixed seed, so the sums stay far from the i16 limits.
+        fn network(hidden: usize) -> Network {
+            let mut random = SkillRandom::with_seed(7);
+            let mut weight = |range: u64| -> i64 { (random.next() % (2 * range + 1)) as i64 - range as i64 };
+
+            return Network {
+                id: NEXT_NETWORK_ID.fetch_add(1, Ordering::Relaxed),
+                hidden,
+                feature_weights: (0..FEATURES * hidden).map(|_| weight(64) as i16).collect(),
+                feature_bias: (0..hidden).map(|_| weight(128) as i16).collect(),
+                output_weights: (0..2 * hidden).map(|_| weight(100) as i8).collect(),
+                output_bias: weight(1000) as i32
+            }
+        }
+
+        // The vectorised sum against the plain one, where the machine has AVX2.
+        fn check_output_sums(accumulator: &[i16], weights: &[i8]) {
+            #[cfg(target_arch = "x86_64")]
+            {
+                if accumulator.len() % 16 == 0 && is_x86_feature_detected!("avx2") {
+                    assert_eq!(unsafe { output_sum_avx2(accumulator, weights) }, output_sum_scalar(accumulator, weights));
+                }
+            }
+        }
+
+        #[test]
+        fn output_sums_clamp() {
+            let accumulator: Vec<i16> = (0..32).map(|i| i * 40 - 400).collect();
+            let weights: Vec<i8> = (0..32).map(|i| (i * 9 - 128) as i8).collect();
+            check_output_sums(&accumulator, &weights);
+        }
+
+        //
+        // Captures, en passant on d5e6, a promotion with capture on f7g8q and castling on e1g1.
+        // Both sizes are checked, 16 hidden neurons go through the vectorised output sum.
+        #[test]
+        fn updates_match_refreshes() {
+            let moves = ["e2e4", "d7d5", "e4d5", "e7e5", "d5e6", "b8c6", "e6f7", "e8e7", "f7g8q", "h8g8",
+                "g1f3", "d8d6", "f1e2", "c8g4", "e1g1", "c6d4", "f3d4", "d6d4", "d2d3", "g4e2", "d1e2"];
+
+            for hidden in [5, 16] {
+                let network = network(hidden);
+                let mut game = uci::game_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+                let mut accumulator = network.refresh(&game);
+
+                for uci_move in moves.iter() {
+                    let chess_move = uci::find_move(&game, uci_move).expect(uci_move);
+                    game = GameTree::play(&game, &chess_move).unwrap();
+
+                    network.update(&mut accumulator, &game);
+                    assert!(accumulator == network.refresh(&game), "{}", uci_move);
+                    check_output_sums(&accumulator.white, &network.output_weights[..hidden]);
+                    check_output_sums(&accumulator.black, &network.output_weights[hidden..]);
+                    assert_eq!(network.output(&accumulator, &game.get_turn()), network.evaluate(&game), "{}", uci_move);
+                }
+
+                // Random games from there on.
+                assert!(network.check_updates(&game, 200, 1).is_ok());
+            }
+        }
+    }
+}
@@ -0,0 +1,185 @@
+pub mod perft {
+    use crate::chess::game::game::Game;
+    use crate::chess::variant::variant;
+    use crate::engine::tree::game_tree::GameTree;
+    use crate::engine::uci::uci;
+
+    // A position with the known number of leaves from depth 1 on.
+    pub struct PerftCase {
+        pub variant: &'static str,
+        pub fen: &'static str,
+        pub counts: &'static [u64]
+    }
+
+    //
+    // The standard and Capablanca counts are the published ones. The minichess counts stop
+    // at two plies, which can be counted by hand. In Gardner each of the 7 first moves has 7
+    // replies, a pawn or knight on the third rank blocks the black pawn in front of it and can
+    // be taken by the ones beside it, one more reply after b3, c3, d3 and Nc3. In Los Alamos
+    // the pawns are still apart after one move each.
+    pub const CASES: [PerftCase; 20] = [
+        PerftCase { variant: "chess", fen: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", counts: &[20, 400, 8902, 197281] },
+        PerftCase { variant: "chess", fen: "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", counts: &[48, 2039, 97862] },
+        PerftCase { variant: "chess", fen: "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", counts: &[14, 191, 2812, 43238] },
+        PerftCase { variant: "chess", fen: "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", counts: &[6, 264, 9467] },
+        PerftCase { variant: "chess", fen: "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", counts: &[44, 1486, 62379] },
+        PerftCase { variant: "capablanca", fen: "rnabqkbcnr/pppppppppp/10/10/10/10/PPPPPPPPPP/RNABQKBCNR w KQkq - 0 1", counts: &[28, 784, 25228] },
+        PerftCase { variant: "gardner", fen: "rnbqk/ppppp/5/PPPPP/RNBQK w - - 0 1", counts: &[7, 53] },
+        PerftCase { variant: "losalamos", fen: "rnqknr/pppppp/6/6/PPPPPP/RNQKNR w - - 0 1", counts: &[10, 100] },
+        PerftCase { variant: "crazyhouse", fen: "2k5/8/8/8/8/8/8/4K3[Qn] w - - 0 1", counts: &[67, 3083, 88634, 932554] },
+        PerftCase { variant: "crazyhouse", fen: "r1bqk2r/pppp1ppp/2n1p3/4P3/1b1Pn3/2NB1N2/PPP2PPP/R1BQK2R[] b KQkq - 0 1", counts: &[42, 1347, 58057] },
+        PerftCase { variant: "crazyhouse", fen: "4k3/1Q~6/8/8/4b3/8/Kpp5/8[] b - - 0 1", counts: &[20, 360, 5445, 132758] },
+        PerftCase { variant: "crazyhouse", fen: "2k5/8/8/8/8/8/8/4K3[QRBNPqrbnp] w - - 0 1", counts: &[301, 75353] },
+        PerftCase { variant: "atomic", fen: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", counts: &[20, 400, 8902, 197326] },
+        PerftCase { variant: "atomic", fen: "rn2kb1r/1pp1p2p/p2q1pp1/3P4/2P3b1/4PN2/PP3PPP/R2QKB1R b KQkq - 0 1", counts: &[40, 1238, 45237] },
+        PerftCase { variant: "atomic", fen: "rn1qkb1r/p5pp/2p5/3p4/N3P3/5P2/PPP4P/R1BQK3 w Qkq - 0 1", counts: &[28, 833, 23353] },
+        PerftCase { variant: "atomic", fen: "8/8/8/8/8/8/2k5/rR4KR w KQ - 0 1", counts: &[18, 180, 4364, 61401] },
+        PerftCase { variant: "atomic", fen: "r3k1rR/5K2/8/8/8/8/8/8 b kq - 0 1", counts: &[25, 282, 6753] },
+        PerftCase { variant: "antichess", fen: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1", counts: &[20, 400, 8067, 153299] },
+        PerftCase { variant: "antichess", fen: "8/1p6/8/8/8/8/P7/8 w - - 0 1", counts: &[2, 4, 4, 3, 1, 0] },
+        PerftCase { variant: "antichess", fen: "8/2p5/8/8/8/8/P7/8 w - - 0 1", counts: &[2, 4, 4, 4, 4, 4, 4, 4, 12, 36, 312, 2557, 30873] }
+    ];
+
+    //
+    // Chess960 start positions from the published fischer.epd, in Shredder and X-FEN castling.
+    // The first is the standard start, the others have the rooks next to the king.
+    pub const CHESS960_CASES: [PerftCase; 4] = [
+        PerftCase { variant: "chess", fen: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1", counts: &[20, 400, 8902, 197281] },
+        PerftCase { variant: "chess", fen: "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9", counts: &[21, 528, 12189, 326672] },
+        PerftCase { variant: "chess", fen: "rkr1bqnn/1ppp1p1p/p5p1/4p3/3PP2b/2P2P2/PP4PP/RKRBBQNN w CAca - 0 9", counts: &[31, 1004, 32006, 1006830] },
+        PerftCase { variant: "chess", fen: "rkb1nnrb/1pppq1pp/p4p2/4p3/5P2/1P1PB3/P1P1P1PP/RK1QNNRB w KQkq - 0 9", counts: &[26, 625, 17050, 442036] }
+    ];
+
+    // The number of move sequences depth plies long from game.
+    pub fn perft(game: &Game, depth: usize) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        let moves = game.get_all_legal_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+
+        let mut nodes = 0;
+        for chess_move in moves.iter() {
+            match GameTree::play(game, chess_move) {
+                Some(after_move) => nodes += perft(&after_move, depth - 1),
+                None => ()
+            }
+        }
+        return nodes;
+    }
+
+    // Compares the cases with their known counts, depths with more than max_nodes leaves are skipped.
+    pub fn check_cases(max_nodes: u64) -> Vec<String> {
+        return CASES.iter().chain(CHESS960_CASES.iter()).flat_map(|case| check_case(case, max_nodes)).collect();
+    }
+
+    fn check_case(case: &PerftCase, max_nodes: u64) -> Vec<String> {
+        let game = match variant::from_name(case.variant).and_then(|game_variant| uci::game_from_variant_fen(case.fen, &game_variant)) {
+            Some(game) => game,
+            None => return vec![format!("{} {}: couldnt set up the position", case.variant, case.fen)]
+        };
+
+        let mut problems = vec![];
+        for (index, expected) in case.counts.iter().enumerate() {
+            if *expected > max_nodes {
+                break;
+            }
+            let nodes = perft(&game, index + 1);
+            if nodes != *expected {
+                problems.push(format!("{} {}: depth {} gives {} instead of {}", case.variant, case.fen, index + 1, nodes, expected));
+            }
+        }
+
+        return problems;
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::chess::color::color::Color;
+        use crate::chess::variant::variant::Outcome;
+
+        // Small enough for a debug build.
+        const MAX_NODES: u64 = 60000;
+
+        fn check_variant(name: &str) {
+            let problems: Vec<String> = CASES.iter()
+                .filter(|case| case.variant == name)
+                .flat_map(|case| check_case(case, MAX_NODES))
+                .collect();
+            assert!(problems.is_empty(), "{}", problems.join("\n"));
+        }
+
+        #[test]
+        fn standard() {
+            check_variant("chess");
+        }
+
+        #[test]
+        fn chess960() {
+            let problems: Vec<String> = CHESS960_CASES.iter().flat_map(|case| check_case(case, MAX_NODES)).collect();
+            assert!(problems.is_empty(), "{}", problems.join("\n"));
+        }
+
+        #[test]
+        fn capablanca() {
+            check_variant("capablanca");
+        }
+
+        #[test]
+        fn antichess() {
+            check_variant("antichess");
+        }
+
+        #[test]
+        fn antichess_stalemated_side_wins() {
+            let antichess = variant::from_name("antichess").unwrap();
+            let game = uci::game_from_variant_fen("8/8/8/8/8/p7/P7/8 w - - 0 1", &antichess).unwrap();
+
+            assert!(matches!(game.outcome(), Some(Outcome::Win(Color::White))));
+        }
+
+        #[test]
+        fn atomic() {
+            check_variant("atomic");
+        }
+
+        #[test]
+        fn atomic_exploding_the_king_wins() {
+            let atomic = variant::from_name("atomic").unwrap();
+            let game = uci::game_from_variant_fen("4k3/4p3/8/8/8/8/8/4QK2 w - - 0 1", &atomic).unwrap();
+            let capture = uci::find_move(&game, "e1e7").unwrap();
+            let after_capture = GameTree::play(&game, &capture).unwrap();
+
+            assert!(matches!(after_capture.outcome(), Some(Outcome::Win(Color::White))));
+        }
+
+        #[test]
+        fn crazyhouse() {
+            check_variant("crazyhouse");
+        }
+
+        #[test]
+        fn crazyhouse_promoted_piece_goes_back_as_a_pawn() {
+            let crazyhouse = variant::from_name("crazyhouse").unwrap();
+            let game = uci::game_from_variant_fen("4k3/1Q~6/8/8/4b3/8/Kpp5/8[] b - - 0 1", &crazyhouse).unwrap();
+            let capture = uci::find_move(&game, "e4b7").unwrap();
+            let after_capture = GameTree::play(&game, &capture).unwrap();
+
+            assert_eq!(after_capture.pockets_to_fen(), "p");
+        }
+
+        #[test]
+        fn gardner() {
+            check_variant("gardner");
+        }
+
+        #[test]
+        fn los_alamos() {
+            check_variant("losalamos");
+        }
+    }
+}
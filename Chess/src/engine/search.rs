@@ -0,0 +1,946 @@
+pub mod search {
+    use std::fmt;
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+    use std::thread;
+    use std::thread::JoinHandle;
+    use std::time::{Duration, Instant};
+    use crate::chess::chess_move::chess_move::{ChessMove, MoveType};
+    use crate::chess::color::color::Color;
+    use crate::chess::game::game::Game;
+    use crate::chess::variant::variant::Outcome;
+    use crate::engine::engine_moves::engine_moves::EngineMoves;
+    use crate::engine::evaluation::evaluation::{variant_evaluation, Evaluator, MaterialEvaluator};
+    use crate::engine::endgame_table::endgame_table::{Dtm, EndgameTables};
+    use crate::engine::mcts::mcts::{Mcts, MctsOptions};
+    use crate::engine::skill::skill::{MAX_ELO, MAX_SKILL_LEVEL, Skill, SkillRandom};
+    use crate::engine::syzygy::syzygy::{Tablebase, TB_WIN_VALUE, Wdl};
+    use crate::engine::transposition_table::transposition_table::{Bound, TableEntry, TranspositionTable};
+    use crate::engine::zobrist::zobrist;
+
+    pub const MATE_VALUE: i32 = 30000;
+    pub const INFINITY: i32 = 32000;
+    pub const MAX_DEPTH: usize = 64;
+    const MAX_PLY: usize = 64;
+
+    // How often, in nodes, a thread looks at the clock and the node limit.
+    const CHECK_INTERVAL: u64 = 1024;
+
+    //
+    // A line of MAX_PLY plies takes less than 64 KB, but a thread that hashes first makes the
+    // 64 KB zobrist key table on its stack, and debug builds copy it several times there.
+    const THREAD_STACK_SIZE: usize = 4 * 1024 * 1024;
+
+    //
+    // Depth staggering for the helper threads. Helper i skips the depths where
+    // (depth + SKIP_PHASE[i]) / SKIP_SIZE[i] is odd, so the threads spread out over
+    // different depths instead of all searching the same tree.
+    const SKIP_SIZE: [usize; 20] = [1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 3, 3, 4, 4, 4, 4, 4, 4, 4, 4];
+    const SKIP_PHASE: [usize; 20] = [0, 1, 0, 1, 2, 3, 0, 1, 2, 3, 4, 5, 0, 1, 2, 3, 4, 5, 6, 7];
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Backend {
+        AlphaBeta,
+        // Monte Carlo tree search, single threaded and without skill levels.
+        Mcts
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct EngineOptions {
+        pub threads: usize,
+        // Size of the transposition table in megabytes.
+        pub hash_size: usize,
+        // Number of best root moves to search and report.
+        pub multi_pv: usize,
+        pub skill_level: u8,
+        // When set the strength comes from elo instead of skill_level.
+        pub limit_strength: bool,
+        pub elo: u32,
+        pub backend: Backend,
+        pub mcts: MctsOptions
+    }
+
+    impl EngineOptions {
+        pub fn default() -> EngineOptions {
+            return EngineOptions {
+                threads: 1,
+                hash_size: 16,
+                multi_pv: 1,
+                skill_level: MAX_SKILL_LEVEL,
+                limit_strength: false,
+                elo: MAX_ELO,
+                backend: Backend::AlphaBeta,
+                mcts: MctsOptions::default()
+            }
+        }
+
+        pub fn skill(&self) -> Skill {
+            if self.limit_strength {
+                return Skill::from_elo(self.elo);
+            }
+
+            return Skill::new(self.skill_level);
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct SearchLimits {
+        pub depth: Option<usize>,
+        pub nodes: Option<u64>,
+        pub move_time: Option<Duration>,
+        pub infinite: bool,
+        // Searching on the opponents time, the limits only start counting at the ponder hit.
+        pub ponder: bool
+    }
+
+    impl SearchLimits {
+        pub fn new() -> SearchLimits {
+            return SearchLimits {
+                depth: None,
+                nodes: None,
+                move_time: None,
+                infinite: false,
+                ponder: false
+            }
+        }
+
+        pub fn depth(depth: usize) -> SearchLimits {
+            let mut limits = SearchLimits::new();
+            limits.depth = Some(depth);
+            return limits;
+        }
+    }
+
+    // Progress of the main thread after a finished iteration, nodes are summed over all threads.
+    #[derive(Clone)]
+    pub struct SearchInfo {
+        pub depth: usize,
+        // Rank of the line, 1 is the best.
+        pub multi_pv: usize,
+        pub score: i32,
+        pub nodes: u64,
+        pub nps: u64,
+        pub time: Duration,
+        pub pv: EngineMoves,
+        // Ranks of the searched board, for the square names of the pv.
+        pub ranks: usize
+    }
+
+    impl fmt::Display for SearchInfo {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "info depth {} multipv {} score {} nodes {} nps {} time {} pv {}",
+                   self.depth, self.multi_pv, format_score(self.score), self.nodes, self.nps, self.time.as_millis(), self.pv.to_uci_string(self.ranks))
+        }
+    }
+
+    // Score as written by the protocol, either centipawns or moves to mate.
+    pub fn format_score(score: i32) -> String {
+        if score > MATE_VALUE - MAX_PLY as i32 {
+            return format!("mate {}", (MATE_VALUE - score + 1) / 2);
+        }
+        if score < -MATE_VALUE + MAX_PLY as i32 {
+            return format!("mate -{}", (MATE_VALUE + score) / 2);
+        }
+
+        return format!("cp {}", score);
+    }
+
+    // A root move with its score and the line the search expects to follow it.
+    #[derive(Clone)]
+    pub struct PvLine {
+        // From the side to move.
+        pub score: i32,
+        pub pv: EngineMoves
+    }
+
+    #[derive(Clone)]
+    pub struct SearchResult {
+        pub best_move: Option<ChessMove>,
+        // From the side to move.
+        pub score: i32,
+        pub pv: EngineMoves,
+        // The best root moves sorted best first, as many as the multi_pv option asks for.
+        pub lines: Vec<PvLine>,
+        pub depth: usize,
+        pub nodes: u64
+    }
+
+    impl SearchResult {
+        // The reply the search expects, the move to ponder on.
+        pub fn ponder_move(&self) -> Option<ChessMove> {
+            let line = self.pv.line();
+            return line.get(1).cloned();
+        }
+    }
+
+    //
+    // Iterative deepening alpha-beta search. With more than one thread it runs as Lazy SMP:
+    // every thread searches the same root, they only share the transposition table and the
+    // stop signal. The main thread reports the progress and the result.
+    #[derive(Clone)]
+    pub struct Search {
+        options: EngineOptions,
+        table: Arc<TranspositionTable>,
+        stop: Arc<AtomicBool>,
+        pondering: Arc<AtomicBool>,
+        // When the last ponder hit came in, the time limit is measured from there.
+        ponder_hit_at: Arc<Mutex<Option<Instant>>>,
+        evaluator: Arc<dyn Evaluator>,
+        tablebase: Option<Arc<Tablebase>>,
+        endgame_tables: Option<Arc<EndgameTables>>
+    }
+
+    impl Search {
+        pub fn new(options: EngineOptions) -> Search {
+            return Search::with_evaluator(options, Arc::new(MaterialEvaluator::new()));
+        }
+
+        pub fn with_evaluator(options: EngineOptions, evaluator: Arc<dyn Evaluator>) -> Search {
+            return Search {
+                table: Arc::new(TranspositionTable::new(options.hash_size)),
+                options,
+                stop: Arc::new(AtomicBool::new(false)),
+                pondering: Arc::new(AtomicBool::new(false)),
+                ponder_hit_at: Arc::new(Mutex::new(None)),
+                evaluator,
+                tablebase: None,
+                endgame_tables: None
+            }
+        }
+
+        pub fn options(&self) -> &EngineOptions {
+            return &self.options;
+        }
+
+        pub fn set_threads(&mut self, threads: usize) {
+            self.options.threads = threads.max(1);
+        }
+
+        pub fn set_multi_pv(&mut self, multi_pv: usize) {
+            self.options.multi_pv = multi_pv.max(1);
+        }
+
+        pub fn set_skill_level(&mut self, skill_level: u8) {
+            self.options.skill_level = skill_level.min(MAX_SKILL_LEVEL);
+        }
+
+        pub fn set_limit_strength(&mut self, limit_strength: bool) {
+            self.options.limit_strength = limit_strength;
+        }
+
+        pub fn set_elo(&mut self, elo: u32) {
+            self.options.elo = elo;
+        }
+
+        pub fn set_hash_size(&mut self, hash_size: usize) {
+            self.options.hash_size = hash_size;
+            self.table = Arc::new(TranspositionTable::new(hash_size));
+        }
+
+        pub fn set_backend(&mut self, backend: Backend) {
+            self.options.backend = backend;
+        }
+
+        pub fn set_mcts(&mut self, mcts: MctsOptions) {
+            self.options.mcts = mcts;
+        }
+
+        pub fn evaluator(&self) -> Arc<dyn Evaluator> {
+            return self.evaluator.clone();
+        }
+
+        pub fn set_evaluator(&mut self, evaluator: Arc<dyn Evaluator>) {
+            self.evaluator = evaluator;
+        }
+
+        pub fn set_tablebase(&mut self, tablebase: Arc<Tablebase>) {
+            self.tablebase = Some(tablebase);
+        }
+
+        pub fn clear_tablebase(&mut self) {
+            self.tablebase = None;
+        }
+
+        pub fn set_endgame_tables(&mut self, endgame_tables: Arc<EndgameTables>) {
+            self.endgame_tables = Some(endgame_tables);
+        }
+
+        // Forgets everything learned, used between games.
+        pub fn clear(&self) {
+            self.table.clear();
+        }
+
+        pub fn stop(&self) {
+            self.stop.store(true, Ordering::SeqCst);
+        }
+
+        pub fn run(&self, game: &Game, limits: &SearchLimits, report: &mut dyn FnMut(&SearchInfo)) -> SearchResult {
+            self.stop.store(false, Ordering::SeqCst);
+            self.pondering.store(limits.ponder, Ordering::SeqCst);
+            *self.ponder_hit_at.lock().unwrap() = None;
+            return self.search(game, limits, report);
+        }
+
+        //
+        // Starts searching the position after the expected reply while the opponent thinks.
+        // The search keeps going until ponder_hit or stop is called, it never returns on its own
+        // before that.
+        pub fn ponder<F, G>(&self, game: Game, limits: SearchLimits, on_info: F, on_done: G) -> JoinHandle<()>
+            where F: FnMut(&SearchInfo) + Send + 'static, G: FnOnce(SearchResult) + Send + 'static
+        {
+            let mut limits = limits;
+            limits.ponder = true;
+            return self.start(game, limits, on_info, on_done);
+        }
+
+        // The opponent played the expected move, the running search turns into a normal one
+        // and keeps everything it found so far.
+        pub fn ponder_hit(&self) {
+            *self.ponder_hit_at.lock().unwrap() = Some(Instant::now());
+            self.pondering.store(false, Ordering::SeqCst);
+        }
+
+        // The opponent played something else, the ponder search is stopped and its result is of no use.
+        pub fn ponder_miss(&self) {
+            self.stop();
+        }
+
+        pub fn is_pondering(&self) -> bool {
+            return self.pondering.load(Ordering::SeqCst);
+        }
+
+        // Runs the search on its own thread, so it can be stopped while it is running.
+        pub fn start<F, G>(&self, game: Game, limits: SearchLimits, mut on_info: F, on_done: G) -> JoinHandle<()>
+            where F: FnMut(&SearchInfo) + Send + 'static, G: FnOnce(SearchResult) + Send + 'static
+        {
+            self.stop.store(false, Ordering::SeqCst);
+            self.pondering.store(limits.ponder, Ordering::SeqCst);
+            *self.ponder_hit_at.lock().unwrap() = None;
+            let search = self.clone();
+
+            return thread::Builder::new()
+                .stack_size(THREAD_STACK_SIZE)
+                .spawn(move || {
+                    let result = search.search(&game, &limits, &mut on_info);
+                    on_done(result);
+                })
+                .expect("Couldnt start the search thread.");
+        }
+
+        fn search(&self, game: &Game, limits: &SearchLimits, report: &mut dyn FnMut(&SearchInfo)) -> SearchResult {
+            if self.options.backend == Backend::Mcts {
+                return self.search_mcts(game, limits, report);
+            }
+
+            let start = Instant::now();
+            let threads = self.options.threads.max(1);
+            let node_counters: Vec<AtomicU64> = (0..threads).map(|_| AtomicU64::new(0)).collect();
+
+            // A weakened engine searches shallower, with a noisy evaluation and more lines to pick from.
+            let skill = self.options.skill();
+            let mut random = SkillRandom::new();
+            let noise_seed = random.next();
+
+            let mut limits = limits.clone();
+            match skill.depth_limit() {
+                Some(depth_limit) => limits.depth = Some(limits.depth.unwrap_or(depth_limit).min(depth_limit)),
+                None => ()
+            }
+            let limits = &limits;
+            let multi_pv = self.options.multi_pv.max(skill.multi_pv());
+
+            let result = thread::scope(|scope| {
+                for id in 1..threads {
+                    let node_counters = &node_counters;
+                    thread::Builder::new()
+                        .stack_size(THREAD_STACK_SIZE)
+                        .spawn_scoped(scope, move || {
+                            let mut worker = Worker::new(id, self, limits, node_counters, start);
+                            worker.noise = (skill.noise(), noise_seed);
+                            worker.iterate(game, None);
+                        })
+                        .expect("Couldnt start a helper search thread.");
+                }
+
+                let mut main_worker = Worker::new(0, self, limits, &node_counters, start);
+                main_worker.multi_pv = multi_pv;
+                main_worker.noise = (skill.noise(), noise_seed);
+                let result = main_worker.iterate(game, Some(report));
+
+                // An infinite or pondering search only returns when it is told to.
+                while (limits.infinite || self.is_pondering()) && !self.stop.load(Ordering::SeqCst) {
+                    thread::sleep(Duration::from_millis(1));
+                }
+
+                self.stop.store(true, Ordering::SeqCst);
+                result
+            });
+
+            let mut result = result;
+            result.nodes = total_nodes(&node_counters);
+
+            if skill.enabled() && result.lines.len() > 1 {
+                let line = result.lines[skill.pick_line(&result.lines, &mut random)].clone();
+                result.best_move = line.pv.first_move().or(result.best_move);
+                result.score = line.score;
+                result.pv = line.pv;
+            }
+
+            return result;
+        }
+
+        // Without a node limit the search runs for the iterations in the options, the depth limit is not used.
+        fn search_mcts(&self, game: &Game, limits: &SearchLimits, report: &mut dyn FnMut(&SearchInfo)) -> SearchResult {
+            let start = Instant::now();
+            let mut limits = limits.clone();
+            if limits.nodes.is_none() && !limits.infinite {
+                limits.nodes = Some(self.options.mcts.iterations);
+            }
+
+            let mut mcts = Mcts::new(self.options.mcts.clone(), self.evaluator.clone());
+            let result = mcts.run(game, self.options.multi_pv, &mut |iterations| self.limits_reached(&limits, start, iterations), report);
+
+            while (limits.infinite || self.is_pondering()) && !self.stop.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_millis(1));
+            }
+
+            self.stop.store(true, Ordering::SeqCst);
+            return result;
+        }
+
+        //
+        // Raises the stop signal when the node or time limit is reached and tells whether the
+        // search has to stop. Limits are not counted while pondering.
+        fn limits_reached(&self, limits: &SearchLimits, start: Instant, nodes: u64) -> bool {
+            let pondering = self.is_pondering();
+
+            let out_of_nodes = match limits.nodes {
+                Some(max_nodes) => !pondering && nodes >= max_nodes,
+                None => false
+            };
+            let out_of_time = match limits.move_time {
+                Some(move_time) => !limits.infinite && !pondering && self.clock_start(start).elapsed() >= move_time,
+                None => false
+            };
+
+            if out_of_nodes || out_of_time {
+                self.stop();
+            }
+
+            return self.stop.load(Ordering::Relaxed);
+        }
+
+        // The time limit counts from the start, or from the ponder hit when pondering.
+        fn clock_start(&self, start: Instant) -> Instant {
+            return match *self.ponder_hit_at.lock().unwrap() {
+                Some(ponder_hit_at) => ponder_hit_at,
+                None => start
+            }
+        }
+    }
+
+    fn total_nodes(node_counters: &Vec<AtomicU64>) -> u64 {
+        return node_counters.iter().map(|counter| counter.load(Ordering::Relaxed)).sum();
+    }
+
+    fn play(game: &Game, chess_move: &ChessMove) -> Game {
+        let mut new_game = game.clone();
+        let mut after_move = new_game.move_piece(&new_game.get_turn(), chess_move).unwrap();
+        after_move.turn += 1;
+        return after_move;
+    }
+
+    // A finished game from the side to move, wins found sooner score higher.
+    fn outcome_score(game: &Game, outcome: &Outcome, ply: usize) -> i32 {
+        return match outcome {
+            Outcome::Win(color) if *color == game.get_turn() => MATE_VALUE - ply as i32,
+            Outcome::Win(_) => -MATE_VALUE + ply as i32,
+            Outcome::Draw => 0
+        }
+    }
+
+    // Mate scores are stored relative to the node, so they stay right when found through another path.
+    fn score_to_table(score: i32, ply: usize) -> i32 {
+        if score > MATE_VALUE - MAX_PLY as i32 {
+            return score + ply as i32;
+        }
+        if score < -MATE_VALUE + MAX_PLY as i32 {
+            return score - ply as i32;
+        }
+        return score;
+    }
+
+    fn score_from_table(score: i32, ply: usize) -> i32 {
+        if score > MATE_VALUE - MAX_PLY as i32 {
+            return score - ply as i32;
+        }
+        if score < -MATE_VALUE + MAX_PLY as i32 {
+            return score + ply as i32;
+        }
+        return score;
+    }
+
+    struct Worker<'a> {
+        id: usize,
+        search: &'a Search,
+        limits: &'a SearchLimits,
+        node_counters: &'a Vec<AtomicU64>,
+        start: Instant,
+        nodes: u64,
+        // The moves the root is searched with, in a tablebase position only the best ones.
+        root_moves: Vec<ChessMove>,
+        // Root moves already taken by a better line in this iteration.
+        excluded_root_moves: Vec<String>,
+        multi_pv: usize,
+        // Amplitude and seed of the evaluation noise of a weakened engine.
+        noise: (i32, u64)
+    }
+
+    impl<'a> Worker<'a> {
+        fn new(id: usize, search: &'a Search, limits: &'a SearchLimits, node_counters: &'a Vec<AtomicU64>, start: Instant) -> Worker<'a> {
+            return Worker {
+                id,
+                search,
+                limits,
+                node_counters,
+                start,
+                nodes: 0,
+                root_moves: vec![],
+                excluded_root_moves: vec![],
+                multi_pv: 1,
+                noise: (0, 0)
+            }
+        }
+
+        fn iterate(&mut self, game: &Game, mut report: Option<&mut dyn FnMut(&SearchInfo)>) -> SearchResult {
+            let root_moves = self.tablebase_root_moves(game).unwrap_or(game.get_all_legal_moves());
+            self.root_moves = root_moves.clone();
+
+            let mut result = SearchResult {
+                best_move: root_moves.first().cloned(),
+                score: 0,
+                pv: EngineMoves::new(),
+                lines: vec![],
+                depth: 0,
+                nodes: 0
+            };
+
+            if root_moves.len() == 0 {
+                result.score = outcome_score(game, &game.outcome().unwrap_or(Outcome::Draw), 0);
+                return result;
+            }
+
+            let max_depth = self.limits.depth.unwrap_or(MAX_DEPTH);
+
+            let multi_pv = self.multi_pv.max(1).min(root_moves.len());
+
+            'deepening: for depth in 1..=max_depth {
+                if self.id > 0 {
+                    let i = (self.id - 1) % SKIP_SIZE.len();
+                    if ((depth + SKIP_PHASE[i]) / SKIP_SIZE[i]) % 2 == 1 {
+                        continue;
+                    }
+                }
+
+                // Every following line is searched without the root moves of the better lines.
+                let mut lines = vec![];
+                self.excluded_root_moves.clear();
+
+                for _ in 0..multi_pv {
+                    let mut pv = vec![];
+                    let score = self.negamax(game, depth, 0, -INFINITY, INFINITY, &mut pv);
+
+                    // Results of an unfinished iteration are not trusted.
+                    if self.stopped() {
+                        break 'deepening;
+                    }
+
+                    self.complete_pv(game, &mut pv, depth);
+
+                    match pv.first() {
+                        Some(chess_move) => self.excluded_root_moves.push(chess_move.to_uci(game.board.num_rows())),
+                        None => break
+                    }
+
+                    lines.push(PvLine {
+                        score,
+                        pv: EngineMoves::from_line(&pv)
+                    });
+                }
+
+                lines.sort_by_key(|line| -line.score);
+
+                result.best_move = lines[0].pv.first_move().or(result.best_move);
+                result.score = lines[0].score;
+                result.pv = lines[0].pv.clone();
+                result.lines = lines;
+                result.depth = depth;
+
+                match report.as_mut() {
+                    Some(report) => {
+                        let nodes = total_nodes(self.node_counters);
+                        let time = self.start.elapsed();
+                        let nps = nodes * 1000 / (time.as_millis() as u64).max(1);
+
+                        for (i, line) in result.lines.iter().enumerate() {
+                            report(&SearchInfo {
+                                depth,
+                                multi_pv: i + 1,
+                                score: line.score,
+                                nodes,
+                                nps,
+                                time,
+                                pv: line.pv.clone(),
+                                ranks: game.board.num_rows()
+                            });
+                        }
+                    },
+                    None => ()
+                }
+            }
+
+            return result;
+        }
+
+        // Lines cut short by a transposition table hit are followed further through the table.
+        fn complete_pv(&self, game: &Game, pv: &mut Vec<ChessMove>, depth: usize) {
+            let mut position = game.clone();
+            for chess_move in pv.iter() {
+                position = play(&position, chess_move);
+            }
+
+            while pv.len() < depth {
+                let entry = match self.search.table.probe(zobrist::hash(&position)) {
+                    Some(entry) => entry,
+                    None => break
+                };
+
+                match position.get_all_legal_moves().into_iter().find(|chess_move| entry.matches(chess_move)) {
+                    Some(chess_move) => {
+                        position = play(&position, &chess_move);
+                        pv.push(chess_move);
+                    },
+                    None => break
+                }
+            }
+        }
+
+        fn stopped(&self) -> bool {
+            return self.search.stop.load(Ordering::Relaxed);
+        }
+
+        // Counts the node and every so often checks the limits, any thread can raise the stop signal.
+        fn visit_node(&mut self) -> bool {
+            self.nodes += 1;
+            self.node_counters[self.id].store(self.nodes, Ordering::Relaxed);
+
+            if self.nodes % CHECK_INTERVAL == 0 {
+                return self.search.limits_reached(self.limits, self.start, total_nodes(self.node_counters));
+            }
+
+            return self.stopped();
+        }
+
+        // Evaluation from the side to move.
+        fn evaluate(&self, game: &Game) -> i32 {
+            let mut score = variant_evaluation(self.search.evaluator.as_ref(), game);
+
+            // The noise depends on the position, so the same position always gets the same score.
+            let (noise, seed) = self.noise;
+            if noise > 0 {
+                let mut random = SkillRandom::with_seed(zobrist::hash(game) ^ seed);
+                score += (random.next() % (2 * noise as u64 + 1)) as i32 - noise;
+            }
+
+            return match game.get_turn() {
+                Color::White => score,
+                Color::Black => -score
+            }
+        }
+
+        //
+        // The root moves that keep the best tablebase result under the fifty-move rule. Every
+        // winning move scores the same in the search, only the dtz makes progress towards the win.
+        fn tablebase_root_moves(&self, game: &Game) -> Option<Vec<ChessMove>> {
+            return match &self.search.tablebase {
+                Some(tablebase) if tablebase.can_probe(game) => match tablebase.best_root_moves(game) {
+                    Ok(moves) if moves.len() > 0 => Some(moves),
+                    _ => None
+                },
+                _ => None
+            }
+        }
+
+        fn probe_tablebase(&self, game: &Game, ply: usize) -> Option<i32> {
+            match self.probe_endgame_tables(game, ply) {
+                Some(score) => return Some(score),
+                None => ()
+            }
+
+            let tablebase = match &self.search.tablebase {
+                Some(tablebase) => tablebase,
+                None => return None
+            };
+
+            if !tablebase.can_probe(game) {
+                return None;
+            }
+
+            return match tablebase.probe_wdl(game) {
+                Ok(Wdl::Win) => Some(TB_WIN_VALUE - ply as i32),
+                Ok(Wdl::Loss) => Some(-TB_WIN_VALUE + ply as i32),
+                Ok(wdl) => Some(wdl as i32),
+                Err(_) => None
+            }
+        }
+
+        //
+        // Exact mate scores from the generated tables. Mates that end beyond MAX_PLY would
+        // look like normal scores, they are scored like tablebase wins that are won faster
+        // the shorter they are.
+        fn probe_endgame_tables(&self, game: &Game, ply: usize) -> Option<i32> {
+            let endgame_tables = match &self.search.endgame_tables {
+                Some(endgame_tables) if endgame_tables.can_probe(game) => endgame_tables,
+                _ => return None
+            };
+
+            let mate_score = |plies: u32| {
+                let plies = ply as i32 + plies as i32;
+                if plies < MAX_PLY as i32 { MATE_VALUE - plies } else { TB_WIN_VALUE - plies }
+            };
+
+            return match endgame_tables.probe(game) {
+                Ok(Dtm::Win(plies)) => Some(mate_score(plies)),
+                Ok(Dtm::Loss(plies)) => Some(-mate_score(plies)),
+                Ok(Dtm::Draw) => Some(0),
+                Err(_) => None
+            }
+        }
+
+        fn order_moves(moves: &mut Vec<ChessMove>, entry: &Option<TableEntry>) {
+            moves.sort_by_key(|chess_move| {
+                let hash_move = match entry {
+                    Some(entry) => entry.matches(chess_move),
+                    None => false
+                };
+
+                let priority = if hash_move {
+                    1_000_000
+                } else if chess_move.is_capture() {
+                    let victim = match chess_move.piece_at_position {
+                        Some(piece) => piece.to_u8() as i32,
+                        None => 1
+                    };
+                    10_000 + victim * 10 - chess_move.piece.to_u8() as i32
+                } else {
+                    match chess_move.move_type {
+                        MoveType::Promote(_) => 9_000,
+                        _ => 0
+                    }
+                };
+
+                -priority
+            });
+        }
+
+        fn negamax(&mut self, game: &Game, depth: usize, ply: usize, mut alpha: i32, beta: i32, pv: &mut Vec<ChessMove>) -> i32 {
+            pv.clear();
+
+            if self.visit_node() {
+                return 0;
+            }
+
+            if ply > 0 && game.log.half_move_clock() >= 100 {
+                return 0;
+            }
+
+            if ply >= MAX_PLY {
+                return self.evaluate(game);
+            }
+
+            match game.variant.outcome(game) {
+                Some(outcome) => return outcome_score(game, &outcome, ply),
+                None => ()
+            }
+
+            let hash = zobrist::hash(game);
+            let entry = self.search.table.probe(hash);
+
+            match entry {
+                Some(entry) if ply > 0 && entry.depth as usize >= depth => {
+                    let score = score_from_table(entry.score, ply);
+                    match entry.bound {
+                        Bound::Exact => return score,
+                        Bound::Lower if score >= beta => return score,
+                        Bound::Upper if score <= alpha => return score,
+                        _ => ()
+                    }
+                },
+                _ => ()
+            }
+
+            if ply > 0 {
+                match self.probe_tablebase(game, ply) {
+                    Some(score) => return score,
+                    None => ()
+                }
+            }
+
+            if depth == 0 {
+                return self.quiescence(game, ply, alpha, beta);
+            }
+
+            let mut moves = game.get_all_legal_moves();
+            if moves.len() == 0 {
+                return outcome_score(game, &game.variant.no_moves_outcome(game), ply);
+            }
+
+            if ply == 0 && self.root_moves.len() > 0 {
+                moves.retain(|chess_move| self.root_moves.contains(chess_move));
+            }
+            if ply == 0 && self.excluded_root_moves.len() > 0 {
+                moves.retain(|chess_move| !self.excluded_root_moves.contains(&chess_move.to_uci(game.board.num_rows())));
+            }
+
+            Self::order_moves(&mut moves, &entry);
+
+            let original_alpha = alpha;
+            let mut best_score = -INFINITY;
+            let mut best_move: Option<ChessMove> = None;
+            let mut child_pv = vec![];
+
+            for chess_move in moves.iter() {
+                let after_move = play(game, chess_move);
+                let score = -self.negamax(&after_move, depth - 1, ply + 1, -beta, -alpha, &mut child_pv);
+
+                if self.stopped() {
+                    return 0;
+                }
+
+                if score > best_score {
+                    best_score = score;
+                    best_move = Some(chess_move.clone());
+
+                    if score > alpha {
+                        alpha = score;
+                        pv.clear();
+                        pv.push(chess_move.clone());
+                        pv.append(&mut child_pv);
+                    }
+                }
+
+                if alpha >= beta {
+                    break;
+                }
+            }
+
+            let bound = if best_score >= beta {
+                Bound::Lower
+            } else if best_score > original_alpha {
+                Bound::Exact
+            } else {
+                Bound::Upper
+            };
+
+            let best_move = match best_move {
+                Some(chess_move) => {
+                    let promotion = match chess_move.move_type {
+                        MoveType::Promote(piece) | MoveType::Drop(piece) => Some(piece),
+                        _ => None
+                    };
+                    Some((chess_move.from, chess_move.to, promotion))
+                },
+                None => None
+            };
+
+            // A root searched without some of its moves does not have the real score.
+            if ply == 0 && self.excluded_root_moves.len() > 0 {
+                return best_score;
+            }
+
+            self.search.table.store(hash, TableEntry {
+                score: score_to_table(best_score, ply),
+                depth: depth as u8,
+                bound,
+                best_move
+            });
+
+            return best_score;
+        }
+
+        // Only captures are searched, so the evaluation is not taken in the middle of an exchange.
+        fn quiescence(&mut self, game: &Game, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+            if self.visit_node() {
+                return 0;
+            }
+
+            match game.variant.outcome(game) {
+                Some(outcome) => return outcome_score(game, &outcome, ply),
+                None => ()
+            }
+
+            let stand_pat = self.evaluate(game);
+            if ply >= MAX_PLY || stand_pat >= beta {
+                return stand_pat;
+            }
+            if stand_pat > alpha {
+                alpha = stand_pat;
+            }
+
+            let mut captures: Vec<ChessMove> = game.get_all_turn_available_moves()
+                .into_iter()
+                .filter(|chess_move| chess_move.is_capture())
+                .collect();
+
+            Self::order_moves(&mut captures, &None);
+
+            for chess_move in captures.iter() {
+                if !game.variant.is_legal(game, chess_move) {
+                    continue;
+                }
+
+                let after_move = play(game, chess_move);
+                let score = -self.quiescence(&after_move, ply + 1, -beta, -alpha);
+
+                if self.stopped() {
+                    return 0;
+                }
+
+                if score >= beta {
+                    return score;
+                }
+                if score > alpha {
+                    alpha = score;
+                }
+            }
+
+            return alpha;
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::path::PathBuf;
+        use super::*;
+        use crate::engine::tree::game_tree::GameTree;
+        use crate::engine::uci::uci;
+
+        // Every rook move that keeps the win scores the same, the search has to take one that brings the dtz down.
+        #[test]
+        fn converts_tablebase_wins() {
+            let mut tablebase = Tablebase::new();
+            tablebase.add_directory(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("syzygy")).unwrap();
+            let tablebase = Arc::new(tablebase);
+
+            let mut search = Search::new(EngineOptions::default());
+            search.set_tablebase(tablebase.clone());
+
+            let game = uci::game_from_fen("8/8/8/2R5/1K6/8/5k2/8 w - - 0 1").unwrap();
+            assert_eq!(tablebase.probe_dtz(&game).unwrap(), 21);
+
+            let result = search.run(&game, &SearchLimits::depth(4), &mut |_info| ());
+            let after_move = GameTree::play(&game, &result.best_move.unwrap()).unwrap();
+            assert_eq!(tablebase.probe_dtz(&after_move).unwrap(), -20);
+        }
+    }
+}
@@ -0,0 +1,121 @@
+pub mod skill {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use crate::engine::evaluation::evaluation::PAWN_VALUE;
+    use crate::engine::search::search::PvLine;
+
+    pub const MAX_SKILL_LEVEL: u8 = 20;
+    pub const MIN_ELO: u32 = 800;
+    pub const MAX_ELO: u32 = 2800;
+
+    // Lines searched when the engine is weakened, so it has worse moves to choose from.
+    const SKILL_MULTI_PV: usize = 4;
+
+    //
+    // Weakens the engine in three ways: the search depth is capped, the evaluation gets
+    // noise, and the played move is picked among the best lines with a bias towards the
+    // better ones. Level 20 is full strength. Every level is roughly 100 Elo, from 800 at
+    // level 0 up to 2800 at level 20, the scale is an estimate and not measured.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct Skill {
+        level: u8
+    }
+
+    impl Skill {
+        pub fn new(level: u8) -> Skill {
+            return Skill {
+                level: level.min(MAX_SKILL_LEVEL)
+            }
+        }
+
+        pub fn from_elo(elo: u32) -> Skill {
+            let elo = elo.clamp(MIN_ELO, MAX_ELO);
+            let level = ((elo - MIN_ELO) as f64 / (MAX_ELO - MIN_ELO) as f64 * MAX_SKILL_LEVEL as f64).round();
+            return Skill::new(level as u8);
+        }
+
+        pub fn elo(&self) -> u32 {
+            return MIN_ELO + (MAX_ELO - MIN_ELO) * self.level as u32 / MAX_SKILL_LEVEL as u32;
+        }
+
+        pub fn enabled(&self) -> bool {
+            return self.level < MAX_SKILL_LEVEL;
+        }
+
+        pub fn depth_limit(&self) -> Option<usize> {
+            if !self.enabled() {
+                return None;
+            }
+
+            return Some(1 + self.level as usize / 3);
+        }
+
+        // Largest amount of centipawns added to or taken from an evaluation.
+        pub fn noise(&self) -> i32 {
+            return (MAX_SKILL_LEVEL - self.level) as i32 * 10;
+        }
+
+        pub fn multi_pv(&self) -> usize {
+            if !self.enabled() {
+                return 1;
+            }
+
+            return SKILL_MULTI_PV;
+        }
+
+        //
+        // Picks the index of the line to play. Every line gets a random push that is larger
+        // for weaker levels, lines close to the best one need less push to be chosen.
+        pub fn pick_line(&self, lines: &Vec<PvLine>, random: &mut SkillRandom) -> usize {
+            if !self.enabled() || lines.len() < 2 {
+                return 0;
+            }
+
+            let top_score = lines[0].score;
+            let weakness = 120 - 2 * self.level as i32;
+            let delta = (top_score - lines[lines.len() - 1].score).min(PAWN_VALUE);
+
+            let mut best_index = 0;
+            let mut max_score = i32::MIN;
+
+            for (i, line) in lines.iter().enumerate() {
+                let push = (weakness * (top_score - line.score) + delta * (random.next() % weakness as u64) as i32) / 128;
+
+                if line.score + push >= max_score {
+                    max_score = line.score + push;
+                    best_index = i;
+                }
+            }
+
+            return best_index;
+        }
+    }
+
+    // Small xorshift generator, seeded from the clock so games differ.
+    pub struct SkillRandom {
+        state: u64
+    }
+
+    impl SkillRandom {
+        pub fn new() -> SkillRandom {
+            let seed = match SystemTime::now().duration_since(UNIX_EPOCH) {
+                Ok(duration) => duration.as_nanos() as u64,
+                Err(_) => 0
+            };
+
+            return SkillRandom::with_seed(seed);
+        }
+
+        pub fn with_seed(seed: u64) -> SkillRandom {
+            return SkillRandom {
+                state: seed | 1
+            }
+        }
+
+        pub fn next(&mut self) -> u64 {
+            self.state ^= self.state << 13;
+            self.state ^= self.state >> 7;
+            self.state ^= self.state << 17;
+            return self.state;
+        }
+    }
+}
@@ -0,0 +1,1920 @@
+pub mod syzygy {
+    use std::collections::HashMap;
+    use std::{fmt, fs, io};
+    use std::path::{Path, PathBuf};
+    use std::sync::OnceLock;
+    use crate::chess::chess_move::chess_move::{ChessMove, MoveType};
+    use crate::chess::color::color::Color;
+    use crate::chess::game::game::Game;
+    use crate::chess::piece::piece::Piece;
+    use crate::chess::position::position::Position;
+
+    // Leaf value used for positions the tablebase reports as won, from whites point of view.
+    pub const TB_WIN_VALUE: i32 = 20000;
+
+    const MAX_DTZ: i32 = 1000;
+    const MAX_PIECES: usize = 7;
+
+    const WDL_MAGIC: [u8; 4] = [0x71, 0xe8, 0x23, 0x5d];
+    const DTZ_MAGIC: [u8; 4] = [0xd7, 0x66, 0x0c, 0xa5];
+
+    // Subtable flags
+    const FLAG_STM: u8 = 1;
+    const FLAG_MAPPED: u8 = 2;
+    const FLAG_WIN_PLIES: u8 = 4;
+    const FLAG_LOSS_PLIES: u8 = 8;
+    const FLAG_WIDE_DTZ: u8 = 16;
+    const FLAG_SINGLE_VALUE: u8 = 128;
+
+    // Piece codes as they are stored in the table headers, black pieces have the fourth bit set.
+    const TB_PAWN: u8 = 1;
+    const TB_KNIGHT: u8 = 2;
+    const TB_BISHOP: u8 = 3;
+    const TB_ROOK: u8 = 4;
+    const TB_QUEEN: u8 = 5;
+    const TB_KING: u8 = 6;
+    const TB_BLACK: u8 = 8;
+
+    const Z0: u64 = u64::MAX;
+
+    // Maps squares into the a1-d1-d4 triangle.
+    const TRIANGLE: [u64; 64] = [
+        6, 0, 1, 2, 2, 1, 0, 6,
+        0, 7, 3, 4, 4, 3, 7, 0,
+        1, 3, 8, 5, 5, 8, 3, 1,
+        2, 4, 5, 9, 9, 5, 4, 2,
+        2, 4, 5, 9, 9, 5, 4, 2,
+        1, 3, 8, 5, 5, 8, 3, 1,
+        0, 7, 3, 4, 4, 3, 7, 0,
+        6, 0, 1, 2, 2, 1, 0, 6,
+    ];
+
+    const INV_TRIANGLE: [usize; 10] = [1, 2, 3, 10, 11, 19, 0, 9, 18, 27];
+
+    // Maps the b1-h1-h7 triangle to 0..=27.
+    const LOWER: [u64; 64] = [
+        28,  0,  1,  2,  3,  4,  5,  6,
+         0, 29,  7,  8,  9, 10, 11, 12,
+         1,  7, 30, 13, 14, 15, 16, 17,
+         2,  8, 13, 31, 18, 19, 20, 21,
+         3,  9, 14, 18, 32, 22, 23, 24,
+         4, 10, 15, 19, 22, 33, 25, 26,
+         5, 11, 16, 20, 23, 25, 34, 27,
+         6, 12, 17, 21, 24, 26, 27, 35,
+    ];
+
+    const MULT_TWIST: [u64; 64] = [
+        15, 63, 55, 47, 40, 48, 56, 12,
+        62, 11, 39, 31, 24, 32,  8, 57,
+        54, 38,  7, 23, 16,  4, 33, 49,
+        46, 30, 22,  3,  0, 17, 25, 41,
+        45, 29, 21,  2,  1, 18, 26, 42,
+        53, 37,  6, 20, 19,  5, 34, 50,
+        61, 10, 36, 28, 27, 35,  9, 58,
+        14, 60, 52, 44, 43, 51, 59, 13,
+    ];
+
+    // a5, b5, c5, a6, b6 and a7
+    const TEST45: [usize; 6] = [32, 33, 34, 40, 41, 48];
+
+    // Encoding of the 462 configurations of two kings that do not touch.
+    const KK_IDX: [[u64; 64]; 10] = [[
+         Z0,  Z0,  Z0,   0,   1,   2,   3,   4,
+         Z0,  Z0,  Z0,   5,   6,   7,   8,   9,
+         10,  11,  12,  13,  14,  15,  16,  17,
+         18,  19,  20,  21,  22,  23,  24,  25,
+         26,  27,  28,  29,  30,  31,  32,  33,
+         34,  35,  36,  37,  38,  39,  40,  41,
+         42,  43,  44,  45,  46,  47,  48,  49,
+         50,  51,  52,  53,  54,  55,  56,  57,
+    ], [
+         58,  Z0,  Z0,  Z0,  59,  60,  61,  62,
+         63,  Z0,  Z0,  Z0,  64,  65,  66,  67,
+         68,  69,  70,  71,  72,  73,  74,  75,
+         76,  77,  78,  79,  80,  81,  82,  83,
+         84,  85,  86,  87,  88,  89,  90,  91,
+         92,  93,  94,  95,  96,  97,  98,  99,
+        100, 101, 102, 103, 104, 105, 106, 107,
+        108, 109, 110, 111, 112, 113, 114, 115,
+    ], [
+        116, 117,  Z0,  Z0,  Z0, 118, 119, 120,
+        121, 122,  Z0,  Z0,  Z0, 123, 124, 125,
+        126, 127, 128, 129, 130, 131, 132, 133,
+        134, 135, 136, 137, 138, 139, 140, 141,
+        142, 143, 144, 145, 146, 147, 148, 149,
+        150, 151, 152, 153, 154, 155, 156, 157,
+        158, 159, 160, 161, 162, 163, 164, 165,
+        166, 167, 168, 169, 170, 171, 172, 173,
+    ], [
+        174,  Z0,  Z0,  Z0, 175, 176, 177, 178,
+        179,  Z0,  Z0,  Z0, 180, 181, 182, 183,
+        184,  Z0,  Z0,  Z0, 185, 186, 187, 188,
+        189, 190, 191, 192, 193, 194, 195, 196,
+        197, 198, 199, 200, 201, 202, 203, 204,
+        205, 206, 207, 208, 209, 210, 211, 212,
+        213, 214, 215, 216, 217, 218, 219, 220,
+        221, 222, 223, 224, 225, 226, 227, 228,
+    ], [
+        229, 230,  Z0,  Z0,  Z0, 231, 232, 233,
+        234, 235,  Z0,  Z0,  Z0, 236, 237, 238,
+        239, 240,  Z0,  Z0,  Z0, 241, 242, 243,
+        244, 245, 246, 247, 248, 249, 250, 251,
+        252, 253, 254, 255, 256, 257, 258, 259,
+        260, 261, 262, 263, 264, 265, 266, 267,
+        268, 269, 270, 271, 272, 273, 274, 275,
+        276, 277, 278, 279, 280, 281, 282, 283,
+    ], [
+        284, 285, 286, 287, 288, 289, 290, 291,
+        292, 293,  Z0,  Z0,  Z0, 294, 295, 296,
+        297, 298,  Z0,  Z0,  Z0, 299, 300, 301,
+        302, 303,  Z0,  Z0,  Z0, 304, 305, 306,
+        307, 308, 309, 310, 311, 312, 313, 314,
+        315, 316, 317, 318, 319, 320, 321, 322,
+        323, 324, 325, 326, 327, 328, 329, 330,
+        331, 332, 333, 334, 335, 336, 337, 338,
+    ], [
+         Z0,  Z0, 339, 340, 341, 342, 343, 344,
+         Z0,  Z0, 345, 346, 347, 348, 349, 350,
+         Z0,  Z0, 441, 351, 352, 353, 354, 355,
+         Z0,  Z0,  Z0, 442, 356, 357, 358, 359,
+         Z0,  Z0,  Z0,  Z0, 443, 360, 361, 362,
+         Z0,  Z0,  Z0,  Z0,  Z0, 444, 363, 364,
+         Z0,  Z0,  Z0,  Z0,  Z0,  Z0, 445, 365,
+         Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0, 446,
+    ], [
+         Z0,  Z0,  Z0, 366, 367, 368, 369, 370,
+         Z0,  Z0,  Z0, 371, 372, 373, 374, 375,
+         Z0,  Z0,  Z0, 376, 377, 378, 379, 380,
+         Z0,  Z0,  Z0, 447, 381, 382, 383, 384,
+         Z0,  Z0,  Z0,  Z0, 448, 385, 386, 387,
+         Z0,  Z0,  Z0,  Z0,  Z0, 449, 388, 389,
+         Z0,  Z0,  Z0,  Z0,  Z0,  Z0, 450, 390,
+         Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0, 451,
+    ], [
+        452, 391, 392, 393, 394, 395, 396, 397,
+         Z0,  Z0,  Z0,  Z0, 398, 399, 400, 401,
+         Z0,  Z0,  Z0,  Z0, 402, 403, 404, 405,
+         Z0,  Z0,  Z0,  Z0, 406, 407, 408, 409,
+         Z0,  Z0,  Z0,  Z0, 453, 410, 411, 412,
+         Z0,  Z0,  Z0,  Z0,  Z0, 454, 413, 414,
+         Z0,  Z0,  Z0,  Z0,  Z0,  Z0, 455, 415,
+         Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0, 456,
+    ], [
+        457, 416, 417, 418, 419, 420, 421, 422,
+         Z0, 458, 423, 424, 425, 426, 427, 428,
+         Z0,  Z0,  Z0,  Z0,  Z0, 429, 430, 431,
+         Z0,  Z0,  Z0,  Z0,  Z0, 432, 433, 434,
+         Z0,  Z0,  Z0,  Z0,  Z0, 435, 436, 437,
+         Z0,  Z0,  Z0,  Z0,  Z0, 459, 438, 439,
+         Z0,  Z0,  Z0,  Z0,  Z0,  Z0, 460, 440,
+         Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0, 461,
+    ]];
+
+    // Encoding of a pair of identical pieces.
+    const PP_IDX: [[u64; 64]; 10] = [[
+          0,  Z0,   1,   2,   3,   4,   5,   6,
+          7,   8,   9,  10,  11,  12,  13,  14,
+         15,  16,  17,  18,  19,  20,  21,  22,
+         23,  24,  25,  26,  27,  28,  29,  30,
+         31,  32,  33,  34,  35,  36,  37,  38,
+         39,  40,  41,  42,  43,  44,  45,  46,
+         Z0,  47,  48,  49,  50,  51,  52,  53,
+         54,  55,  56,  57,  58,  59,  60,  61,
+    ], [
+         62,  Z0,  Z0,  63,  64,  65,  Z0,  66,
+         Z0,  67,  68,  69,  70,  71,  72,  Z0,
+         73,  74,  75,  76,  77,  78,  79,  80,
+         81,  82,  83,  84,  85,  86,  87,  88,
+         89,  90,  91,  92,  93,  94,  95,  96,
+         Z0,  97,  98,  99, 100, 101, 102, 103,
+         Z0, 104, 105, 106, 107, 108, 109,  Z0,
+        110,  Z0, 111, 112, 113, 114,  Z0, 115,
+    ], [
+        116,  Z0,  Z0,  Z0, 117,  Z0,  Z0, 118,
+         Z0, 119, 120, 121, 122, 123, 124,  Z0,
+         Z0, 125, 126, 127, 128, 129, 130,  Z0,
+        131, 132, 133, 134, 135, 136, 137, 138,
+         Z0, 139, 140, 141, 142, 143, 144, 145,
+         Z0, 146, 147, 148, 149, 150, 151,  Z0,
+         Z0, 152, 153, 154, 155, 156, 157,  Z0,
+        158,  Z0,  Z0, 159, 160,  Z0,  Z0, 161,
+    ], [
+        162,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0, 163,
+         Z0, 164,  Z0, 165, 166, 167, 168,  Z0,
+         Z0, 169, 170, 171, 172, 173, 174,  Z0,
+         Z0, 175, 176, 177, 178, 179, 180,  Z0,
+         Z0, 181, 182, 183, 184, 185, 186,  Z0,
+         Z0,  Z0, 187, 188, 189, 190, 191,  Z0,
+         Z0, 192, 193, 194, 195, 196, 197,  Z0,
+        198,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0, 199,
+    ], [
+        200,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0, 201,
+         Z0, 202,  Z0,  Z0, 203,  Z0, 204,  Z0,
+         Z0,  Z0, 205, 206, 207, 208,  Z0,  Z0,
+         Z0, 209, 210, 211, 212, 213, 214,  Z0,
+         Z0,  Z0, 215, 216, 217, 218, 219,  Z0,
+         Z0,  Z0, 220, 221, 222, 223,  Z0,  Z0,
+         Z0, 224,  Z0, 225, 226,  Z0, 227,  Z0,
+        228,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0, 229,
+    ], [
+        230,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0, 231,
+         Z0, 232,  Z0,  Z0,  Z0,  Z0, 233,  Z0,
+         Z0,  Z0, 234,  Z0, 235, 236,  Z0,  Z0,
+         Z0,  Z0, 237, 238, 239, 240,  Z0,  Z0,
+         Z0,  Z0,  Z0, 241, 242, 243,  Z0,  Z0,
+         Z0,  Z0, 244, 245, 246, 247,  Z0,  Z0,
+         Z0, 248,  Z0,  Z0,  Z0,  Z0, 249,  Z0,
+        250,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0, 251,
+    ], [
+         Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0, 259,
+         Z0, 252,  Z0,  Z0,  Z0,  Z0, 260,  Z0,
+         Z0,  Z0, 253,  Z0,  Z0, 261,  Z0,  Z0,
+         Z0,  Z0,  Z0, 254, 262,  Z0,  Z0,  Z0,
+         Z0,  Z0,  Z0,  Z0, 255,  Z0,  Z0,  Z0,
+         Z0,  Z0,  Z0,  Z0,  Z0, 256,  Z0,  Z0,
+         Z0,  Z0,  Z0,  Z0,  Z0,  Z0, 257,  Z0,
+         Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0, 258,
+    ], [
+         Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0,
+         Z0,  Z0,  Z0,  Z0,  Z0,  Z0, 268,  Z0,
+         Z0,  Z0, 263,  Z0,  Z0, 269,  Z0,  Z0,
+         Z0,  Z0,  Z0, 264, 270,  Z0,  Z0,  Z0,
+         Z0,  Z0,  Z0,  Z0, 265,  Z0,  Z0,  Z0,
+         Z0,  Z0,  Z0,  Z0,  Z0, 266,  Z0,  Z0,
+         Z0,  Z0,  Z0,  Z0,  Z0,  Z0, 267,  Z0,
+         Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0,
+    ], [
+         Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0,
+         Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0,
+         Z0,  Z0,  Z0,  Z0,  Z0, 274,  Z0,  Z0,
+         Z0,  Z0,  Z0, 271, 275,  Z0,  Z0,  Z0,
+         Z0,  Z0,  Z0,  Z0, 272,  Z0,  Z0,  Z0,
+         Z0,  Z0,  Z0,  Z0,  Z0, 273,  Z0,  Z0,
+         Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0,
+         Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0,
+    ], [
+         Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0,
+         Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0,
+         Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0,
+         Z0,  Z0,  Z0,  Z0, 277,  Z0,  Z0,  Z0,
+         Z0,  Z0,  Z0,  Z0, 276,  Z0,  Z0,  Z0,
+         Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0,
+         Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0,
+         Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0,  Z0,
+    ]];
+
+    //
+    // Loss: the side to move loses
+    // BlessedLoss: the side to move loses, but can hold a draw with the fifty-move rule
+    // Draw: draw
+    // CursedWin: the side to move wins, but the fifty-move rule saves the opponent
+    // Win: the side to move wins
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+    pub enum Wdl {
+        Loss = -2,
+        BlessedLoss = -1,
+        Draw = 0,
+        CursedWin = 1,
+        Win = 2
+    }
+
+    impl Wdl {
+        fn from_table(value: u16) -> Result<Wdl, TablebaseError> {
+            return match value {
+                0 => Ok(Wdl::Loss),
+                1 => Ok(Wdl::BlessedLoss),
+                2 => Ok(Wdl::Draw),
+                3 => Ok(Wdl::CursedWin),
+                4 => Ok(Wdl::Win),
+                _ => Err(TablebaseError::corrupt_table(format!("invalid wdl value {}", value)))
+            }
+        }
+
+        pub fn to_opposite(self) -> Wdl {
+            return match self {
+                Wdl::Loss => Wdl::Win,
+                Wdl::BlessedLoss => Wdl::CursedWin,
+                Wdl::Draw => Wdl::Draw,
+                Wdl::CursedWin => Wdl::BlessedLoss,
+                Wdl::Win => Wdl::Loss
+            }
+        }
+
+        // The dtz of a position where the best move resets the fifty-move counter.
+        fn dtz_before_zeroing(self) -> i32 {
+            return match self {
+                Wdl::Loss => -1,
+                Wdl::BlessedLoss => -101,
+                Wdl::Draw => 0,
+                Wdl::CursedWin => 101,
+                Wdl::Win => 1
+            }
+        }
+
+        // Converts the wdl of the side to move into a tree value seen from whites side.
+        pub fn to_value(self, turn: &Color) -> i32 {
+            let value = match self {
+                Wdl::Loss => -TB_WIN_VALUE,
+                Wdl::BlessedLoss => -1,
+                Wdl::Draw => 0,
+                Wdl::CursedWin => 1,
+                Wdl::Win => TB_WIN_VALUE
+            };
+
+            return match turn {
+                Color::White => value,
+                Color::Black => -value
+            }
+        }
+    }
+
+    impl fmt::Display for Wdl {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match *self {
+                Wdl::Loss => write!(f, "Loss"),
+                Wdl::BlessedLoss => write!(f, "Blessed loss"),
+                Wdl::Draw => write!(f, "Draw"),
+                Wdl::CursedWin => write!(f, "Cursed win"),
+                Wdl::Win => write!(f, "Win")
+            }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub enum TablebaseError {
+        MissingTable {
+            material: String
+        },
+        TooManyPieces {
+            pieces: usize
+        },
+        Castling,
+        CorruptTable {
+            message: String
+        },
+        Read {
+            path: PathBuf,
+            message: String
+        }
+    }
+
+    impl fmt::Display for TablebaseError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                TablebaseError::MissingTable { material } =>
+                    write!(f, "Missing tablebase file for: {}", material),
+                TablebaseError::TooManyPieces { pieces } =>
+                    write!(f, "Too many pieces for the tablebase: {}", pieces),
+                TablebaseError::Castling =>
+                    write!(f, "Tablebases do not contain positions with castling rights."),
+                TablebaseError::CorruptTable { message } =>
+                    write!(f, "Corrupt tablebase file: {}", message),
+                TablebaseError::Read { path, message } =>
+                    write!(f, "Couldnt read tablebase file {}: {}", path.display(), message)
+            }
+        }
+    }
+
+    impl TablebaseError {
+        fn corrupt_table(message: String) -> TablebaseError {
+            return TablebaseError::CorruptTable {
+                message
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum ProbeState {
+        Normal,
+        ZeroingBestMove
+    }
+
+    fn binomial(mut n: u64, k: u64) -> u64 {
+        if k > n {
+            return 0;
+        }
+        if k > n - k {
+            return binomial(n, n - k);
+        }
+
+        let mut r = 1;
+        let mut d = 1;
+        while d <= k {
+            r = r * n / d;
+            n -= 1;
+            d += 1;
+        }
+
+        return r;
+    }
+
+    fn file_of(square: usize) -> usize {
+        return square & 7;
+    }
+
+    fn rank_of(square: usize) -> usize {
+        return square >> 3;
+    }
+
+    fn flip_vertical(square: usize) -> usize {
+        return square ^ 56;
+    }
+
+    fn flip_horizontal(square: usize) -> usize {
+        return square ^ 7;
+    }
+
+    fn flip_diagonal(square: usize) -> usize {
+        return (file_of(square) << 3) | rank_of(square);
+    }
+
+    fn offdiag(square: usize) -> bool {
+        return file_of(square) != rank_of(square);
+    }
+
+    struct Consts {
+        mult_idx: [[u64; 10]; 5],
+        mult_factor: [u64; 5],
+        map_pawns: [u64; 64],
+        lead_pawn_idx: [[u64; 64]; 6],
+        lead_pawns_size: [[u64; 4]; 6],
+    }
+
+    fn consts() -> &'static Consts {
+        static CONSTS: OnceLock<Consts> = OnceLock::new();
+
+        return CONSTS.get_or_init(|| {
+            let mut mult_idx = [[0; 10]; 5];
+            let mut mult_factor = [0; 5];
+
+            for i in 0..5 {
+                let mut s = 0;
+                for j in 0..10 {
+                    mult_idx[i][j] = s;
+                    s += if i == 0 { 1 } else { binomial(MULT_TWIST[INV_TRIANGLE[j]], i as u64) };
+                }
+                mult_factor[i] = s;
+            }
+
+            let mut available_squares = 48;
+            let mut map_pawns = [0; 64];
+            let mut lead_pawn_idx = [[0; 64]; 6];
+            let mut lead_pawns_size = [[0; 4]; 6];
+
+            for lead_pawns_count in 1..6 {
+                for file in 0..4 {
+                    let mut idx = 0;
+                    for rank in 1..7 {
+                        let square = file + 8 * rank;
+                        if lead_pawns_count == 1 {
+                            available_squares -= 1;
+                            map_pawns[square] = available_squares;
+                            available_squares -= 1;
+                            map_pawns[flip_horizontal(square)] = available_squares;
+                        }
+                        lead_pawn_idx[lead_pawns_count][square] = idx;
+                        idx += binomial(map_pawns[square], lead_pawns_count as u64 - 1);
+                    }
+                    lead_pawns_size[lead_pawns_count][file] = idx;
+                }
+            }
+
+            Consts {
+                mult_idx,
+                mult_factor,
+                map_pawns,
+                lead_pawn_idx,
+                lead_pawns_size
+            }
+        });
+    }
+
+    // Piece counts indexed by piece code, white pieces in 1..7 and black pieces in 9..15.
+    #[derive(Clone, PartialEq)]
+    struct Material {
+        counts: [u8; 16]
+    }
+
+    impl Material {
+        fn from_codes(codes: &Vec<u8>) -> Material {
+            let mut counts = [0; 16];
+            for code in codes.iter() {
+                counts[*code as usize] += 1;
+            }
+
+            return Material { counts }
+        }
+
+        fn from_name(name: &str) -> Option<Material> {
+            let mut counts = [0; 16];
+            let mut color = 0;
+
+            for c in name.chars() {
+                let code = match c {
+                    'K' => TB_KING,
+                    'Q' => TB_QUEEN,
+                    'R' => TB_ROOK,
+                    'B' => TB_BISHOP,
+                    'N' => TB_KNIGHT,
+                    'P' => TB_PAWN,
+                    'v' => {
+                        if color == TB_BLACK {
+                            return None;
+                        }
+                        color = TB_BLACK;
+                        continue;
+                    },
+                    _ => return None
+                };
+                counts[(code + color) as usize] += 1;
+            }
+
+            if color != TB_BLACK {
+                return None;
+            }
+
+            return Some(Material { counts })
+        }
+
+        fn side_name(&self, color: u8) -> String {
+            let mut name = String::new();
+            for (code, c) in [(TB_KING, 'K'), (TB_QUEEN, 'Q'), (TB_ROOK, 'R'), (TB_BISHOP, 'B'), (TB_KNIGHT, 'N'), (TB_PAWN, 'P')].iter() {
+                for _ in 0..self.counts[(code + color) as usize] {
+                    name.push(*c);
+                }
+            }
+
+            return name;
+        }
+
+        fn name(&self) -> String {
+            return format!("{}v{}", self.side_name(0), self.side_name(TB_BLACK));
+        }
+
+        fn flipped(&self) -> Material {
+            let mut counts = [0; 16];
+            for i in 0..8 {
+                counts[i] = self.counts[i + 8];
+                counts[i + 8] = self.counts[i];
+            }
+
+            return Material { counts }
+        }
+
+        fn count(&self) -> usize {
+            return self.counts.iter().map(|c| *c as usize).sum();
+        }
+
+        fn side_count(&self, color: u8) -> usize {
+            let mut sum = 0;
+            for code in TB_PAWN..=TB_KING {
+                sum += self.counts[(code + color) as usize] as usize;
+            }
+
+            return sum;
+        }
+
+        fn is_symmetric(&self) -> bool {
+            return self.counts[0..8] == self.counts[8..16];
+        }
+
+        fn has_pawns(&self) -> bool {
+            return self.counts[TB_PAWN as usize] > 0 || self.counts[(TB_PAWN + TB_BLACK) as usize] > 0;
+        }
+
+        fn both_sides_have_pawns(&self) -> bool {
+            return self.counts[TB_PAWN as usize] > 0 && self.counts[(TB_PAWN + TB_BLACK) as usize] > 0;
+        }
+
+        fn unique_pieces(&self) -> u8 {
+            return self.counts.iter().filter(|c| **c == 1).count() as u8;
+        }
+
+        fn min_like_man(&self) -> u8 {
+            let mut min = 0;
+            for c in self.counts.iter() {
+                if *c >= 2 && (min == 0 || *c < min) {
+                    min = *c;
+                }
+            }
+
+            return min;
+        }
+    }
+
+    // The board as the tables see it: a1 = 0, h1 = 7 and a8 = 56.
+    struct TbBoard {
+        squares: [u8; 64],
+        white_to_move: bool
+    }
+
+    impl TbBoard {
+        fn from_game(game: &Game) -> TbBoard {
+            let mut squares = [0; 64];
+
+            for row in 0..8 {
+                for column in 0..8 {
+                    match game.get_piece_from_position(&Position::new(column, row)) {
+                        None => (),
+                        Some((piece, color)) => {
+                            let code = match piece {
+                                Piece::Pawn => TB_PAWN,
+                                Piece::Knight => TB_KNIGHT,
+                                Piece::Bishop => TB_BISHOP,
+                                Piece::Rook => TB_ROOK,
+                                Piece::Queen => TB_QUEEN,
+                                Piece::King => TB_KING,
+                                _ => panic!("Tablebases only hold standard pieces.")
+                            };
+                            let color_code = match color {
+                                Color::White => 0,
+                                Color::Black => TB_BLACK
+                            };
+                            squares[(7 - row) * 8 + column] = code + color_code;
+                        }
+                    }
+                }
+            }
+
+            return TbBoard {
+                squares,
+                white_to_move: game.get_turn() == Color::White
+            }
+        }
+
+        fn material(&self) -> Material {
+            let mut counts = [0; 16];
+            for code in self.squares.iter() {
+                if *code != 0 {
+                    counts[*code as usize] += 1;
+                }
+            }
+
+            return Material { counts }
+        }
+
+        fn piece_count(&self) -> usize {
+            return self.squares.iter().filter(|code| **code != 0).count();
+        }
+    }
+
+    fn read_u8(data: &Vec<u8>, ptr: u64) -> Result<u8, TablebaseError> {
+        return match data.get(ptr as usize) {
+            Some(byte) => Ok(*byte),
+            None => Err(TablebaseError::corrupt_table(format!("read out of bounds at {}", ptr)))
+        }
+    }
+
+    fn read_u16_le(data: &Vec<u8>, ptr: u64) -> Result<u16, TablebaseError> {
+        return Ok(read_u8(data, ptr)? as u16 | (read_u8(data, ptr + 1)? as u16) << 8);
+    }
+
+    fn read_u32_le(data: &Vec<u8>, ptr: u64) -> Result<u32, TablebaseError> {
+        return Ok(read_u16_le(data, ptr)? as u32 | (read_u16_le(data, ptr + 2)? as u32) << 16);
+    }
+
+    // Blocks are read a few bytes past their end, the bytes beyond the file count as zero.
+    fn read_u32_be_padded(data: &Vec<u8>, ptr: u64) -> u32 {
+        let mut value = 0;
+        for i in 0..4 {
+            let byte = match data.get((ptr + i) as usize) {
+                Some(byte) => *byte,
+                None => 0
+            };
+            value = (value << 8) | byte as u32;
+        }
+
+        return value;
+    }
+
+    // Reads a 3 byte huffman tree node.
+    fn read_lr(data: &Vec<u8>, ptr: u64) -> Result<(u16, u16), TablebaseError> {
+        let b0 = read_u8(data, ptr)? as u16;
+        let b1 = read_u8(data, ptr + 1)? as u16;
+        let b2 = read_u8(data, ptr + 2)? as u16;
+
+        let left = ((b1 & 0xf) << 8) | b0;
+        let right = (b2 << 4) | (b1 >> 4);
+
+        return Ok((left, right));
+    }
+
+    // Description of how a piece configuration is encoded into an index.
+    struct GroupData {
+        pieces: Vec<u8>,
+        lens: Vec<usize>,
+        factors: Vec<u64>
+    }
+
+    impl GroupData {
+        fn new(pieces: Vec<u8>, order: [u8; 2], file: usize) -> GroupData {
+            let material = Material::from_codes(&pieces);
+
+            // Without pawns the first group is made of three unique pieces, or the two kings,
+            // or the smallest set of identical pieces. The remaining identical pieces are grouped together.
+            let first_len = if material.has_pawns() {
+                0
+            } else if material.unique_pieces() >= 3 {
+                3
+            } else if material.unique_pieces() == 2 {
+                2
+            } else {
+                material.min_like_man() as usize
+            };
+
+            let mut lens = vec![];
+            if first_len > 0 {
+                lens.push(first_len);
+            }
+
+            let mut i = first_len;
+            while i < pieces.len() {
+                let mut j = i + 1;
+                while j < pieces.len() && pieces[j] == pieces[i] {
+                    j += 1;
+                }
+                lens.push(j - i);
+                i = j;
+            }
+
+            let pp = material.both_sides_have_pawns();
+            let mut factors = vec![0; lens.len() + 1];
+            let mut free_squares = 64 - lens[0] - if pp { lens[1] } else { 0 };
+            let mut next = if pp { 2 } else { 1 };
+            let mut idx: u64 = 1;
+            let mut k = 0;
+
+            while next < lens.len() || k == order[0] || k == order[1] {
+                if k == order[0] {
+                    // Leading pawns or pieces.
+                    factors[0] = idx;
+
+                    if material.has_pawns() {
+                        idx *= consts().lead_pawns_size[lens[0]][file];
+                    } else if material.unique_pieces() >= 3 {
+                        idx *= 31332;
+                    } else if material.unique_pieces() == 2 {
+                        idx *= 462;
+                    } else if material.min_like_man() == 2 {
+                        idx *= 278;
+                    } else {
+                        idx *= consts().mult_factor[material.min_like_man() as usize - 1];
+                    }
+                } else if k == order[1] {
+                    // Remaining pawns.
+                    factors[1] = idx;
+                    idx *= binomial(48 - lens[0] as u64, lens[1] as u64);
+                } else {
+                    // Remaining pieces.
+                    factors[next] = idx;
+                    idx *= binomial(free_squares as u64, lens[next] as u64);
+                    free_squares -= lens[next];
+                    next += 1;
+                }
+                k += 1;
+            }
+
+            factors[lens.len()] = idx;
+
+            return GroupData {
+                pieces,
+                lens,
+                factors
+            }
+        }
+    }
+
+    // Offsets into the table of remapped dtz values.
+    struct DtzMap {
+        map_ptr: u64,
+        by_wdl: [u16; 4],
+        wide: bool
+    }
+
+    // Everything needed to decompress one subtable.
+    struct PairsData {
+        flags: u8,
+        groups: GroupData,
+        block_size: u32,
+        span: u32,
+        blocks_num: u32,
+        btree: u64,
+        min_symlen: u8,
+        lowest_sym: u64,
+        base: Vec<u64>,
+        symlen: Vec<u8>,
+        sparse_index: u64,
+        sparse_index_size: u32,
+        block_lengths: u64,
+        block_length_size: u32,
+        data: u64,
+        dtz_map: Option<DtzMap>
+    }
+
+    impl PairsData {
+        fn parse(data: &Vec<u8>, mut ptr: u64, groups: GroupData, is_wdl: bool) -> Result<(PairsData, u64), TablebaseError> {
+            let flags = read_u8(data, ptr)?;
+
+            if flags & FLAG_SINGLE_VALUE != 0 {
+                let single_value = if is_wdl { read_u8(data, ptr + 1)? } else { 0 };
+
+                let pairs = PairsData {
+                    flags,
+                    groups,
+                    block_size: 0,
+                    span: 0,
+                    blocks_num: 0,
+                    btree: 0,
+                    min_symlen: single_value,
+                    lowest_sym: 0,
+                    base: vec![],
+                    symlen: vec![],
+                    sparse_index: 0,
+                    sparse_index_size: 0,
+                    block_lengths: 0,
+                    block_length_size: 0,
+                    data: 0,
+                    dtz_map: None
+                };
+
+                return Ok((pairs, ptr + 2));
+            }
+
+            let tb_size = groups.factors[groups.lens.len()];
+            let block_size = 1_u32 << read_u8(data, ptr + 1)?;
+            let span = 1_u32 << read_u8(data, ptr + 2)?;
+            let sparse_index_size = ((tb_size + span as u64 - 1) / span as u64) as u32;
+            let padding = read_u8(data, ptr + 3)?;
+            let blocks_num = read_u32_le(data, ptr + 4)?;
+            let block_length_size = blocks_num + padding as u32;
+
+            let max_symlen = read_u8(data, ptr + 8)?;
+            let min_symlen = read_u8(data, ptr + 9)?;
+            if max_symlen > 32 || min_symlen > 32 || min_symlen == 0 || max_symlen < min_symlen {
+                return Err(TablebaseError::corrupt_table("invalid symbol lengths".to_string()));
+            }
+
+            let h = (max_symlen - min_symlen + 1) as usize;
+            let lowest_sym = ptr + 10;
+
+            let mut base = vec![0_u64; h];
+            for i in (0..h - 1).rev() {
+                let sym_ptr = lowest_sym + i as u64 * 2;
+                base[i] = (base[i + 1] + read_u16_le(data, sym_ptr)? as u64 - read_u16_le(data, sym_ptr + 2)? as u64) / 2;
+            }
+
+            for i in 0..h {
+                base[i] <<= 64 - (min_symlen as u32 + i as u32);
+            }
+
+            ptr += 10 + h as u64 * 2;
+            let sym = read_u16_le(data, ptr)? as usize;
+            ptr += 2;
+            let btree = ptr;
+
+            let mut symlen = vec![0_u8; sym];
+            let mut visited = vec![false; sym];
+            for s in 0..sym {
+                read_symlen(data, btree, &mut symlen, &mut visited, s as u16, 16)?;
+            }
+            ptr += sym as u64 * 3 + (sym as u64 & 1);
+
+            let pairs = PairsData {
+                flags,
+                groups,
+                block_size,
+                span,
+                blocks_num,
+                btree,
+                min_symlen,
+                lowest_sym,
+                base,
+                symlen,
+                sparse_index: 0,
+                sparse_index_size,
+                block_lengths: 0,
+                block_length_size,
+                data: 0,
+                dtz_map: None
+            };
+
+            return Ok((pairs, ptr));
+        }
+    }
+
+    fn read_symlen(data: &Vec<u8>, btree: u64, symlen: &mut Vec<u8>, visited: &mut Vec<bool>, sym: u16, depth: u8) -> Result<(), TablebaseError> {
+        match visited.get(sym as usize) {
+            None => return Err(TablebaseError::corrupt_table("symbol out of range".to_string())),
+            Some(true) => return Ok(()),
+            Some(false) => ()
+        }
+
+        let (left, right) = read_lr(data, btree + 3 * sym as u64)?;
+
+        if right == 0xfff {
+            symlen[sym as usize] = 0;
+        } else {
+            if depth == 0 {
+                return Err(TablebaseError::corrupt_table("huffman tree too deep".to_string()));
+            }
+
+            read_symlen(data, btree, symlen, visited, left, depth - 1)?;
+            read_symlen(data, btree, symlen, visited, right, depth - 1)?;
+
+            symlen[sym as usize] = symlen[left as usize] + symlen[right as usize] + 1;
+        }
+
+        visited[sym as usize] = true;
+        return Ok(());
+    }
+
+    // A single wdl or dtz file, kept in memory.
+    struct Table {
+        data: Vec<u8>,
+        is_wdl: bool,
+        material: Material,
+        files: Vec<Vec<PairsData>>
+    }
+
+    impl Table {
+        fn open(path: &Path, material: &Material, is_wdl: bool) -> Result<Table, TablebaseError> {
+            let data = match fs::read(path) {
+                Ok(data) => data,
+                Err(err) => return Err(TablebaseError::Read { path: path.to_path_buf(), message: err.to_string() })
+            };
+
+            return Table::new(data, material, is_wdl);
+        }
+
+        fn new(data: Vec<u8>, material: &Material, is_wdl: bool) -> Result<Table, TablebaseError> {
+            let magic = if is_wdl { WDL_MAGIC } else { DTZ_MAGIC };
+            if data.len() < 5 || data[0..4] != magic {
+                return Err(TablebaseError::corrupt_table("invalid magic header".to_string()));
+            }
+
+            let layout = data[4];
+            let split = layout & 1 != 0;
+            let has_pawns = layout & 2 != 0;
+
+            if has_pawns != material.has_pawns() || split == material.is_symmetric() {
+                return Err(TablebaseError::corrupt_table("layout does not match the material".to_string()));
+            }
+
+            let pp = material.both_sides_have_pawns();
+            let num_files = if has_pawns { 4 } else { 1 };
+            let num_sides = if is_wdl && !material.is_symmetric() { 2 } else { 1 };
+            let count = material.count();
+
+            let mut ptr: u64 = 5;
+            let mut groups_by_file: Vec<Vec<GroupData>> = vec![];
+
+            for file in 0..num_files {
+                let first = read_u8(&data, ptr)?;
+                let second = if pp { read_u8(&data, ptr + 1)? } else { 0xff };
+                let order = [[first & 0xf, second & 0xf], [first >> 4, second >> 4]];
+                ptr += if pp { 2 } else { 1 };
+
+                let mut sides = vec![];
+                for side in 0..num_sides {
+                    let mut pieces = vec![];
+                    for i in 0..count {
+                        let byte = read_u8(&data, ptr + i as u64)?;
+                        let code = if side == 0 { byte & 0xf } else { byte >> 4 };
+                        if code & 7 < TB_PAWN || code & 7 > TB_KING {
+                            return Err(TablebaseError::corrupt_table("invalid piece in header".to_string()));
+                        }
+                        pieces.push(code);
+                    }
+
+                    let key = Material::from_codes(&pieces);
+                    if key != *material && key.flipped() != *material {
+                        return Err(TablebaseError::corrupt_table("pieces do not match the material".to_string()));
+                    }
+
+                    sides.push(GroupData::new(pieces, order[side], file));
+                }
+                ptr += count as u64;
+
+                groups_by_file.append(&mut vec![sides]);
+            }
+
+            ptr += ptr & 1;
+
+            if (groups_by_file[0][0].pieces[0] & 7 == TB_PAWN) != has_pawns {
+                return Err(TablebaseError::corrupt_table("leading pawn missing".to_string()));
+            }
+
+            let mut files: Vec<Vec<PairsData>> = vec![];
+            for groups in groups_by_file {
+                let mut sides = vec![];
+                for group in groups {
+                    let (pairs, next_ptr) = PairsData::parse(&data, ptr, group, is_wdl)?;
+                    sides.push(pairs);
+                    ptr = next_ptr;
+                }
+                files.push(sides);
+            }
+
+            if !is_wdl {
+                let map_ptr = ptr;
+
+                for file in files.iter_mut() {
+                    if file[0].flags & FLAG_MAPPED != 0 {
+                        let wide = file[0].flags & FLAG_WIDE_DTZ != 0;
+                        let mut by_wdl = [0_u16; 4];
+
+                        for idx in by_wdl.iter_mut() {
+                            if wide {
+                                *idx = ((ptr - map_ptr + 2) / 2) as u16;
+                                ptr += read_u16_le(&data, ptr)? as u64 * 2 + 2;
+                            } else {
+                                *idx = (ptr - map_ptr + 1) as u16;
+                                ptr += read_u8(&data, ptr)? as u64 + 1;
+                            }
+                        }
+
+                        file[0].dtz_map = Some(DtzMap { map_ptr, by_wdl, wide });
+                    }
+                }
+
+                ptr += ptr & 1;
+            }
+
+            for file in files.iter_mut() {
+                for side in file.iter_mut() {
+                    side.sparse_index = ptr;
+                    ptr += side.sparse_index_size as u64 * 6;
+                }
+            }
+
+            for file in files.iter_mut() {
+                for side in file.iter_mut() {
+                    side.block_lengths = ptr;
+                    ptr += side.block_length_size as u64 * 2;
+                }
+            }
+
+            for file in files.iter_mut() {
+                for side in file.iter_mut() {
+                    // Compressed data is aligned to 64 bytes.
+                    ptr = (ptr + 0x3f) & !0x3f;
+                    side.data = ptr;
+                    ptr += side.blocks_num as u64 * side.block_size as u64;
+                }
+            }
+
+            let table_material = Material::from_codes(&files[0][0].groups.pieces);
+
+            return Ok(Table {
+                data,
+                is_wdl,
+                material: table_material,
+                files
+            });
+        }
+
+        // Finds the value stored for idx by decoding the huffman symbols of the block that holds it.
+        fn decompress_pairs(&self, d: &PairsData, idx: u64) -> Result<u16, TablebaseError> {
+            if d.flags & FLAG_SINGLE_VALUE != 0 {
+                return Ok(d.min_symlen as u16);
+            }
+
+            let main_idx = idx / d.span as u64;
+            let mut block = read_u32_le(&self.data, d.sparse_index + 6 * main_idx)? as i64;
+            let offset = read_u16_le(&self.data, d.sparse_index + 6 * main_idx + 4)? as i64;
+
+            let mut lit_idx = (idx % d.span as u64) as i64 - d.span as i64 / 2 + offset;
+
+            while lit_idx < 0 {
+                block -= 1;
+                if block < 0 {
+                    return Err(TablebaseError::corrupt_table("block index out of range".to_string()));
+                }
+                lit_idx += read_u16_le(&self.data, d.block_lengths + block as u64 * 2)? as i64 + 1;
+            }
+
+            loop {
+                let block_length = read_u16_le(&self.data, d.block_lengths + block as u64 * 2)? as i64 + 1;
+                if lit_idx >= block_length {
+                    lit_idx -= block_length;
+                    block += 1;
+                } else {
+                    break;
+                }
+            }
+
+            let mut cursor = d.data + block as u64 * d.block_size as u64;
+            let mut buf = ((read_u32_be_padded(&self.data, cursor) as u64) << 32) | read_u32_be_padded(&self.data, cursor + 4) as u64;
+            cursor += 8;
+            let mut buf_size = 64;
+
+            let mut sym;
+            loop {
+                let mut len = 0;
+                while len < d.base.len() && buf < d.base[len] {
+                    len += 1;
+                }
+                if len == d.base.len() {
+                    return Err(TablebaseError::corrupt_table("invalid huffman code".to_string()));
+                }
+
+                sym = ((buf - d.base[len]) >> (64 - len - d.min_symlen as usize)) as u16;
+                sym += read_u16_le(&self.data, d.lowest_sym + 2 * len as u64)?;
+
+                let sym_length = match d.symlen.get(sym as usize) {
+                    Some(length) => *length as i64,
+                    None => return Err(TablebaseError::corrupt_table("symbol out of range".to_string()))
+                };
+
+                if lit_idx < sym_length + 1 {
+                    break;
+                }
+
+                lit_idx -= sym_length + 1;
+                len += d.min_symlen as usize;
+                buf <<= len;
+                buf_size -= len;
+
+                if buf_size <= 32 {
+                    buf_size += 32;
+                    buf |= (read_u32_be_padded(&self.data, cursor) as u64) << (64 - buf_size);
+                    cursor += 4;
+                }
+            }
+
+            while d.symlen[sym as usize] != 0 {
+                let (left, right) = read_lr(&self.data, d.btree + 3 * sym as u64)?;
+                let left_length = d.symlen[left as usize] as i64;
+
+                if lit_idx < left_length + 1 {
+                    sym = left;
+                } else {
+                    lit_idx -= left_length + 1;
+                    sym = right;
+                }
+            }
+
+            let w = d.btree + 3 * sym as u64;
+            if self.is_wdl {
+                return Ok(read_u8(&self.data, w)? as u16);
+            }
+
+            return Ok(read_u16_le(&self.data, w)? & 0xfff);
+        }
+
+        // Finds the subtable and the index of the position in it. For dtz tables the position
+        // might be stored from the other side, in which case None is returned.
+        fn encode(&self, board: &TbBoard) -> Result<Option<(&PairsData, u64)>, TablebaseError> {
+            let consts = consts();
+            let key = board.material();
+            let material = &self.material;
+
+            let symmetric_btm = material.is_symmetric() && !board.white_to_move;
+            let black_stronger = key != *material;
+            let flip = symmetric_btm || black_stronger;
+            let bside = !board.white_to_move ^ flip;
+            let color_flip = if flip { TB_BLACK } else { 0 };
+
+            let flip_square = |square: usize| -> usize {
+                if flip { flip_vertical(square) } else { square }
+            };
+
+            let mut squares: Vec<usize> = vec![];
+            let mut used = [false; 64];
+
+            // Tables with pawns have a subtable for each file the leading pawn can be on.
+            let file_index = if material.has_pawns() {
+                let lead_pawn = self.files[0][0].groups.pieces[0] ^ color_flip;
+
+                for square in 0..64 {
+                    if board.squares[square] == lead_pawn {
+                        used[square] = true;
+                        squares.push(flip_square(square));
+                    }
+                }
+
+                for i in 1..squares.len() {
+                    if consts.map_pawns[squares[0]] < consts.map_pawns[squares[i]] {
+                        squares.swap(0, i);
+                    }
+                }
+
+                if file_of(squares[0]) >= 4 {
+                    file_of(flip_horizontal(squares[0]))
+                } else {
+                    file_of(squares[0])
+                }
+            } else {
+                0
+            };
+
+            let file = &self.files[file_index];
+            let side = &file[if bside { file.len() - 1 } else { 0 }];
+
+            if !self.is_wdl && ((side.flags & FLAG_STM != 0) != bside) && (!material.is_symmetric() || material.has_pawns()) {
+                return Ok(None);
+            }
+
+            let lead_pawns_count = squares.len();
+
+            for piece in side.groups.pieces.iter().skip(lead_pawns_count) {
+                let code = piece ^ color_flip;
+                let mut found = false;
+
+                for square in 0..64 {
+                    if board.squares[square] == code && !used[square] {
+                        used[square] = true;
+                        squares.push(flip_square(square));
+                        found = true;
+                        break;
+                    }
+                }
+
+                if !found {
+                    return Err(TablebaseError::corrupt_table("piece of the table not on the board".to_string()));
+                }
+            }
+
+            if file_of(squares[0]) >= 4 {
+                for square in squares.iter_mut() {
+                    *square = flip_horizontal(*square);
+                }
+            }
+
+            let lens = &side.groups.lens;
+
+            let mut idx = if material.has_pawns() {
+                let mut idx = consts.lead_pawn_idx[lead_pawns_count][squares[0]];
+
+                squares[1..lead_pawns_count].sort_by_key(|square| consts.map_pawns[*square]);
+
+                for i in 1..lead_pawns_count {
+                    idx += binomial(consts.map_pawns[squares[i]], i as u64);
+                }
+
+                idx
+            } else {
+                if rank_of(squares[0]) >= 4 {
+                    for square in squares.iter_mut() {
+                        *square = flip_vertical(*square);
+                    }
+                }
+
+                for i in 0..lens[0] {
+                    if !offdiag(squares[i]) {
+                        continue;
+                    }
+
+                    if rank_of(squares[i]) > file_of(squares[i]) {
+                        for square in squares[i..].iter_mut() {
+                            *square = flip_diagonal(*square);
+                        }
+                    }
+
+                    break;
+                }
+
+                let unique_pieces = material.unique_pieces();
+
+                if unique_pieces > 2 {
+                    let adjust1 = (squares[1] > squares[0]) as u64;
+                    let adjust2 = (squares[2] > squares[0]) as u64 + (squares[2] > squares[1]) as u64;
+
+                    if offdiag(squares[0]) {
+                        TRIANGLE[squares[0]] * 63 * 62
+                            + (squares[1] as u64 - adjust1) * 62
+                            + (squares[2] as u64 - adjust2)
+                    } else if offdiag(squares[1]) {
+                        6 * 63 * 62
+                            + rank_of(squares[0]) as u64 * 28 * 62
+                            + LOWER[squares[1]] * 62
+                            + squares[2] as u64
+                            - adjust2
+                    } else if offdiag(squares[2]) {
+                        6 * 63 * 62
+                            + 4 * 28 * 62
+                            + rank_of(squares[0]) as u64 * 7 * 28
+                            + (rank_of(squares[1]) as u64 - adjust1) * 28
+                            + LOWER[squares[2]]
+                    } else {
+                        6 * 63 * 62
+                            + 4 * 28 * 62
+                            + 4 * 7 * 28
+                            + rank_of(squares[0]) as u64 * 7 * 6
+                            + (rank_of(squares[1]) as u64 - adjust1) * 6
+                            + (rank_of(squares[2]) as u64 - adjust2)
+                    }
+                } else if unique_pieces == 2 {
+                    KK_IDX[TRIANGLE[squares[0]] as usize][squares[1]]
+                } else if material.min_like_man() == 2 {
+                    if TRIANGLE[squares[0]] > TRIANGLE[squares[1]] {
+                        squares.swap(0, 1);
+                    }
+
+                    if file_of(squares[0]) >= 4 {
+                        for square in squares.iter_mut() {
+                            *square = flip_horizontal(*square);
+                        }
+                    }
+
+                    if rank_of(squares[0]) >= 4 {
+                        for square in squares.iter_mut() {
+                            *square = flip_vertical(*square);
+                        }
+                    }
+
+                    if rank_of(squares[0]) > file_of(squares[0]) || (!offdiag(squares[0]) && rank_of(squares[1]) > file_of(squares[1])) {
+                        for square in squares.iter_mut() {
+                            *square = flip_diagonal(*square);
+                        }
+                    }
+
+                    if TEST45.contains(&squares[1]) && TRIANGLE[squares[0]] == TRIANGLE[squares[1]] {
+                        squares.swap(0, 1);
+
+                        for square in squares.iter_mut() {
+                            *square = flip_diagonal(flip_vertical(*square));
+                        }
+                    }
+
+                    PP_IDX[TRIANGLE[squares[0]] as usize][squares[1]]
+                } else {
+                    for i in 1..lens[0] {
+                        if TRIANGLE[squares[0]] > TRIANGLE[squares[i]] {
+                            squares.swap(0, i);
+                        }
+                    }
+
+                    if file_of(squares[0]) >= 4 {
+                        for square in squares.iter_mut() {
+                            *square = flip_horizontal(*square);
+                        }
+                    }
+
+                    if rank_of(squares[0]) >= 4 {
+                        for square in squares.iter_mut() {
+                            *square = flip_vertical(*square);
+                        }
+                    }
+
+                    if rank_of(squares[0]) > file_of(squares[0]) {
+                        for square in squares.iter_mut() {
+                            *square = flip_diagonal(*square);
+                        }
+                    }
+
+                    for i in 1..lens[0] {
+                        for j in (i + 1)..lens[0] {
+                            if MULT_TWIST[squares[i]] > MULT_TWIST[squares[j]] {
+                                squares.swap(i, j);
+                            }
+                        }
+                    }
+
+                    let mut idx = consts.mult_idx[lens[0] - 1][TRIANGLE[squares[0]] as usize];
+                    for i in 1..lens[0] {
+                        idx += binomial(MULT_TWIST[squares[i]], i as u64);
+                    }
+
+                    idx
+                }
+            };
+
+            if idx == Z0 {
+                return Err(TablebaseError::corrupt_table("position can not be encoded".to_string()));
+            }
+
+            idx *= side.groups.factors[0];
+
+            // Encode the remaining groups.
+            let mut remaining_pawns = material.both_sides_have_pawns();
+            let mut group_square = lens[0];
+
+            for next in 1..lens.len() {
+                let (previous, rest) = squares.split_at_mut(group_square);
+                let group = &mut rest[..lens[next]];
+                group.sort();
+
+                let mut n = 0;
+                for (i, square) in group.iter().enumerate() {
+                    let adjust = previous.iter().filter(|previous_square| *square > **previous_square).count() as u64;
+                    n += binomial(*square as u64 - adjust - if remaining_pawns { 8 } else { 0 }, i as u64 + 1);
+                }
+
+                remaining_pawns = false;
+                idx += n * side.groups.factors[next];
+                group_square += lens[next];
+            }
+
+            return Ok(Some((side, idx)));
+        }
+
+        fn probe_wdl(&self, board: &TbBoard) -> Result<Wdl, TablebaseError> {
+            return match self.encode(board)? {
+                Some((side, idx)) => Wdl::from_table(self.decompress_pairs(side, idx)?),
+                None => Err(TablebaseError::corrupt_table("wdl tables should have both sides".to_string()))
+            }
+        }
+
+        fn probe_dtz(&self, board: &TbBoard, wdl: Wdl) -> Result<Option<i32>, TablebaseError> {
+            let (side, idx) = match self.encode(board)? {
+                Some(t) => t,
+                None => return Ok(None)
+            };
+
+            let mut res = self.decompress_pairs(side, idx)?;
+
+            match &side.dtz_map {
+                None => (),
+                Some(map) => {
+                    let wdl_index = match wdl {
+                        Wdl::Win => 0,
+                        Wdl::Loss => 1,
+                        Wdl::CursedWin => 2,
+                        Wdl::BlessedLoss => 3,
+                        Wdl::Draw => return Ok(Some(0))
+                    };
+
+                    res = if map.wide {
+                        read_u16_le(&self.data, map.map_ptr + 2 * (map.by_wdl[wdl_index] as u64 + res as u64))?
+                    } else {
+                        read_u8(&self.data, map.map_ptr + map.by_wdl[wdl_index] as u64 + res as u64)? as u16
+                    };
+                }
+            }
+
+            let stores_plies = match wdl {
+                Wdl::Win => side.flags & FLAG_WIN_PLIES != 0,
+                Wdl::Loss => side.flags & FLAG_LOSS_PLIES != 0,
+                _ => false
+            };
+
+            return Ok(Some(if stores_plies { res as i32 } else { 2 * res as i32 }));
+        }
+    }
+
+    struct TableEntry {
+        path: PathBuf,
+        material: Material,
+        table: OnceLock<Result<Table, TablebaseError>>
+    }
+
+    impl TableEntry {
+        fn get(&self, is_wdl: bool) -> Result<&Table, TablebaseError> {
+            let table = self.table.get_or_init(|| Table::open(&self.path, &self.material, is_wdl));
+
+            return match table {
+                Ok(t) => Ok(t),
+                Err(err) => Err(err.clone())
+            }
+        }
+    }
+
+    // Syzygy tablebases loaded from local directories. Files are only read the first time they are probed.
+    pub struct Tablebase {
+        wdl: HashMap<String, TableEntry>,
+        dtz: HashMap<String, TableEntry>,
+        max_pieces: usize
+    }
+
+    impl Tablebase {
+        pub fn new() -> Tablebase {
+            return Tablebase {
+                wdl: HashMap::new(),
+                dtz: HashMap::new(),
+                max_pieces: 0
+            }
+        }
+
+        pub fn max_pieces(&self) -> usize {
+            return self.max_pieces;
+        }
+
+        // Adds every .rtbw and .rtbz file in the directory, returns how many were added.
+        pub fn add_directory<P: AsRef<Path>>(&mut self, path: P) -> io::Result<usize> {
+            let mut added = 0;
+
+            for entry in fs::read_dir(path)? {
+                let path = entry?.path();
+                if self.add_file(&path) {
+                    added += 1;
+                }
+            }
+
+            return Ok(added);
+        }
+
+        fn add_file(&mut self, path: &Path) -> bool {
+            let stem = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(stem) => stem,
+                None => return false
+            };
+
+            let material = match Material::from_name(stem) {
+                Some(material) => material,
+                None => return false
+            };
+
+            if material.count() > MAX_PIECES || material.side_count(0) == 0 || material.side_count(TB_BLACK) == 0 {
+                return false;
+            }
+
+            let is_wdl = match path.extension().and_then(|extension| extension.to_str()) {
+                Some("rtbw") => true,
+                Some("rtbz") => false,
+                _ => return false
+            };
+
+            let entry = TableEntry {
+                path: path.to_path_buf(),
+                material: material.clone(),
+                table: OnceLock::new()
+            };
+
+            if is_wdl {
+                self.wdl.insert(material.name(), entry);
+            } else {
+                self.dtz.insert(material.name(), entry);
+            }
+
+            if material.count() > self.max_pieces {
+                self.max_pieces = material.count();
+            }
+
+            return true;
+        }
+
+        // Whether the position is small enough and free of castling rights, so that it can be probed.
+        pub fn can_probe(&self, game: &Game) -> bool {
+            return game.variant.is_standard() && game.is_standard_board() && TbBoard::from_game(game).piece_count() <= self.max_pieces && !Self::has_castling_rights(game);
+        }
+
+        // The game theoretical value for the side to move, assuming the fifty-move counter is zero.
+        pub fn probe_wdl(&self, game: &Game) -> Result<Wdl, TablebaseError> {
+            let (wdl, _state) = self.probe(game)?;
+            return Ok(wdl);
+        }
+
+        //
+        // Distance to zeroing of the fifty-move counter in plies, for the side to move.
+        // Positive values are wins and negative values are losses, values beyond 100 are
+        // cursed wins or blessed losses. Some tables round to full moves, so the value
+        // can be one ply too high.
+        pub fn probe_dtz(&self, game: &Game) -> Result<i32, TablebaseError> {
+            let (wdl, state) = self.probe(game)?;
+            return self.dtz(game, wdl, state);
+        }
+
+        // All legal moves together with the dtz they lead to, seen from the side to move.
+        pub fn root_moves(&self, game: &Game) -> Result<Vec<(ChessMove, i32)>, TablebaseError> {
+            let mut root_moves = vec![];
+
+            for chess_move in game.get_all_legal_moves() {
+                let after = Self::play(game, &chess_move);
+
+                let mut dtz = if Self::is_zeroing(&chess_move) {
+                    self.probe_wdl(&after)?.to_opposite().dtz_before_zeroing()
+                } else {
+                    let v = -self.probe_dtz(&after)?;
+                    if v > 0 { v + 1 } else if v < 0 { v - 1 } else { 0 }
+                };
+
+                if after.is_checkmate() {
+                    dtz = 1;
+                }
+
+                root_moves.push((chess_move, dtz));
+            }
+
+            return Ok(root_moves);
+        }
+
+        //
+        // The moves that keep the best result under the fifty-move rule. Wins are converted
+        // along the shortest dtz, losses are dragged out as long as possible.
+        pub fn best_root_moves(&self, game: &Game) -> Result<Vec<ChessMove>, TablebaseError> {
+            let half_moves = game.log.half_move_clock() as i32;
+            let root_moves = self.root_moves(game)?;
+
+            let mut best_rank = i32::MIN;
+            let mut ranked = vec![];
+
+            for (chess_move, dtz) in root_moves {
+                let rank = if dtz > 0 {
+                    if dtz + half_moves <= 99 {
+                        MAX_DTZ - dtz
+                    } else {
+                        MAX_DTZ - (dtz + half_moves)
+                    }
+                } else if dtz < 0 {
+                    if -dtz + half_moves > 100 {
+                        -1
+                    } else {
+                        -MAX_DTZ - dtz
+                    }
+                } else {
+                    0
+                };
+
+                if rank > best_rank {
+                    best_rank = rank;
+                }
+
+                ranked.push((chess_move, rank));
+            }
+
+            let mut best_moves = vec![];
+            for (chess_move, rank) in ranked {
+                if rank == best_rank {
+                    best_moves.push(chess_move);
+                }
+            }
+
+            return Ok(best_moves);
+        }
+
+        fn has_castling_rights(game: &Game) -> bool {
+            return game.castling_rights().iter().any(|right| *right);
+        }
+
+        fn play(game: &Game, chess_move: &ChessMove) -> Game {
+            let mut new_game = game.clone();
+            let mut after_move = new_game.move_piece(&new_game.get_turn(), chess_move).unwrap();
+            after_move.turn += 1;
+            return after_move;
+        }
+
+        fn is_capture(chess_move: &ChessMove) -> bool {
+            return match chess_move.move_type {
+                MoveType::EnPassant(_) => true,
+                _ => chess_move.piece_at_position.is_some()
+            }
+        }
+
+        fn is_zeroing(chess_move: &ChessMove) -> bool {
+            return chess_move.piece == Piece::Pawn || Self::is_capture(chess_move);
+        }
+
+        fn probe(&self, game: &Game) -> Result<(Wdl, ProbeState), TablebaseError> {
+            let pieces = TbBoard::from_game(game).piece_count();
+            if pieces > MAX_PIECES {
+                return Err(TablebaseError::TooManyPieces { pieces });
+            }
+            if Self::has_castling_rights(game) {
+                return Err(TablebaseError::Castling);
+            }
+
+            // Captures are resolved first, a position with a capture that reaches a result is worth
+            // at least that result, and the tables are free to store a lower value for it.
+            let mut best_capture = Wdl::Loss;
+            let mut best_en_passant = Wdl::Loss;
+
+            let legal_moves = game.get_all_legal_moves();
+
+            for chess_move in legal_moves.iter() {
+                if !Self::is_capture(chess_move) {
+                    continue;
+                }
+
+                let after = Self::play(game, chess_move);
+                let v = self.probe_ab(&after, Wdl::Loss, best_capture.to_opposite())?.to_opposite();
+
+                if v == Wdl::Win {
+                    return Ok((v, ProbeState::ZeroingBestMove));
+                }
+
+                match chess_move.move_type {
+                    MoveType::EnPassant(_) => if v > best_en_passant { best_en_passant = v },
+                    _ => if v > best_capture { best_capture = v }
+                }
+            }
+
+            let v = self.probe_wdl_table(game)?;
+
+            // The table does not know about en passant, so an en passant capture can be strictly better.
+            if best_en_passant > v && best_en_passant > best_capture {
+                return Ok((best_en_passant, ProbeState::ZeroingBestMove));
+            }
+
+            if best_en_passant > best_capture {
+                best_capture = best_en_passant;
+            }
+
+            if best_capture >= v {
+                let state = if best_capture > Wdl::Draw { ProbeState::ZeroingBestMove } else { ProbeState::Normal };
+                return Ok((best_capture, state));
+            }
+
+            // Stalemate without the en passant capture, so the capture has to be played.
+            let only_en_passant = legal_moves.iter().all(|chess_move| match chess_move.move_type {
+                MoveType::EnPassant(_) => true,
+                _ => false
+            });
+            if v == Wdl::Draw && legal_moves.len() > 0 && only_en_passant {
+                return Ok((best_en_passant, ProbeState::ZeroingBestMove));
+            }
+
+            return Ok((v, ProbeState::Normal));
+        }
+
+        // Alpha-beta over captures only, used for positions without en passant rights.
+        fn probe_ab(&self, game: &Game, mut alpha: Wdl, beta: Wdl) -> Result<Wdl, TablebaseError> {
+            for chess_move in game.get_all_legal_moves() {
+                if !Self::is_capture(&chess_move) {
+                    continue;
+                }
+
+                let after = Self::play(game, &chess_move);
+                let v = self.probe_ab(&after, beta.to_opposite(), alpha.to_opposite())?.to_opposite();
+
+                if v >= beta {
+                    return Ok(v);
+                }
+                if v > alpha {
+                    alpha = v;
+                }
+            }
+
+            let v = self.probe_wdl_table(game)?;
+            if v > alpha {
+                return Ok(v);
+            }
+
+            return Ok(alpha);
+        }
+
+        fn dtz(&self, game: &Game, wdl: Wdl, state: ProbeState) -> Result<i32, TablebaseError> {
+            if wdl == Wdl::Draw {
+                return Ok(0);
+            }
+
+            if state == ProbeState::ZeroingBestMove {
+                return Ok(wdl.dtz_before_zeroing());
+            }
+
+            let legal_moves = game.get_all_legal_moves();
+
+            // A winning pawn move resets the counter, captures were already handled when probing wdl.
+            if wdl > Wdl::Draw {
+                for chess_move in legal_moves.iter() {
+                    if chess_move.piece != Piece::Pawn || Self::is_capture(chess_move) {
+                        continue;
+                    }
+
+                    let after = Self::play(game, chess_move);
+                    if self.probe_wdl(&after)?.to_opposite() == wdl {
+                        return Ok(wdl.dtz_before_zeroing());
+                    }
+                }
+            }
+
+            match self.probe_dtz_table(game, wdl)? {
+                Some(plies) => {
+                    let dtz = wdl.dtz_before_zeroing();
+                    return Ok(if dtz > 0 { dtz + plies } else { dtz - plies });
+                },
+                None => ()
+            }
+
+            // The table only stores the other side to move, so search one ply deeper.
+            let mut best = if wdl > Wdl::Draw { None } else { Some(wdl.dtz_before_zeroing()) };
+
+            for chess_move in legal_moves.iter() {
+                if Self::is_zeroing(chess_move) {
+                    continue;
+                }
+
+                let after = Self::play(game, chess_move);
+                let v = -self.probe_dtz(&after)?;
+
+                if v == 1 && after.is_checkmate() {
+                    best = Some(1);
+                } else if v.signum() == (wdl as i32).signum() {
+                    let v = if v > 0 { v + 1 } else { v - 1 };
+                    best = match best {
+                        None => Some(v),
+                        Some(b) => if v < b { Some(v) } else { Some(b) }
+                    };
+                }
+            }
+
+            return match best {
+                Some(dtz) => Ok(dtz),
+                None => Err(TablebaseError::corrupt_table("no move keeps the tablebase result".to_string()))
+            }
+        }
+
+        fn probe_wdl_table(&self, game: &Game) -> Result<Wdl, TablebaseError> {
+            let board = TbBoard::from_game(game);
+            let key = board.material();
+
+            // Only the two kings are left.
+            if key.count() == 2 {
+                return Ok(Wdl::Draw);
+            }
+
+            let entry = Self::find_entry(&self.wdl, &key)?;
+            return entry.get(true)?.probe_wdl(&board);
+        }
+
+        fn probe_dtz_table(&self, game: &Game, wdl: Wdl) -> Result<Option<i32>, TablebaseError> {
+            let board = TbBoard::from_game(game);
+            let key = board.material();
+
+            let entry = Self::find_entry(&self.dtz, &key)?;
+            return entry.get(false)?.probe_dtz(&board, wdl);
+        }
+
+        fn find_entry<'a>(tables: &'a HashMap<String, TableEntry>, key: &Material) -> Result<&'a TableEntry, TablebaseError> {
+            return match tables.get(&key.name()) {
+                Some(entry) => Ok(entry),
+                None => match tables.get(&key.flipped().name()) {
+                    Some(entry) => Ok(entry),
+                    None => Err(TablebaseError::MissingTable { material: key.name() })
+                }
+            }
+        }
+    }
+
+    // The fixtures in tests/syzygy are the official KQvK, KRvK and KPvK tables.
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::engine::uci::uci;
+
+        fn fixture_directory() -> PathBuf {
+            return PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("syzygy");
+        }
+
+        fn fixtures() -> Tablebase {
+            let mut tablebase = Tablebase::new();
+            assert_eq!(tablebase.add_directory(fixture_directory()).unwrap(), 6);
+            return tablebase;
+        }
+
+        fn game(fen: &str) -> Game {
+            return uci::game_from_fen(fen).unwrap();
+        }
+
+        #[test]
+        fn probes_wdl() {
+            let tablebase = fixtures();
+            let cases = [
+                ("8/8/8/8/8/8/2Q5/K3k3 w - - 0 1", Wdl::Win),
+                ("8/8/8/8/8/8/2Q5/K3k3 b - - 0 1", Wdl::Loss),
+                // The king takes the queen.
+                ("8/8/8/4k3/3Q4/8/8/7K b - - 0 1", Wdl::Draw),
+                ("8/8/8/2R5/1K6/8/5k2/8 w - - 0 1", Wdl::Win),
+                ("8/3k4/8/8/8/8/4P3/3K4 w - - 0 1", Wdl::Draw),
+                ("8/8/8/8/8/8/3KP3/5k2 w - - 0 1", Wdl::Win)
+            ];
+
+            for (fen, expected) in cases.iter() {
+                assert_eq!(tablebase.probe_wdl(&game(fen)).unwrap(), *expected, "{}", fen);
+            }
+        }
+
+        #[test]
+        fn probes_dtz() {
+            let tablebase = fixtures();
+            let cases = [
+                // Mate in one and the mated side.
+                ("k7/8/1K6/8/8/8/7Q/8 w - - 0 1", 1),
+                ("k6Q/8/1K6/8/8/8/8/8 b - - 0 1", -1),
+                ("8/8/8/2R5/1K6/8/5k2/8 w - - 0 1", 21),
+                ("8/3k4/8/8/8/8/4P3/3K4 w - - 0 1", 0)
+            ];
+
+            for (fen, expected) in cases.iter() {
+                assert_eq!(tablebase.probe_dtz(&game(fen)).unwrap(), *expected, "{}", fen);
+            }
+        }
+
+        // Black has the pawn, so the tables are probed with the colours swapped.
+        #[test]
+        fn probes_with_colours_swapped() {
+            let tablebase = fixtures();
+            let black_pawn = game("8/5p2/6k1/K7/8/8/8/8 w - - 0 1");
+            let white_pawn = game("8/8/8/8/k7/6K1/5P2/8 b - - 0 1");
+
+            assert_eq!(tablebase.probe_wdl(&black_pawn).unwrap(), Wdl::Loss);
+            assert_eq!(tablebase.probe_wdl(&white_pawn).unwrap(), Wdl::Loss);
+            assert_eq!(tablebase.probe_dtz(&black_pawn).unwrap(), -2);
+            assert_eq!(tablebase.probe_dtz(&white_pawn).unwrap(), -2);
+
+            let pawn_to_move = game("8/8/8/2K5/5kp1/8/8/8 b - - 0 1");
+            assert_eq!(tablebase.probe_wdl(&pawn_to_move).unwrap(), Wdl::Win);
+            assert_eq!(tablebase.probe_dtz(&pawn_to_move).unwrap(), 1);
+        }
+
+        #[test]
+        fn finds_the_mate() {
+            let tablebase = fixtures();
+            let moves = tablebase.best_root_moves(&game("k7/8/1K6/8/8/8/7Q/8 w - - 0 1")).unwrap();
+            let moves: Vec<String> = moves.iter().map(|chess_move| chess_move.to_uci(8)).collect();
+
+            assert_eq!(moves, vec!["h2h8"]);
+        }
+    }
+}
@@ -0,0 +1,141 @@
+pub mod transposition_table {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use crate::chess::chess_move::chess_move::{ChessMove, MoveType};
+    use crate::chess::piece::piece::Piece;
+    use crate::chess::position::position::Position;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Bound {
+        Exact,
+        Lower,
+        Upper
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    pub struct TableEntry {
+        pub score: i32,
+        pub depth: u8,
+        pub bound: Bound,
+        // From square, to square and promotion or dropped piece of the best move.
+        pub best_move: Option<(Position, Position, Option<Piece>)>
+    }
+
+    impl TableEntry {
+        pub fn matches(&self, chess_move: &ChessMove) -> bool {
+            return match self.best_move {
+                None => false,
+                Some((from, to, promotion)) => {
+                    let move_promotion = match chess_move.move_type {
+                        MoveType::Promote(piece) | MoveType::Drop(piece) => Some(piece),
+                        _ => None
+                    };
+                    chess_move.from == from && chess_move.to == to && move_promotion == promotion
+                }
+            }
+        }
+    }
+
+    //
+    // Shared between all search threads without locks. Every entry is stored as the
+    // key xored with the data next to the data, so a torn write from two threads
+    // fails the key check instead of returning a mixed entry.
+    pub struct TranspositionTable {
+        entries: Vec<(AtomicU64, AtomicU64)>
+    }
+
+    impl TranspositionTable {
+        pub fn new(size_mb: usize) -> TranspositionTable {
+            let count = (size_mb * 1024 * 1024 / 16).max(1);
+            let mut entries = Vec::with_capacity(count);
+            for _ in 0..count {
+                entries.push((AtomicU64::new(0), AtomicU64::new(0)));
+            }
+
+            return TranspositionTable {
+                entries
+            }
+        }
+
+        pub fn clear(&self) {
+            for (key, data) in self.entries.iter() {
+                key.store(0, Ordering::Relaxed);
+                data.store(0, Ordering::Relaxed);
+            }
+        }
+
+        pub fn probe(&self, hash: u64) -> Option<TableEntry> {
+            let (key, data) = &self.entries[(hash % self.entries.len() as u64) as usize];
+            let data = data.load(Ordering::Relaxed);
+
+            if data == 0 || key.load(Ordering::Relaxed) ^ data != hash {
+                return None;
+            }
+
+            return Some(Self::unpack(data));
+        }
+
+        pub fn store(&self, hash: u64, entry: TableEntry) {
+            let (key, data) = &self.entries[(hash % self.entries.len() as u64) as usize];
+
+            // Keep deeper results of the same position.
+            let old = data.load(Ordering::Relaxed);
+            if old != 0 && key.load(Ordering::Relaxed) ^ old == hash && Self::unpack(old).depth > entry.depth {
+                return;
+            }
+
+            let packed = Self::pack(&entry);
+            key.store(hash ^ packed, Ordering::Relaxed);
+            data.store(packed, Ordering::Relaxed);
+        }
+
+        fn pack(entry: &TableEntry) -> u64 {
+            let bound = match entry.bound {
+                Bound::Exact => 1_u64,
+                Bound::Lower => 2,
+                Bound::Upper => 3
+            };
+
+            // Squares as row * 16 + column, so boards up to 16 by 16 fit.
+            let encoded_move = match entry.best_move {
+                None => 0_u64,
+                Some((from, to, promotion)) => {
+                    let promotion = match promotion {
+                        None => 0,
+                        Some(piece) => piece.to_u8() as u64
+                    };
+                    1 | ((from.row * 16 + from.column) as u64) << 1 | ((to.row * 16 + to.column) as u64) << 9 | promotion << 17
+                }
+            };
+
+            return (entry.score as u32 as u64) | (entry.depth as u64) << 32 | bound << 40 | encoded_move << 42;
+        }
+
+        fn unpack(data: u64) -> TableEntry {
+            let bound = match (data >> 40) & 3 {
+                1 => Bound::Exact,
+                2 => Bound::Lower,
+                _ => Bound::Upper
+            };
+
+            let encoded_move = (data >> 42) as usize;
+            let best_move = if encoded_move & 1 == 0 {
+                None
+            } else {
+                let from = (encoded_move >> 1) & 255;
+                let to = (encoded_move >> 9) & 255;
+                let promotion = match (encoded_move >> 17) & 15 {
+                    0 => None,
+                    value => Some(Piece::from_u8(value as u8))
+                };
+                Some((Position::new(from % 16, from / 16), Position::new(to % 16, to / 16), promotion))
+            };
+
+            return TableEntry {
+                score: data as u32 as i32,
+                depth: (data >> 32) as u8,
+                bound,
+                best_move
+            }
+        }
+    }
+}
@@ -1,39 +1,376 @@
 pub mod game_tree {
+    use std::collections::{HashMap, VecDeque};
     use crate::chess::chess_move::chess_move::ChessMove;
+    use crate::chess::color::color::Color;
     use crate::chess::game::game::Game;
+    use crate::engine::engine_moves::engine_moves::EngineMoves;
+    use crate::engine::evaluation::evaluation::{terminal_value, variant_evaluation, Evaluator};
+    use crate::engine::zobrist::zobrist;
+
+    pub type NodeId = usize;
+
+    pub const ROOT: NodeId = 0;
+
+    // Window of a node that alpha-beta has not searched.
+    pub const UNSEARCHED: (i32, i32) = (i32::MAX, i32::MIN);
+
+    #[derive(Debug, Clone, Copy)]
+    pub struct Node {
+        // From whites point of view.
+        pub score: i32,
+        pub alpha: i32,
+        pub beta: i32,
+        // Exact value of a leaf from the tablebase, evaluate keeps it instead of the static evaluation.
+        pub tablebase_score: Option<i32>,
+        // Range of the node's edges in the edge list, empty for leaves.
+        first_edge: usize,
+        edge_count: usize
+    }
 
     #[derive(Clone)]
-    pub enum GameTree {
-    //  Tree(game state, alpha, beta, other moves)
-        Tree(Game, Option<ChessMove>, i32, i32, Vec<GameTree>),
-    //  Leaf(game state, leaf value)
-        Leaf(Game, Option<ChessMove>, i32)
+    pub struct Edge {
+        pub chess_move: ChessMove,
+        pub child: NodeId
+    }
+
+    //
+    // Game tree kept in flat lists where nodes point at each other by index. Only the root
+    // position is stored, the position of any other node is found by playing the moves on
+    // the way down. With transpositions shared, positions reached at the same ply through
+    // different move orders are a single node and the tree becomes a DAG.
+    #[derive(Clone)]
+    pub struct GameTree {
+        game: Game,
+        nodes: Vec<Node>,
+        edges: Vec<Edge>,
+        // Zobrist key and turn of every node, only kept when transpositions are shared.
+        transpositions: Option<HashMap<(u64, u32), NodeId>>
     }
 
     impl GameTree {
-        pub fn init_tree(game: Game) -> GameTree {
-            return GameTree::Tree(game, None,i32::MAX, i32::MIN, vec![])
+        pub fn new(game: Game) -> GameTree {
+            return GameTree {
+                game,
+                nodes: vec![GameTree::new_node(0)],
+                edges: vec![],
+                transpositions: None
+            }
         }
 
-        pub fn new_tree(game: Game, chess_move: ChessMove) -> GameTree {
-            return GameTree::Tree(game, Some(chess_move),i32::MAX, i32::MIN, vec![])
+        pub fn with_transpositions(game: Game) -> GameTree {
+            let mut tree = GameTree::new(game.clone());
+            tree.transpositions = Some(HashMap::new());
+            tree.insert_transposition(ROOT, &game);
+            return tree;
         }
 
-        pub fn new_leaf(game: Game, chess_move: ChessMove) -> GameTree {
-            return GameTree::Leaf(game, Some(chess_move),0)
+        fn new_node(score: i32) -> Node {
+            return Node {
+                score,
+                alpha: UNSEARCHED.0,
+                beta: UNSEARCHED.1,
+                tablebase_score: None,
+                first_edge: 0,
+                edge_count: 0
+            }
         }
 
-        pub fn append(parent: GameTree, game_tree: GameTree) -> GameTree {
-            match parent {
-                GameTree::Tree(game, chess_move, alpha, beta, trees) => {
-                    let mut new_trees = trees.clone();
-                    new_trees.append(&mut vec![game_tree.clone()]);
-                    return GameTree::Tree(game, chess_move, alpha, beta, new_trees)
+        fn insert_transposition(&mut self, id: NodeId, game: &Game) {
+            match &mut self.transpositions {
+                Some(transpositions) => {
+                    transpositions.insert((zobrist::hash(game), game.turn), id);
                 },
-                GameTree::Leaf(game, chess_move, eval) => {
-                    return GameTree::Leaf(game, chess_move, eval)
+                None => ()
+            }
+        }
+
+        // The root position.
+        pub fn game(&self) -> &Game {
+            return &self.game;
+        }
+
+        pub fn node(&self, id: NodeId) -> &Node {
+            return &self.nodes[id];
+        }
+
+        pub fn children(&self, id: NodeId) -> &[Edge] {
+            let node = &self.nodes[id];
+            return &self.edges[node.first_edge..node.first_edge + node.edge_count];
+        }
+
+        pub fn is_leaf(&self, id: NodeId) -> bool {
+            return self.nodes[id].edge_count == 0;
+        }
+
+        pub fn node_count(&self) -> usize {
+            return self.nodes.len();
+        }
+
+        pub fn set_score(&mut self, id: NodeId, score: i32) {
+            self.nodes[id].score = score;
+        }
+
+        pub fn set_tablebase_score(&mut self, id: NodeId, score: i32) {
+            self.nodes[id].score = score;
+            self.nodes[id].tablebase_score = Some(score);
+        }
+
+        // The position after chess_move, None when the move can not be played.
+        pub fn play(game: &Game, chess_move: &ChessMove) -> Option<Game> {
+            let mut new_game = game.clone();
+            let mut after_move = new_game.move_piece(&game.get_turn(), chess_move).ok()?;
+            after_move.turn += 1;
+            return Some(after_move);
+        }
+
+        //
+        // Gives a leaf one child per move, game is the leaf's position. Returns the new nodes
+        // with their positions, children shared through a transposition are not included.
+        pub fn expand(&mut self, id: NodeId, game: &Game, moves: &Vec<ChessMove>) -> Vec<(NodeId, Game)> {
+            if !self.is_leaf(id) {
+                return vec![];
+            }
+
+            let mut added = vec![];
+            let first_edge = self.edges.len();
+
+            for chess_move in moves.iter() {
+                let after_move = match GameTree::play(game, chess_move) {
+                    Some(after_move) => after_move,
+                    None => continue
+                };
+
+                let shared = match &self.transpositions {
+                    Some(transpositions) => transpositions.get(&(zobrist::hash(&after_move), after_move.turn)).cloned(),
+                    None => None
+                };
+
+                let child = match shared {
+                    Some(child) => child,
+                    None => {
+                        let child = self.nodes.len();
+                        self.nodes.push(GameTree::new_node(0));
+                        self.insert_transposition(child, &after_move);
+                        added.push((child, after_move));
+                        child
+                    }
+                };
+
+                self.edges.push(Edge {
+                    chess_move: chess_move.clone(),
+                    child
+                });
+            }
+
+            self.nodes[id].first_edge = first_edge;
+            self.nodes[id].edge_count = self.edges.len() - first_edge;
+            return added;
+        }
+
+        // Turns a node into a leaf. Nodes below it stay in the lists until compact is called.
+        pub fn prune(&mut self, id: NodeId) {
+            self.nodes[id].edge_count = 0;
+        }
+
+        // Drops the nodes and edges the root can no longer reach.
+        pub fn compact(&mut self) {
+            *self = self.rebuild(ROOT, self.game.clone());
+        }
+
+        // The subtree after chess_move with its position as the new root, None when the move is not in the tree.
+        pub fn advance(&self, chess_move: &ChessMove) -> Option<GameTree> {
+            let edge = self.children(ROOT).iter().find(|edge| edge.chess_move == *chess_move)?;
+            let game = GameTree::play(&self.game, chess_move)?;
+            return Some(self.rebuild(edge.child, game));
+        }
+
+        fn rebuild(&self, root: NodeId, game: Game) -> GameTree {
+            let mut tree = GameTree {
+                game: game.clone(),
+                nodes: vec![GameTree::new_node(self.nodes[root].score)],
+                edges: vec![],
+                transpositions: self.transpositions.as_ref().map(|_| HashMap::new())
+            };
+            tree.nodes[ROOT].alpha = self.nodes[root].alpha;
+            tree.nodes[ROOT].beta = self.nodes[root].beta;
+            tree.nodes[ROOT].tablebase_score = self.nodes[root].tablebase_score;
+            tree.insert_transposition(ROOT, &game);
+
+            // Positions are only needed to key the transpositions again.
+            let keep_games = self.transpositions.is_some();
+            let mut new_ids = HashMap::new();
+            new_ids.insert(root, ROOT);
+            let mut queue = VecDeque::new();
+            queue.push_back((root, if keep_games { Some(game) } else { None }));
+
+            while let Some((old_id, game)) = queue.pop_front() {
+                let first_edge = tree.edges.len();
+
+                for edge in self.children(old_id) {
+                    let child = match new_ids.get(&edge.child) {
+                        Some(child) => *child,
+                        None => {
+                            let child = tree.nodes.len();
+                            let mut node = self.nodes[edge.child];
+                            node.first_edge = 0;
+                            node.edge_count = 0;
+                            tree.nodes.push(node);
+                            new_ids.insert(edge.child, child);
+
+                            let child_game = match &game {
+                                Some(game) => GameTree::play(game, &edge.chess_move),
+                                None => None
+                            };
+                            match &child_game {
+                                Some(child_game) => tree.insert_transposition(child, child_game),
+                                None => ()
+                            }
+
+                            queue.push_back((edge.child, child_game));
+                            child
+                        }
+                    };
+
+                    tree.edges.push(Edge {
+                        chess_move: edge.chess_move.clone(),
+                        child
+                    });
                 }
+
+                let new_id = new_ids[&old_id];
+                tree.nodes[new_id].first_edge = first_edge;
+                tree.nodes[new_id].edge_count = tree.edges.len() - first_edge;
+            }
+
+            return tree;
+        }
+
+        //
+        // Scores the leaves with the evaluator and backs the values up with alpha-beta,
+        // white maximizes and black minimizes. Every searched node keeps the window it
+        // ended with and its value, clamped to the window like the value it returned, as
+        // the score. Nodes that were cut off keep the UNSEARCHED window. A shared node
+        // holds the result of its last visit.
+        pub fn evaluate(&mut self, evaluator: &dyn Evaluator) -> i32 {
+            for node in self.nodes.iter_mut() {
+                node.alpha = UNSEARCHED.0;
+                node.beta = UNSEARCHED.1;
+            }
+
+            let game = self.game.clone();
+            return self.alpha_beta(ROOT, &game, evaluator, i32::MIN, i32::MAX);
+        }
+
+        fn alpha_beta(&mut self, id: NodeId, game: &Game, evaluator: &dyn Evaluator, alpha: i32, beta: i32) -> i32 {
+            if self.is_leaf(id) {
+                let score = match self.nodes[id].tablebase_score {
+                    Some(score) => score,
+                    None => if game.get_all_legal_moves().is_empty() {
+                        terminal_value(game)
+                    } else {
+                        variant_evaluation(evaluator, game)
+                    }
+                };
+                self.nodes[id].score = score.clamp(alpha, beta);
+                return self.nodes[id].score;
             }
+
+            let maximizing = game.get_turn() == Color::White;
+            let mut alpha = alpha;
+            let mut beta = beta;
+            let first_edge = self.nodes[id].first_edge;
+
+            for i in first_edge..first_edge + self.nodes[id].edge_count {
+                let edge = self.edges[i].clone();
+                let after_move = match GameTree::play(game, &edge.chess_move) {
+                    Some(after_move) => after_move,
+                    None => continue
+                };
+
+                let value = self.alpha_beta(edge.child, &after_move, evaluator, alpha, beta);
+                if maximizing {
+                    alpha = alpha.max(value);
+                } else {
+                    beta = beta.min(value);
+                }
+
+                if alpha >= beta {
+                    break;
+                }
+            }
+
+            let node = &mut self.nodes[id];
+            node.alpha = alpha;
+            node.beta = beta;
+            node.score = if maximizing { alpha } else { beta };
+            return node.score;
+        }
+
+        //
+        // The principal variation after evaluate. In every node it follows the first child
+        // that reached the node's value, later children can only match it by failing low.
+        pub fn best_line(&self) -> EngineMoves {
+            let mut line = vec![];
+            let mut id = ROOT;
+
+            loop {
+                let score = self.nodes[id].score;
+                match self.children(id).iter().find(|edge| self.nodes[edge.child].score == score) {
+                    Some(edge) => {
+                        line.push(edge.chess_move.clone());
+                        id = edge.child;
+                    },
+                    None => break
+                }
+            }
+
+            return EngineMoves::from_line(&line);
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::path::PathBuf;
+        use super::*;
+        use crate::chess::variant::variant;
+        use crate::engine::engine::engine;
+        use crate::engine::evaluation::evaluation::MaterialEvaluator;
+        use crate::engine::syzygy::syzygy::{Tablebase, TB_WIN_VALUE};
+        use crate::engine::uci::uci;
+
+        fn game(fen: &str) -> Game {
+            return uci::game_from_fen(fen).unwrap();
+        }
+
+        // The line used to stop where a leaf cut off the search held its unclamped score.
+        #[test]
+        fn best_line_reaches_the_leaves() {
+            let mut tree = engine::tree_init_with_transpositions(game("r3k3/8/8/8/8/8/8/R3K2Q w - - 0 1"), 4);
+            tree.evaluate(&MaterialEvaluator::new());
+            assert_eq!(tree.best_line().moves.len(), 4);
+        }
+
+        // The logged capture carries the pieces it blew up, the edge in the tree does not.
+        #[test]
+        fn advances_over_an_explosion() {
+            let game = uci::game_from_variant_fen("4k3/8/2n5/3p4/4P3/8/8/4K3 w - - 0 1", &variant::from_name("atomic").unwrap()).unwrap();
+            let tree = engine::tree_init(game.clone(), 2);
+
+            let chess_move = uci::find_move(&game, "e4d5").unwrap();
+            let logged = GameTree::play(&game, &chess_move).unwrap().log.get_last_move().unwrap();
+            assert!(!logged.exploded.is_empty());
+
+            let advanced = tree.advance(&logged).unwrap();
+            assert!(!advanced.is_leaf(ROOT));
+        }
+
+        #[test]
+        fn keeps_tablebase_scores() {
+            let mut tablebase = Tablebase::new();
+            tablebase.add_directory(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("syzygy")).unwrap();
+
+            let mut tree = engine::tree_init_with_tablebase(game("8/8/8/4k3/8/8/8/KQ6 w - - 0 1"), 1, &tablebase);
+            assert_eq!(tree.evaluate(&MaterialEvaluator::new()), TB_WIN_VALUE);
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,178 @@
+pub mod tree_export {
+    use std::collections::HashSet;
+    use crate::chess::color::color::Color;
+    use crate::chess::game::game::Game;
+    use crate::engine::tree::game_tree::{Edge, GameTree, NodeId, ROOT, UNSEARCHED};
+
+    // How much of the tree to export, None means no limit.
+    #[derive(Debug, Clone, Copy)]
+    pub struct ExportLimits {
+        // Plies below the root.
+        pub max_depth: Option<usize>,
+        // Children kept per node, the best ones for the side to move.
+        pub max_width: Option<usize>
+    }
+
+    impl ExportLimits {
+        pub fn new() -> ExportLimits {
+            return ExportLimits {
+                max_depth: None,
+                max_width: None
+            }
+        }
+    }
+
+    //
+    // Graphviz graph of the tree, render it with `dot -Tsvg`. Nodes show the move in SAN,
+    // the score and the alpha-beta window from GameTree::evaluate. Children that fall
+    // outside the limits are summed up in a single dashed node. A transposition is drawn
+    // once, the other moves into it are written on their edges.
+    pub fn to_dot(tree: &GameTree, limits: &ExportLimits) -> String {
+        let mut dot = String::new();
+        dot.push_str("digraph GameTree {\n");
+        dot.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+
+        write_dot_node(tree, ROOT, tree.game(), "root".to_string(), 0, limits, &mut HashSet::new(), &mut dot);
+
+        dot.push_str("}\n");
+        return dot;
+    }
+
+    fn write_dot_node(tree: &GameTree, node: NodeId, game: &Game, name: String, ply: usize, limits: &ExportLimits, drawn: &mut HashSet<NodeId>, dot: &mut String) {
+        drawn.insert(node);
+
+        let mut text = name;
+        text.push_str(&format!("\\nscore {}", tree.node(node).score));
+        match window(tree, node) {
+            Some((alpha, beta)) => text.push_str(&format!("\\nalpha {} beta {}", bound_to_string(alpha), bound_to_string(beta))),
+            None => ()
+        }
+
+        let style = if window(tree, node).is_none() && !tree.is_leaf(node) { ", style=dashed" } else { "" };
+        dot.push_str(&format!("    n{} [label=\"{}\"{}];\n", node, text, style));
+
+        let (children, omitted) = visible_children(tree, node, game, ply, limits);
+        for edge in children {
+            let san = edge.chess_move.to_san(game);
+
+            if drawn.contains(&edge.child) {
+                dot.push_str(&format!("    n{} -> n{} [label=\"{}\"];\n", node, edge.child, san));
+                continue;
+            }
+
+            match GameTree::play(game, &edge.chess_move) {
+                Some(after_move) => write_dot_node(tree, edge.child, &after_move, san, ply + 1, limits, drawn, dot),
+                None => continue
+            }
+            dot.push_str(&format!("    n{} -> n{};\n", node, edge.child));
+        }
+
+        if omitted > 0 {
+            dot.push_str(&format!("    m{} [label=\"{} more\", style=dashed];\n", node, omitted));
+            dot.push_str(&format!("    n{} -> m{} [style=dashed];\n", node, node));
+        }
+    }
+
+    //
+    // The tree as nested JSON objects with the fields id, move (SAN), uci, score, alpha,
+    // beta, children and omitted, the number of children left out by the limits. The root
+    // and nodes without a window have null in place of the missing fields. A transposition
+    // is written under every move that reaches it, with the same id.
+    pub fn to_json(tree: &GameTree, limits: &ExportLimits) -> String {
+        let mut json = String::new();
+        write_json_node(tree, ROOT, tree.game(), None, 0, limits, &mut json);
+        json.push('\n');
+        return json;
+    }
+
+    fn write_json_node(tree: &GameTree, node: NodeId, game: &Game, edge: Option<(String, String)>, ply: usize, limits: &ExportLimits, json: &mut String) {
+        let (san, uci) = match edge {
+            Some((san, uci)) => (json_string(&san), json_string(&uci)),
+            None => ("null".to_string(), "null".to_string())
+        };
+
+        json.push('{');
+        json.push_str(&format!("\"id\":{},\"move\":{},\"uci\":{},", node, san, uci));
+        json.push_str(&format!("\"score\":{},", tree.node(node).score));
+        match window(tree, node) {
+            Some((alpha, beta)) => json.push_str(&format!("\"alpha\":{},\"beta\":{},", json_bound(alpha), json_bound(beta))),
+            None => json.push_str("\"alpha\":null,\"beta\":null,")
+        }
+
+        let (children, omitted) = visible_children(tree, node, game, ply, limits);
+        json.push_str("\"children\":[");
+        let mut first = true;
+        for edge in children {
+            let after_move = match GameTree::play(game, &edge.chess_move) {
+                Some(after_move) => after_move,
+                None => continue
+            };
+
+            if !first {
+                json.push(',');
+            }
+            first = false;
+
+            let names = (edge.chess_move.to_san(game), edge.chess_move.to_uci(game.board.num_rows()));
+            write_json_node(tree, edge.child, &after_move, Some(names), ply + 1, limits, json);
+        }
+        json.push_str(&format!("],\"omitted\":{}}}", omitted));
+    }
+
+    // The window of a searched inner node.
+    fn window(tree: &GameTree, node: NodeId) -> Option<(i32, i32)> {
+        let node_data = tree.node(node);
+        if tree.is_leaf(node) || (node_data.alpha, node_data.beta) == UNSEARCHED {
+            return None;
+        }
+
+        return Some((node_data.alpha, node_data.beta));
+    }
+
+    // The children inside the limits, best first for the side to move, and how many were left out.
+    fn visible_children<'a>(tree: &'a GameTree, node: NodeId, game: &Game, ply: usize, limits: &ExportLimits) -> (Vec<&'a Edge>, usize) {
+        let mut children: Vec<&Edge> = tree.children(node).iter().collect();
+        let total = children.len();
+
+        match limits.max_depth {
+            Some(max_depth) if ply >= max_depth => return (vec![], total),
+            _ => ()
+        }
+
+        match limits.max_width {
+            Some(max_width) if total > max_width => {
+                let score = |edge: &&Edge| tree.node(edge.child).score;
+                if game.get_turn() == Color::White {
+                    children.sort_by(|a, b| score(b).cmp(&score(a)));
+                } else {
+                    children.sort_by(|a, b| score(a).cmp(&score(b)));
+                }
+                children.truncate(max_width);
+            },
+            _ => ()
+        }
+
+        let omitted = total - children.len();
+        return (children, omitted);
+    }
+
+    fn bound_to_string(bound: i32) -> String {
+        return match bound {
+            i32::MAX => "inf".to_string(),
+            i32::MIN => "-inf".to_string(),
+            _ => bound.to_string()
+        }
+    }
+
+    // JSON has no infinity, the open ends of a window are written as null.
+    fn json_bound(bound: i32) -> String {
+        return match bound {
+            i32::MAX | i32::MIN => "null".to_string(),
+            _ => bound.to_string()
+        }
+    }
+
+    fn json_string(value: &str) -> String {
+        return format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""));
+    }
+}
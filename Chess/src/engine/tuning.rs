@@ -0,0 +1,303 @@
+pub mod tuning {
+    use std::fmt;
+    use crate::chess::color::color::Color;
+    use crate::chess::game::game::Game;
+    use crate::chess::position::position::Position;
+    use crate::engine::evaluation::evaluation::EvalParams;
+    use crate::engine::uci::uci;
+
+    // Scaling constants tried when none is given, in hundredths.
+    const K_MIN: usize = 10;
+    const K_MAX: usize = 300;
+
+    #[derive(Debug)]
+    pub enum TuningError {
+        MissingResult {
+            line: String
+        },
+        InvalidPosition {
+            line: String
+        }
+    }
+
+    impl fmt::Display for TuningError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                TuningError::MissingResult { line } =>
+                    write!(f, "No game result in: {}", line),
+                TuningError::InvalidPosition { line } =>
+                    write!(f, "Invalid position in: {}", line)
+            }
+        }
+    }
+
+    // A position with the result of the game it was taken from, 1 for a white win and 0 for a black win.
+    pub struct LabelledPosition {
+        pub game: Game,
+        pub result: f64
+    }
+
+    impl LabelledPosition {
+        //
+        // Reads a FEN or EPD line followed by the result, either as "1-0", "0-1" and "1/2-1/2"
+        // like in c9 "1-0"; or as a number in brackets like [0.5]. Lines of the self-play data
+        // in the form "fen | score | result" are read too.
+        pub fn parse(line: &str) -> Result<LabelledPosition, TuningError> {
+            let fields: Vec<&str> = line.split('|').collect();
+            if fields.len() == 3 {
+                return match (uci::game_from_fen(fields[0]), fields[2].trim().parse::<f64>()) {
+                    (Some(game), Ok(result)) if (0.0..=1.0).contains(&result) => Ok(LabelledPosition { game, result }),
+                    (None, _) => Err(TuningError::InvalidPosition { line: line.to_string() }),
+                    _ => Err(TuningError::MissingResult { line: line.to_string() })
+                }
+            }
+
+            let placement_end = line.trim_start().find(char::is_whitespace).unwrap_or(line.len());
+            let rest = &line.trim_start()[placement_end..];
+
+            let result = if rest.contains("1/2-1/2") {
+                0.5
+            } else if rest.contains("1-0") {
+                1.0
+            } else if rest.contains("0-1") {
+                0.0
+            } else {
+                let bracketed = rest.split_once('[').and_then(|(_, value)| value.split_once(']'));
+                match bracketed.map(|(value, _)| value.trim().parse::<f64>()) {
+                    Some(Ok(value)) if (0.0..=1.0).contains(&value) => value,
+                    _ => return Err(TuningError::MissingResult { line: line.to_string() })
+                }
+            };
+
+            return match uci::game_from_fen(line) {
+                Some(game) => Ok(LabelledPosition { game, result }),
+                None => Err(TuningError::InvalidPosition { line: line.to_string() })
+            }
+        }
+    }
+
+    pub fn parse_positions(text: &str) -> Vec<Result<LabelledPosition, TuningError>> {
+        return text.lines()
+            .filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .map(|line| LabelledPosition::parse(line))
+            .collect();
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum TuningMethod {
+        // Moves one parameter at a time by one centipawn while that lowers the error.
+        LocalSearch,
+        // Moves every parameter against the gradient of the error, learning_rate is in centipawns per unit of gradient.
+        GradientDescent {
+            learning_rate: f64
+        }
+    }
+
+    #[derive(Clone)]
+    pub struct TuningOptions {
+        pub method: TuningMethod,
+        pub iterations: usize,
+        // Scaling of the sigmoid, found from the starting parameters when None.
+        pub k: Option<f64>
+    }
+
+    impl TuningOptions {
+        pub fn new(method: TuningMethod, iterations: usize) -> TuningOptions {
+            return TuningOptions {
+                method,
+                iterations,
+                k: None
+            }
+        }
+    }
+
+    //
+    // The evaluation is linear in its parameters, so every position is reduced once to the
+    // parameters it uses and how often, white pieces count one up and black pieces one down.
+    pub struct Tuner {
+        features: Vec<Vec<(usize, i32)>>,
+        results: Vec<f64>,
+        // Parameters some position uses, the others can not change the error.
+        active: Vec<usize>,
+        k: f64
+    }
+
+    impl Tuner {
+        pub fn new(positions: &Vec<LabelledPosition>) -> Tuner {
+            let mut used = vec![false; EvalParams::len()];
+            let mut features = vec![];
+
+            for position in positions.iter() {
+                let mut counts = vec![0; EvalParams::len()];
+                let game = &position.game;
+
+                for row in 0..game.board.num_rows() {
+                    for column in 0..game.board.num_columns() {
+                        let square = Position::new(column, row);
+                        // Fairy pieces have no parameters to tune.
+                        match game.get_piece_from_position(&square) {
+                            Some((piece, color)) if piece.is_standard() => {
+                                let sign = if color == Color::White { 1 } else { -1 };
+                                counts[EvalParams::value_index(&piece)] += sign;
+                                counts[EvalParams::square_index(&piece, &color, &EvalParams::table_position(game, &square))] += sign;
+                            },
+                            _ => ()
+                        }
+                    }
+                }
+
+                let position_features: Vec<(usize, i32)> = counts.into_iter().enumerate().filter(|(_, count)| *count != 0).collect();
+                for (index, _) in position_features.iter() {
+                    used[*index] = true;
+                }
+                features.push(position_features);
+            }
+
+            return Tuner {
+                features,
+                results: positions.iter().map(|position| position.result).collect(),
+                active: (0..EvalParams::len()).filter(|index| used[*index]).collect(),
+                k: 1.0
+            }
+        }
+
+        pub fn set_k(&mut self, k: f64) {
+            self.k = k;
+        }
+
+        fn evaluate(&self, position: usize, params: &Vec<f64>) -> f64 {
+            return self.features[position].iter().map(|(index, count)| params[*index] * *count as f64).sum();
+        }
+
+        fn sigmoid(&self, eval: f64) -> f64 {
+            return 1.0 / (1.0 + 10f64.powf(-self.k * eval / 400.0));
+        }
+
+        // Mean squared difference between the results and the win probabilities the evaluation predicts.
+        pub fn error(&self, params: &Vec<f64>) -> f64 {
+            if self.results.is_empty() {
+                return 0.0;
+            }
+
+            let total: f64 = self.results.iter().enumerate()
+                .map(|(position, result)| (result - self.sigmoid(self.evaluate(position, params))).powi(2))
+                .sum();
+            return total / self.results.len() as f64;
+        }
+
+        // Picks the scaling constant that fits the parameters best.
+        pub fn find_k(&mut self, params: &Vec<f64>) -> f64 {
+            let mut best = (f64::MAX, self.k);
+            for candidate in K_MIN..=K_MAX {
+                self.k = candidate as f64 / 100.0;
+                let error = self.error(params);
+                if error < best.0 {
+                    best = (error, self.k);
+                }
+            }
+
+            self.k = best.1;
+            return self.k;
+        }
+
+        // One pass over every parameter, returns whether anything improved.
+        fn local_search_pass(&self, params: &mut Vec<f64>, error: &mut f64) -> bool {
+            let mut improved = false;
+
+            for index in self.active.iter() {
+                for step in [1.0, -1.0] {
+                    params[*index] += step;
+                    let new_error = self.error(params);
+                    if new_error < *error {
+                        *error = new_error;
+                        improved = true;
+                        break;
+                    }
+                    params[*index] -= step;
+                }
+            }
+
+            return improved;
+        }
+
+        fn gradient_step(&self, params: &mut Vec<f64>, learning_rate: f64) {
+            let mut gradient = vec![0.0; params.len()];
+            let scale = self.k * 10f64.ln() / 400.0;
+
+            for (position, result) in self.results.iter().enumerate() {
+                let predicted = self.sigmoid(self.evaluate(position, params));
+                let slope = -2.0 * (result - predicted) * predicted * (1.0 - predicted) * scale;
+                for (index, count) in self.features[position].iter() {
+                    gradient[*index] += slope * *count as f64;
+                }
+            }
+
+            let positions = self.results.len().max(1) as f64;
+            for index in self.active.iter() {
+                params[*index] -= learning_rate * gradient[*index] / positions;
+            }
+        }
+    }
+
+    //
+    // Texel tuning, lowers the error between game results and the evaluation of the positions
+    // taken from those games. on_iteration gets every finished iteration with its error.
+    pub fn tune(positions: &Vec<LabelledPosition>, params: &EvalParams, options: &TuningOptions, on_iteration: &mut dyn FnMut(usize, f64)) -> EvalParams {
+        let mut tuner = Tuner::new(positions);
+        let mut vector: Vec<f64> = params.to_vector().iter().map(|value| *value as f64).collect();
+
+        match options.k {
+            Some(k) => tuner.set_k(k),
+            None => {
+                tuner.find_k(&vector);
+            }
+        }
+
+        let mut error = tuner.error(&vector);
+        for iteration in 1..=options.iterations {
+            match options.method {
+                TuningMethod::LocalSearch => {
+                    let improved = tuner.local_search_pass(&mut vector, &mut error);
+                    on_iteration(iteration, error);
+                    if !improved {
+                        break;
+                    }
+                },
+                TuningMethod::GradientDescent { learning_rate } => {
+                    tuner.gradient_step(&mut vector, learning_rate);
+                    error = tuner.error(&vector);
+                    on_iteration(iteration, error);
+                }
+            }
+        }
+
+        return EvalParams::from_vector(&vector.iter().map(|value| value.round() as i32).collect());
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::chess::variant::variant;
+        use crate::engine::evaluation::evaluation::{Evaluator, MaterialEvaluator};
+
+        // The features of a position have to add up to what the evaluator makes of it, on any board size.
+        #[test]
+        fn features_match_the_evaluator() {
+            let positions = vec![
+                ("standard", "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4"),
+                ("gardner", "rnb1k/pp1pp/2p2/1PP1P/RNBQK w - - 0 1"),
+                ("losalamos", "r1qknr/pppppp/2n3/3P2/PPP1PP/RNQKNR w - - 0 1")
+            ];
+
+            let evaluator = MaterialEvaluator::new();
+            let params: Vec<f64> = EvalParams::default().to_vector().iter().map(|value| *value as f64).collect();
+
+            for (name, fen) in positions.iter() {
+                let game = uci::game_from_variant_fen(fen, &variant::from_name(name).unwrap()).unwrap();
+                let expected = evaluator.evaluate(&game) as f64;
+                let tuner = Tuner::new(&vec![LabelledPosition { game, result: 0.5 }]);
+                assert_eq!(tuner.evaluate(0, &params), expected, "{}", fen);
+            }
+        }
+    }
+}
@@ -0,0 +1,447 @@
+pub mod uci {
+    use std::env;
+    use std::io;
+    use std::io::BufRead;
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::thread::JoinHandle;
+    use std::time::Duration;
+    use crate::chess::chess_move::chess_move::ChessMove;
+    use crate::chess::color::color::Color;
+    use crate::chess::game::game::{Game, MAX_BOARD_SIZE, MIN_BOARD_SIZE};
+    use crate::chess::piece::piece::Piece;
+    use crate::chess::piece_definition::piece_definition;
+    use crate::chess::position::position::Position;
+    use crate::chess::variant::variant::{self, Variant};
+    use crate::engine::evaluation::evaluation::{EvalParams, MaterialEvaluator};
+    use crate::engine::nnue::nnue::{Network, NnueEvaluator};
+    use crate::engine::mcts::mcts::{MctsOptions, PlayoutPolicy};
+    use crate::engine::search::search::{Backend, EngineOptions, Search, SearchLimits};
+    use crate::engine::skill::skill::{MAX_ELO, MAX_SKILL_LEVEL, MIN_ELO};
+    use crate::engine::syzygy::syzygy::Tablebase;
+
+    const MAX_THREADS: usize = 256;
+    const MAX_HASH: usize = 4096;
+    const MAX_MULTI_PV: usize = 256;
+    const MAX_MCTS_ITERATIONS: u64 = 100_000_000;
+    const MAX_PLAYOUT_DEPTH: usize = 500;
+
+    // Protocol front-end, reads commands from stdin and answers on stdout.
+    pub fn run_uci() {
+        let mut search = Search::new(EngineOptions::default());
+        let mut game = Game::default();
+        let mut running: Option<JoinHandle<()>> = None;
+        // Castling moves are written as the king taking its own rook.
+        let mut chess960 = false;
+        let mut game_variant = variant::standard();
+
+        let stdin = io::stdin();
+        for line in stdin.lock().lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break
+            };
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+
+            match tokens.first() {
+                None => (),
+                Some(&"uci") => {
+                    println!("id name Chess");
+                    println!("id author Daresoul");
+                    println!("option name Threads type spin default 1 min 1 max {}", MAX_THREADS);
+                    println!("option name Hash type spin default 16 min 1 max {}", MAX_HASH);
+                    println!("option name Ponder type check default false");
+                    println!("option name MultiPV type spin default 1 min 1 max {}", MAX_MULTI_PV);
+                    println!("option name SkillLevel type spin default {} min 0 max {}", MAX_SKILL_LEVEL, MAX_SKILL_LEVEL);
+                    println!("option name LimitStrength type check default false");
+                    println!("option name Elo type spin default {} min {} max {}", MAX_ELO, MIN_ELO, MAX_ELO);
+                    let mcts = search.options().mcts.clone();
+                    println!("option name Backend type combo default AlphaBeta var AlphaBeta var MCTS");
+                    println!("option name MCTSExploration type spin default {} min 0 max 1000", (mcts.exploration * 100.0).round());
+                    println!("option name MCTSIterations type spin default {} min 1 max {}", mcts.iterations, MAX_MCTS_ITERATIONS);
+                    println!("option name MCTSPlayout type combo default Random var Random var CapturesFirst");
+                    println!("option name MCTSPlayoutDepth type spin default {} min 0 max {}", mcts.playout_depth, MAX_PLAYOUT_DEPTH);
+                    println!("option name EvalFile type string default <empty>");
+                    println!("option name NNUEFile type string default <empty>");
+                    println!("option name SyzygyPath type string default <empty>");
+                    println!("option name UCI_Chess960 type check default false");
+                    let names: Vec<String> = variant::all().iter().map(|known| format!("var {}", known.uci_name())).collect();
+                    println!("option name UCI_Variant type combo default {} {}", game_variant.uci_name(), names.join(" "));
+                    println!("uciok");
+                },
+                Some(&"isready") => println!("readyok"),
+                Some(&"setoption") => {
+                    stop_search(&search, &mut running);
+                    set_option(&mut search, &mut chess960, &mut game_variant, &tokens);
+                },
+                Some(&"ucinewgame") => {
+                    stop_search(&search, &mut running);
+                    search.clear();
+                    game = Game::with_variant(game_variant.clone());
+                },
+                Some(&"position") => {
+                    stop_search(&search, &mut running);
+                    match parse_position(&tokens, &game_variant) {
+                        Some(new_game) => game = new_game,
+                        None => println!("info string invalid position: {}", line)
+                    }
+                },
+                Some(&"go") => {
+                    wait_for_search(&mut running);
+                    let limits = parse_limits(&tokens, &game);
+                    let ranks = game.board.num_rows();
+
+                    running = Some(search.start(
+                        game.clone(),
+                        limits,
+                        |info| println!("{}", info),
+                        move |result| match (result.best_move.clone(), result.ponder_move()) {
+                            (Some(chess_move), Some(ponder_move)) => println!("bestmove {} ponder {}", move_text(&chess_move, chess960, ranks), move_text(&ponder_move, chess960, ranks)),
+                            (Some(chess_move), None) => println!("bestmove {}", move_text(&chess_move, chess960, ranks)),
+                            (None, _) => println!("bestmove 0000")
+                        }
+                    ));
+                },
+                Some(&"ponderhit") => search.ponder_hit(),
+                Some(&"stop") => stop_search(&search, &mut running),
+                Some(&"quit") => {
+                    stop_search(&search, &mut running);
+                    break;
+                },
+                Some(command) => println!("info string unknown command: {}", command)
+            }
+        }
+
+        wait_for_search(&mut running);
+    }
+
+    fn wait_for_search(running: &mut Option<JoinHandle<()>>) {
+        match running.take() {
+            Some(handle) => {
+                match handle.join() {
+                    Ok(_) => (),
+                    Err(_) => println!("info string search thread panicked")
+                }
+            },
+            None => ()
+        }
+    }
+
+    // A search without limits, like go infinite or go ponder, only ends when it is stopped.
+    fn stop_search(search: &Search, running: &mut Option<JoinHandle<()>>) {
+        search.stop();
+        wait_for_search(running);
+    }
+
+    fn move_text(chess_move: &ChessMove, chess960: bool, ranks: usize) -> String {
+        return if chess960 { chess_move.to_uci_chess960(ranks) } else { chess_move.to_uci(ranks) };
+    }
+
+    // setoption name <name> value <value>
+    fn set_option(search: &mut Search, chess960: &mut bool, game_variant: &mut Arc<dyn Variant>, tokens: &Vec<&str>) {
+        let name_index = tokens.iter().position(|token| *token == "name");
+        let value_index = tokens.iter().position(|token| *token == "value");
+
+        let (name, value) = match (name_index, value_index) {
+            (Some(n), Some(v)) if n < v => (tokens[n + 1..v].join(" "), tokens[v + 1..].join(" ")),
+            _ => {
+                println!("info string setoption needs a name and a value");
+                return;
+            }
+        };
+
+        // The variants belong to the protocol front-end, not to the search.
+        if name.to_lowercase() == "uci_chess960" {
+            match value.parse::<bool>() {
+                Ok(value) => *chess960 = value,
+                Err(_) => println!("info string invalid UCI_Chess960 value: {}", value)
+            }
+            return;
+        }
+        if name.to_lowercase() == "uci_variant" {
+            match variant::from_name(&value) {
+                Some(known) => *game_variant = known,
+                None => println!("info string unknown variant: {}", value)
+            }
+            return;
+        }
+
+        match apply_option(search, &name, &value) {
+            Ok(_) => (),
+            Err(message) => println!("info string {}", message)
+        }
+    }
+
+    // Sets an engine option by its protocol name, the match runner configures its engines the same way.
+    pub fn apply_option(search: &mut Search, name: &str, value: &str) -> Result<(), String> {
+        let mcts = search.options().mcts.clone();
+
+        match name.to_lowercase().as_str() {
+            "threads" => match value.parse::<usize>() {
+                Ok(threads) => search.set_threads(threads.clamp(1, MAX_THREADS)),
+                Err(_) => return Err(format!("invalid Threads value: {}", value))
+            },
+            "hash" => match value.parse::<usize>() {
+                Ok(hash) => search.set_hash_size(hash.clamp(1, MAX_HASH)),
+                Err(_) => return Err(format!("invalid Hash value: {}", value))
+            },
+            "multipv" => match value.parse::<usize>() {
+                Ok(multi_pv) => search.set_multi_pv(multi_pv.clamp(1, MAX_MULTI_PV)),
+                Err(_) => return Err(format!("invalid MultiPV value: {}", value))
+            },
+            "skilllevel" | "skill level" => match value.parse::<u8>() {
+                Ok(skill_level) => search.set_skill_level(skill_level),
+                Err(_) => return Err(format!("invalid SkillLevel value: {}", value))
+            },
+            "limitstrength" | "uci_limitstrength" => match value.parse::<bool>() {
+                Ok(limit_strength) => search.set_limit_strength(limit_strength),
+                Err(_) => return Err(format!("invalid LimitStrength value: {}", value))
+            },
+            "elo" | "uci_elo" => match value.parse::<u32>() {
+                Ok(elo) => search.set_elo(elo.clamp(MIN_ELO, MAX_ELO)),
+                Err(_) => return Err(format!("invalid Elo value: {}", value))
+            },
+            "backend" => match value.to_lowercase().as_str() {
+                "alphabeta" => search.set_backend(Backend::AlphaBeta),
+                "mcts" => search.set_backend(Backend::Mcts),
+                _ => return Err(format!("invalid Backend value: {}", value))
+            },
+            // The exploration constant is given in hundredths.
+            "mctsexploration" => match value.parse::<u32>() {
+                Ok(exploration) => search.set_mcts(MctsOptions { exploration: exploration.min(1000) as f64 / 100.0, ..mcts }),
+                Err(_) => return Err(format!("invalid MCTSExploration value: {}", value))
+            },
+            "mctsiterations" => match value.parse::<u64>() {
+                Ok(iterations) => search.set_mcts(MctsOptions { iterations: iterations.clamp(1, MAX_MCTS_ITERATIONS), ..mcts }),
+                Err(_) => return Err(format!("invalid MCTSIterations value: {}", value))
+            },
+            "mctsplayout" => match value.to_lowercase().as_str() {
+                "random" => search.set_mcts(MctsOptions { playout: PlayoutPolicy::Random, ..mcts }),
+                "capturesfirst" => search.set_mcts(MctsOptions { playout: PlayoutPolicy::CapturesFirst, ..mcts }),
+                _ => return Err(format!("invalid MCTSPlayout value: {}", value))
+            },
+            "mctsplayoutdepth" => match value.parse::<usize>() {
+                Ok(playout_depth) => search.set_mcts(MctsOptions { playout_depth: playout_depth.min(MAX_PLAYOUT_DEPTH), ..mcts }),
+                Err(_) => return Err(format!("invalid MCTSPlayoutDepth value: {}", value))
+            },
+            // Parameters written by the tuner, an empty value goes back to the built in ones.
+            "evalfile" => match value {
+                "" | "<empty>" => search.set_evaluator(Arc::new(MaterialEvaluator::new())),
+                path => match EvalParams::load(path) {
+                    Ok(params) => search.set_evaluator(Arc::new(MaterialEvaluator::with_params(params))),
+                    Err(err) => return Err(err.to_string())
+                }
+            },
+            // The network falls back to the evaluator that was set before it, an empty value goes back to the built in one.
+            "nnuefile" => match value {
+                "" | "<empty>" => search.set_evaluator(Arc::new(MaterialEvaluator::new())),
+                path => match Network::load(Path::new(path)) {
+                    Ok(network) => {
+                        let evaluator = NnueEvaluator::new(Arc::new(network), search.evaluator());
+                        search.set_evaluator(Arc::new(evaluator));
+                    },
+                    Err(err) => return Err(err.to_string())
+                }
+            },
+            // Directories separated like in PATH, an empty value turns the tablebases off.
+            "syzygypath" => match value {
+                "" | "<empty>" => search.clear_tablebase(),
+                paths => {
+                    let mut tablebase = Tablebase::new();
+                    for path in env::split_paths(paths) {
+                        match tablebase.add_directory(&path) {
+                            Ok(_) => (),
+                            Err(err) => return Err(format!("Couldnt read tablebase directory {}: {}", path.display(), err))
+                        }
+                    }
+                    search.set_tablebase(Arc::new(tablebase));
+                }
+            },
+            // Pondering is driven by go ponder, the option only tells the engine it may happen.
+            "ponder" => (),
+            _ => return Err(format!("unknown option: {}", name))
+        }
+
+        return Ok(());
+    }
+
+    //
+    // position startpos [moves ...]
+    // position fen <placement> <side> [castling] [en passant] [halfmove] [fullmove] [moves ...]
+    // The en passant field is not read, the game derives it from its move log.
+    fn parse_position(tokens: &Vec<&str>, game_variant: &Arc<dyn Variant>) -> Option<Game> {
+        let moves_index = tokens.iter().position(|token| *token == "moves").unwrap_or(tokens.len());
+
+        let mut game = match tokens.get(1) {
+            Some(&"startpos") => Game::with_variant(game_variant.clone()),
+            Some(&"fen") => game_from_variant_fen(&tokens[2..moves_index].join(" "), game_variant)?,
+            _ => return None
+        };
+
+        for token in tokens.iter().skip(moves_index + 1) {
+            let chess_move = find_move(&game, token)?;
+            let turn = game.get_turn();
+            game = game.move_piece(&turn, &chess_move).ok()?;
+            game.turn += 1;
+        }
+
+        return Some(game);
+    }
+
+    //
+    // Placement, side to move and castling rights of a FEN, X-FEN or Shredder-FEN. The other
+    // fields are not read, without a castling field kings and rooks on their standard squares
+    // may castle.
+    pub fn game_from_fen(fen: &str) -> Option<Game> {
+        return game_from_variant_fen(fen, &variant::standard());
+    }
+
+    //
+    // Like game_from_fen, the variant may read a field of its own after the castling rights.
+    // Boards outside the supported sizes give None.
+    pub fn game_from_variant_fen(fen: &str, game_variant: &Arc<dyn Variant>) -> Option<Game> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        let placement = fields.first()?;
+        let turn = match fields.get(1) {
+            Some(&"b") => 1,
+            _ => 0
+        };
+
+        let mut game = Game::create_board_from_string(placement, turn);
+        let sizes = MIN_BOARD_SIZE..=MAX_BOARD_SIZE;
+        if !sizes.contains(&game.board.num_rows()) || !sizes.contains(&game.board.num_columns()) {
+            return None;
+        }
+
+        game.variant = game_variant.clone();
+        match fields.get(2) {
+            Some(castling) => game.set_castling_from_fen(castling),
+            None => ()
+        }
+        if !game_variant.has_castling() {
+            game.castling = [None; 4];
+        }
+        for field in fields.iter().skip(3) {
+            if game_variant.read_fen_state(&mut game, field) {
+                break;
+            }
+        }
+        return Some(game);
+    }
+
+    // The full FEN of game, the move number counts from the position the game was set up from.
+    pub fn fen_from_game(game: &Game) -> String {
+        let mut rows = vec![];
+        for row in 0..game.board.num_rows() {
+            let mut text = String::new();
+            let mut empty = 0;
+            for column in 0..game.board.num_columns() {
+                match game.get_piece_from_position(&Position::new(column, row)) {
+                    None => empty += 1,
+                    Some((piece, color)) => {
+                        if empty > 0 {
+                            text.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        text.push(piece_definition::to_letter(&piece, &color));
+                        if game.promoted.contains(&Position::new(column, row)) {
+                            text.push('~');
+                        }
+                    }
+                }
+            }
+            if empty > 0 {
+                text.push_str(&empty.to_string());
+            }
+            rows.push(text);
+        }
+
+        // Pieces in hand follow the placement in brackets, like [Qn].
+        let pockets = if game.variant.uses_pockets() { format!("[{}]", game.pockets_to_fen()) } else { String::new() };
+
+        let castling = game.castling_to_fen();
+
+        // The square a double pawn push passed over.
+        let en_passant = match game.log.get_last_move() {
+            Some(last) if last.piece == Piece::Pawn && last.from.row.abs_diff(last.to.row) == 2 =>
+                Position::new(last.from.column, (last.from.row + last.to.row) / 2).to_algebraic(game.board.num_rows()),
+            _ => "-".to_string()
+        };
+
+        let side = if game.get_turn() == Color::White { "w" } else { "b" };
+        let state = match game.variant.fen_state(game) {
+            Some(state) => format!(" {}", state),
+            None => String::new()
+        };
+
+        return format!("{}{} {} {} {}{} {} {}", rows.join("/"), pockets, side, castling, en_passant, state, game.log.half_move_clock(), game.turn / 2 + 1);
+    }
+
+    pub fn find_move(game: &Game, uci_move: &str) -> Option<ChessMove> {
+        for chess_move in game.get_all_legal_moves() {
+            if chess_move.to_uci(game.board.num_rows()) == uci_move || chess_move.to_uci_chess960(game.board.num_rows()) == uci_move {
+                return Some(chess_move);
+            }
+        }
+
+        return None;
+    }
+
+    fn parse_number(tokens: &Vec<&str>, name: &str) -> Option<u64> {
+        let index = tokens.iter().position(|token| *token == name)?;
+        return tokens.get(index + 1)?.parse::<u64>().ok();
+    }
+
+    fn parse_limits(tokens: &Vec<&str>, game: &Game) -> SearchLimits {
+        let mut limits = SearchLimits::new();
+
+        limits.depth = parse_number(tokens, "depth").map(|depth| depth as usize);
+        limits.nodes = parse_number(tokens, "nodes");
+        limits.infinite = tokens.contains(&"infinite");
+        limits.ponder = tokens.contains(&"ponder");
+        limits.move_time = parse_number(tokens, "movetime").map(Duration::from_millis);
+
+        // With a clock, spend a share of the remaining time and half the increment.
+        if limits.move_time.is_none() {
+            let (time, increment) = if game.get_turn() == Color::White {
+                (parse_number(tokens, "wtime"), parse_number(tokens, "winc"))
+            } else {
+                (parse_number(tokens, "btime"), parse_number(tokens, "binc"))
+            };
+
+            match time {
+                Some(time) => {
+                    let moves_to_go = parse_number(tokens, "movestogo").unwrap_or(30).max(1);
+                    let budget = time / moves_to_go + increment.unwrap_or(0) / 2;
+                    limits.move_time = Some(Duration::from_millis(budget.min(time.saturating_sub(50)).max(1)));
+                },
+                None => ()
+            }
+        }
+
+        return limits;
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::path::PathBuf;
+        use super::*;
+        use crate::engine::tree::game_tree::GameTree;
+
+        #[test]
+        fn syzygy_path_loads_the_tables() {
+            let directory = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("syzygy");
+            let mut search = Search::new(EngineOptions::default());
+            assert!(apply_option(&mut search, "SyzygyPath", "/no/such/directory").is_err());
+            assert!(apply_option(&mut search, "SyzygyPath", directory.to_str().unwrap()).is_ok());
+
+            // Only the tablebase knows which rook move brings the win closer.
+            let mut tablebase = Tablebase::new();
+            tablebase.add_directory(&directory).unwrap();
+            let game = game_from_fen("8/8/8/2R5/1K6/8/5k2/8 w - - 0 1").unwrap();
+            let result = search.run(&game, &SearchLimits::depth(4), &mut |_info| ());
+            let after_move = GameTree::play(&game, &result.best_move.unwrap()).unwrap();
+            assert_eq!(tablebase.probe_dtz(&after_move).unwrap(), -20);
+        }
+    }
+}
@@ -0,0 +1,125 @@
+pub mod zobrist {
+    use std::sync::OnceLock;
+    use crate::chess::chess_move::chess_move::ChessMove;
+    use crate::chess::color::color::Color;
+    use crate::chess::game::game::Game;
+    use crate::chess::piece::piece::Piece;
+
+    // Squares are numbered row * 16 + column, boards can be up to 16 wide.
+    const SQUARES: usize = 256;
+
+    struct Keys {
+        // By board value, so piece and color, and square.
+        pieces: [[u64; SQUARES]; 32],
+        black_to_move: u64,
+        castling: [u64; 4],
+        en_passant: [u64; 16],
+        // By side and number of checks given, for Three-check.
+        checks: [[u64; 4]; 2],
+        // By side, piece and number of them in hand.
+        pockets: [[[u64; 16]; 6]; 2]
+    }
+
+    // Fixed seed so hashes are the same between runs.
+    fn keys() -> &'static Keys {
+        static KEYS: OnceLock<Keys> = OnceLock::new();
+
+        return KEYS.get_or_init(|| {
+            let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+            let mut next = || {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state
+            };
+
+            let mut pieces = [[0; SQUARES]; 32];
+            for piece in pieces.iter_mut() {
+                for square in piece.iter_mut() {
+                    *square = next();
+                }
+            }
+
+            let black_to_move = next();
+            let castling = [next(), next(), next(), next()];
+            let mut en_passant = [0; 16];
+            for file in en_passant.iter_mut() {
+                *file = next();
+            }
+
+            let mut checks = [[0; 4]; 2];
+            for side in checks.iter_mut() {
+                for count in side.iter_mut() {
+                    *count = next();
+                }
+            }
+
+            let mut pockets = [[[0; 16]; 6]; 2];
+            for side in pockets.iter_mut() {
+                for piece in side.iter_mut() {
+                    for count in piece.iter_mut() {
+                        *count = next();
+                    }
+                }
+            }
+
+            Keys {
+                pieces,
+                black_to_move,
+                castling,
+                en_passant,
+                checks,
+                pockets
+            }
+        });
+    }
+
+    pub fn hash(game: &Game) -> u64 {
+        let keys = keys();
+        let mut hash = 0;
+
+        for row in 0..game.board.num_rows() {
+            for column in 0..game.board.num_columns() {
+                match game.board.get(row, column) {
+                    Some(value) if *value != 0 => hash ^= keys.pieces[*value as usize][(row * 16 + column) % SQUARES],
+                    _ => ()
+                }
+            }
+        }
+
+        if game.get_turn() == Color::Black {
+            hash ^= keys.black_to_move;
+        }
+
+        for (i, right) in game.castling_rights().iter().enumerate() {
+            if *right {
+                hash ^= keys.castling[i];
+            }
+        }
+
+        match game.log.get_last_move() {
+            Some(last_move) if is_double_pawn_push(&last_move) => hash ^= keys.en_passant[last_move.to.column % 16],
+            _ => ()
+        }
+
+        for (side, given) in game.checks_given.iter().enumerate() {
+            if *given > 0 {
+                hash ^= keys.checks[side][(*given as usize).min(3)];
+            }
+        }
+
+        for (side, pocket) in game.pockets.iter().enumerate() {
+            for (piece, count) in pocket.iter().enumerate() {
+                if *count > 0 {
+                    hash ^= keys.pockets[side][piece][(*count as usize).min(15)];
+                }
+            }
+        }
+
+        return hash;
+    }
+
+    fn is_double_pawn_push(chess_move: &ChessMove) -> bool {
+        return chess_move.piece == Piece::Pawn && chess_move.from.row.abs_diff(chess_move.to.row) == 2;
+    }
+}
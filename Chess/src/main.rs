@@ -1,18 +1,300 @@
 extern crate core;
 
 use std::env;
+use std::fs;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use crate::chess::chess960::chess960;
 use crate::chess::game::game::Game;
+use crate::chess::variant::variant;
+use crate::engine::analysis_tree::analysis_tree::AnalysisTree;
+use crate::engine::evaluation::evaluation::{EvalParams, MaterialEvaluator};
+use crate::engine::nnue::nnue::Network;
+use crate::engine::datagen::datagen::{self, DataGenOptions};
+use crate::engine::match_runner::match_runner::{self, MatchOptions, MatchPlayer, TimeControl};
+use crate::engine::endgame_table::endgame_table::EndgameTables;
+use crate::engine::epd::epd;
+use crate::engine::perft::perft;
 use crate::engine::engine_moves::engine_moves::EngineMoves;
-use crate::engine::tree::game_tree::GameTree;
+use crate::engine::search::search::{format_score, Backend, EngineOptions, Search, SearchLimits};
+use crate::engine::skill::skill::SkillRandom;
+use crate::engine::syzygy::syzygy::Tablebase;
+use crate::engine::tree::game_tree::{GameTree, NodeId, ROOT};
+use crate::engine::tree_export::tree_export;
+use crate::engine::tree_export::tree_export::ExportLimits;
+use crate::engine::tuning::tuning::{self, TuningMethod, TuningOptions};
 
 mod main_graphics;
 mod chess;
 mod engine;
 
+fn load_tablebase() -> Option<Arc<Tablebase>> {
+    match env::var("syzygy") {
+        Ok(path) => {
+            let mut tablebase = Tablebase::new();
+            match tablebase.add_directory(&path) {
+                Ok(count) => println!("Loaded {} tablebase files from {}, up to {} pieces", count, path, tablebase.max_pieces()),
+                Err(err) => println!("Couldnt read tablebase directory {}: {}", path, err)
+            }
+            Some(Arc::new(tablebase))
+        },
+        Err(_) => None
+    }
+}
+
+//
+// Loads the endgame tables in the directory named by endgame_tables. The materials listed
+// in endgame_generate, like KQK,KRK,KPK,KBNK, are generated when missing and saved there.
+fn load_endgame_tables() -> Option<Arc<EndgameTables>> {
+    let directory = env::var("endgame_tables").ok()?;
+    let mut tables = EndgameTables::new();
+    match tables.load_directory(&directory) {
+        Ok(count) => println!("Loaded {} endgame tables from {}", count, directory),
+        Err(err) => println!("{}", err)
+    }
+
+    let mut generated = false;
+    for material in env::var("endgame_generate").unwrap_or(String::new()).split(',') {
+        if material.is_empty() || tables.contains(material) {
+            continue;
+        }
+
+        let start = Instant::now();
+        match tables.generate(material) {
+            Ok(_) => generated = true,
+            Err(err) => {
+                println!("{}", err);
+                continue;
+            }
+        }
+        match tables.table(material) {
+            Some(table) => println!("Generated {} in {:.1}s, {} won positions, longest mate {} plies",
+                table.name(), start.elapsed().as_secs_f64(), table.win_count(), table.longest_mate()),
+            None => ()
+        }
+    }
+
+    if generated {
+        match tables.save_directory(&directory) {
+            Ok(count) => println!("Saved {} endgame tables to {}", count, directory),
+            Err(err) => println!("{}", err)
+        }
+    }
+
+    return Some(Arc::new(tables));
+}
+
+//
+// Lets the search play the endgame in endgame_fen against itself and checks every move
+// against the tables, a move is wrong when it gives a worse result than the best one.
+fn verify_endgame_play(search: &Search, tables: &EndgameTables) {
+    let fen = env::var("endgame_fen").unwrap_or("8/8/8/3k4/8/8/8/1Q2K3 w".to_string());
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+    let turn = if fields.get(1) == Some(&"b") { 1 } else { 0 };
+    let mut game = Game::create_board_from_string(fields.first().unwrap_or(&""), turn);
+
+    let mut wrong_moves = 0;
+    for _ in 0..100 {
+        let best = match tables.best_move(&game) {
+            Ok(Some((_, best))) => best,
+            Ok(None) => break,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+
+        let chess_move = match search.run(&game, &SearchLimits::depth(1), &mut |_info| ()).best_move {
+            Some(chess_move) => chess_move,
+            None => break
+        };
+        let played = match tables.probe_move(&game, &chess_move) {
+            Ok(played) => played,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+
+        if played != best {
+            wrong_moves += 1;
+        }
+        println!("{} {} (best {})", chess_move.to_san(&game), played, best);
+
+        game = match GameTree::play(&game, &chess_move) {
+            Some(after_move) => after_move,
+            None => break
+        };
+    }
+
+    println!("Endgame played with {} moves worse than the tables", wrong_moves);
+}
+
+// Writes the top of the tree to the files named by the tree_dot and tree_json variables.
+fn export_tree(tree: &GameTree) {
+    let mut limits = ExportLimits::new();
+    limits.max_depth = Some(2);
+    limits.max_width = Some(5);
+
+    match env::var("tree_dot") {
+        Ok(path) => match fs::write(&path, tree_export::to_dot(tree, &limits)) {
+            Ok(_) => println!("Wrote tree to {}", path),
+            Err(err) => println!("Couldnt write {}: {}", path, err)
+        },
+        Err(_) => ()
+    }
+
+    match env::var("tree_json") {
+        Ok(path) => match fs::write(&path, tree_export::to_json(tree, &limits)) {
+            Ok(_) => println!("Wrote tree to {}", path),
+            Err(err) => println!("Couldnt write {}: {}", path, err)
+        },
+        Err(_) => ()
+    }
+}
+
+// An engine for the match runner, options are given as Name=Value pairs split by semicolons like in setoption.
+fn match_player(name: &str, options: &str) -> MatchPlayer {
+    let mut search = Search::new(EngineOptions::default());
+    for option in options.split(';').filter(|option| !option.trim().is_empty()) {
+        let (option_name, value) = option.split_once('=').unwrap_or((option, ""));
+        match engine::uci::uci::apply_option(&mut search, option_name.trim(), value.trim()) {
+            Ok(_) => (),
+            Err(message) => println!("{}: {}", name, message)
+        }
+    }
+
+    return MatchPlayer {
+        name: name.to_string(),
+        search
+    }
+}
+
+//
+// Plays the engine configured by match_a against the one configured by match_b for the
+// number of games in match. The openings come from the file in match_openings, one FEN
+// per line, the variant from match_variant and the games are written to match_pgn.
+fn run_match(games: &str) {
+    let games = match games.parse::<usize>() {
+        Ok(games) => games,
+        Err(_) => {
+            println!("Couldnt read number of games {}", games);
+            return;
+        }
+    };
+
+    let control = match (env::var("match_movetime"), env::var("match_nodes")) {
+        (Ok(move_time), _) => TimeControl::MoveTime(Duration::from_millis(move_time.parse::<u64>().unwrap_or(100))),
+        (_, Ok(nodes)) => TimeControl::Nodes(nodes.parse::<u64>().unwrap_or(2000)),
+        _ => TimeControl::Nodes(2000)
+    };
+    let mut options = MatchOptions::new(games, control);
+
+    match env::var("match_variant") {
+        Ok(name) => match variant::from_name(&name) {
+            Some(known) => options.variant = known,
+            None => println!("Unknown variant {}, playing standard chess", name)
+        },
+        Err(_) => ()
+    }
+
+    match env::var("match_openings") {
+        Ok(path) => match fs::read_to_string(&path) {
+            Ok(text) => options.openings = text.lines()
+                .map(|line| line.trim().to_string())
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .collect(),
+            Err(err) => println!("Couldnt read {}: {}", path, err)
+        },
+        Err(_) => ()
+    }
+
+    match env::var("match_sprt") {
+        Ok(bounds) => match bounds.split_once(',') {
+            Some((elo0, elo1)) => {
+                options.sprt.elo0 = elo0.trim().parse::<f64>().unwrap_or(options.sprt.elo0);
+                options.sprt.elo1 = elo1.trim().parse::<f64>().unwrap_or(options.sprt.elo1);
+                options.stop_on_sprt = true;
+            },
+            None => println!("match_sprt needs elo0,elo1")
+        },
+        Err(_) => ()
+    }
+
+    let first = match_player("A", &env::var("match_a").unwrap_or(String::new()));
+    let second = match_player("B", &env::var("match_b").unwrap_or(String::new()));
+
+    let result = match_runner::run_match(&first, &second, &options, &mut |game, score| {
+        println!("Game {}: {} - {} {} ({}), A {}", game.round, game.white, game.black, game.result, game.reason, score);
+    });
+
+    let (elo, error) = result.score.elo();
+    println!("Score of A vs B: {} [{:.3}] {} games", result.score, result.score.score(), result.score.games());
+    println!("Elo difference: {:.1} +/- {:.1}", elo, error);
+    println!("SPRT ({:.1}, {:.1}): LLR {:.2}, {}", options.sprt.elo0, options.sprt.elo1, result.llr, result.verdict);
+
+    let path = env::var("match_pgn").unwrap_or("match.pgn".to_string());
+    match fs::write(&path, result.to_pgn()) {
+        Ok(_) => println!("Wrote games to {}", path),
+        Err(err) => println!("Couldnt write {}: {}", path, err)
+    }
+}
+
+// Solves the position in the fen variable, placement and side to move, for a mate in the number of moves given by mate.
+fn run_mate_solver(moves: &str) {
+    let n = match moves.parse::<usize>() {
+        Ok(n) => n,
+        Err(_) => {
+            println!("Couldnt read mate length {}", moves);
+            return;
+        }
+    };
+
+    let fen = env::var("fen").unwrap_or("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w".to_string());
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+    let turn = if fields.get(1) == Some(&"b") { 1 } else { 0 };
+    let game = Game::create_board_from_string(fields.first().unwrap_or(&""), turn);
+
+    let solution = engine::engine::engine::solve_mate(&game, n);
+    println!("Searched {} nodes for a mate in {}", solution.nodes, solution.n);
+    for chess_move in solution.undecided.iter() {
+        println!("Undecided: {}", chess_move.to_san(&game));
+    }
+    if !solution.is_mate() {
+        println!("No forced mate");
+        return;
+    }
+
+    let keys: Vec<String> = solution.key_moves.iter().map(|chess_move| chess_move.to_san(&game)).collect();
+    println!("Mate in {} with {}", solution.mate_in(), keys.join(", "));
+    if solution.has_unique_key() {
+        println!("The key is unique");
+    } else {
+        println!("The key is not unique");
+    }
+    print_solution(&solution.tree, ROOT, &game, 0);
+}
+
+// Every line of the solution, one move per row indented by ply.
+fn print_solution(tree: &GameTree, node: NodeId, game: &Game, ply: usize) {
+    for edge in tree.children(node) {
+        println!("{}{}", "  ".repeat(ply), edge.chess_move.to_san(game));
+        match GameTree::play(game, &edge.chess_move) {
+            Some(after_move) => print_solution(tree, edge.child, &after_move, ply + 1),
+            None => ()
+        }
+    }
+}
+
 fn run_engine() {
     let mut game = Game::default(); //Game::create_board_from_string("2ppp3/2pqp3/2ppp4/8/8/2PPP3/2PQP3/2PPP3", 0);
     println!("Game made");
-    let tree = engine::engine::engine::tree_init(game, 3);
+    let tablebase = load_tablebase();
+    let mut tree = match &tablebase {
+        Some(tablebase) => engine::engine::engine::tree_init_with_tablebase(game.clone(), 3, tablebase),
+        None => engine::engine::engine::tree_init(game.clone(), 3)
+    };
 
     let moves = engine::engine::engine::show_all_moves(&tree);
     engine::engine_moves::engine_moves::EngineMoves::print_list_of_engine_moves(&moves);
@@ -22,16 +304,476 @@ fn run_engine() {
     println!("nodes: {}", count);
     let leaves = engine::engine::engine::count_leaves(&tree);
     println!("leaves: {}", leaves);
+
+    let value = tree.evaluate(&MaterialEvaluator::new());
+    println!("Tree value {} with line {}", value, tree.best_line().to_uci_string(game.board.num_rows()));
+    export_tree(&tree);
+
+    let shared = engine::engine::engine::tree_init_with_transpositions(game.clone(), 4);
+    println!("Depth 4 with shared transpositions: {} nodes", engine::engine::engine::count_nodes(&shared));
+
+    println!("Best lines:");
+    for line in engine::engine::engine::best_lines(&game, 3, 3) {
+        println!("{} {}", engine::search::search::format_score(line.score), line.pv.to_uci_string(game.board.num_rows()));
+    }
+
+    // The analysis tree keeps its subtree when a move is played.
+    let mut analysis = AnalysisTree::new(game.clone(), 50000);
+    match &tablebase {
+        Some(tablebase) => analysis.set_tablebase(tablebase.clone()),
+        None => ()
+    }
+    while analysis.depth() < 3 && analysis.deepen() {}
+    println!("Analysis tree depth {} with {} nodes", analysis.depth(), analysis.node_count());
+    match analysis.best_move() {
+        Some((chess_move, value)) => {
+            println!("Analysis best move {} ({})", chess_move.to_uci(game.board.num_rows()), value);
+            analysis.play_move(&chess_move);
+            println!("After {} the tree keeps depth {} with {} nodes", chess_move.to_uci(game.board.num_rows()), analysis.depth(), analysis.node_count());
+        },
+        None => ()
+    }
+
+    let mut options = EngineOptions::default();
+    options.threads = match env::var("threads") {
+        Ok(t) => t.parse::<usize>().unwrap_or(1),
+        Err(_) => 1
+    };
+
+    let mut search = Search::new(options);
+    match tablebase {
+        Some(tablebase) => search.set_tablebase(tablebase),
+        None => ()
+    }
+    match load_endgame_tables() {
+        Some(tables) => {
+            search.set_endgame_tables(tables.clone());
+            verify_endgame_play(&search, &tables);
+        },
+        None => ()
+    }
+
+    // Monte Carlo tree search on the same position, to compare with the alpha-beta result below.
+    let mut mcts_search = search.clone();
+    mcts_search.set_backend(Backend::Mcts);
+    let mut mcts_limits = SearchLimits::new();
+    mcts_limits.nodes = Some(1000);
+    let mcts_result = mcts_search.run(&game, &mcts_limits, &mut |_info| ());
+    match mcts_result.best_move {
+        Some(chess_move) => println!("MCTS bestmove {} after {} iterations, {}", chess_move.to_uci(game.board.num_rows()), mcts_result.nodes, format_score(mcts_result.score)),
+        None => println!("No moves")
+    }
+
+    println!("Searching with {} threads", search.options().threads);
+    let result = search.run(&game, &SearchLimits::depth(4), &mut |info| println!("{}", info));
+    match result.best_move {
+        Some(chess_move) => println!("bestmove {}", chess_move.to_uci(game.board.num_rows())),
+        None => println!("No moves")
+    }
     ()
 }
 
+//
+// Runs a test suite like WAC or STS, "chess epd <file> [depth N | movetime MS | nodes N]".
+// Engine options are read from epd_options as Name=Value pairs like for the match runner.
+fn run_epd(args: &[String]) {
+    let path = match args.first() {
+        Some(path) => path,
+        None => {
+            println!("Usage: chess epd <file> [depth N | movetime MS | nodes N]");
+            return;
+        }
+    };
+
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) => {
+            println!("Couldnt read {}: {}", path, err);
+            return;
+        }
+    };
+
+    let mut limits = SearchLimits::new();
+    let mut rest = args[1..].iter();
+    while let Some(name) = rest.next() {
+        let value = rest.next().map(|value| value.parse::<u64>().ok()).flatten();
+        match (name.as_str(), value) {
+            ("depth", Some(depth)) => limits.depth = Some(depth as usize),
+            ("movetime", Some(move_time)) => limits.move_time = Some(Duration::from_millis(move_time)),
+            ("nodes", Some(nodes)) => limits.nodes = Some(nodes),
+            _ => println!("Ignoring limit {}", name)
+        }
+    }
+    if limits.depth.is_none() && limits.move_time.is_none() && limits.nodes.is_none() {
+        limits.move_time = Some(Duration::from_millis(1000));
+    }
+
+    let mut positions = vec![];
+    for position in epd::parse_suite(&text) {
+        match position {
+            Ok(position) => positions.push(position),
+            Err(err) => println!("{}", err)
+        }
+    }
+
+    let search = match_player("epd", &env::var("epd_options").unwrap_or(String::new())).search;
+    let report = epd::run_suite(&search, &positions, &limits, &mut |outcome| {
+        let played = outcome.played.clone().unwrap_or("no move".to_string());
+        match outcome.solved_at {
+            Some(depth) => println!("{} solved at depth {}: {} ({})", outcome.id, depth, played, outcome.expected),
+            None if outcome.solved => println!("{} solved: {} ({})", outcome.id, played, outcome.expected),
+            None => println!("{} failed: {} ({})", outcome.id, played, outcome.expected)
+        }
+        if outcome.max_points > 1 {
+            println!("  {} of {} points", outcome.points, outcome.max_points);
+        }
+    });
+
+    println!("{}", report);
+}
+
+//
+// Tunes the evaluation on labelled positions, "chess tune <file> [method local | gradient]
+// [iterations N] [rate R] [k K] [params FILE] [out FILE]". The output can be loaded with EvalFile.
+fn run_tune(args: &[String]) {
+    let path = match args.first() {
+        Some(path) => path,
+        None => {
+            println!("Usage: chess tune <file> [method local | gradient] [iterations N] [rate R] [k K] [params FILE] [out FILE]");
+            return;
+        }
+    };
+
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) => {
+            println!("Couldnt read {}: {}", path, err);
+            return;
+        }
+    };
+
+    let mut gradient = false;
+    let mut learning_rate = 1_000_000.0;
+    let mut options = TuningOptions::new(TuningMethod::LocalSearch, 100);
+    let mut params = EvalParams::default();
+    let mut out = "eval_params.txt".to_string();
+
+    let mut rest = args[1..].iter();
+    while let Some(name) = rest.next() {
+        let value = match rest.next() {
+            Some(value) => value,
+            None => {
+                println!("{} needs a value", name);
+                return;
+            }
+        };
+
+        match name.as_str() {
+            "method" => gradient = value == "gradient",
+            "iterations" => options.iterations = value.parse::<usize>().unwrap_or(options.iterations),
+            "rate" => learning_rate = value.parse::<f64>().unwrap_or(learning_rate),
+            "k" => options.k = value.parse::<f64>().ok(),
+            "params" => match EvalParams::load(value) {
+                Ok(loaded) => params = loaded,
+                Err(err) => {
+                    println!("{}", err);
+                    return;
+                }
+            },
+            "out" => out = value.clone(),
+            _ => println!("Ignoring {}", name)
+        }
+    }
+    if gradient {
+        options.method = TuningMethod::GradientDescent { learning_rate };
+    }
+
+    let mut positions = vec![];
+    for position in tuning::parse_positions(&text) {
+        match position {
+            Ok(position) => positions.push(position),
+            Err(err) => println!("{}", err)
+        }
+    }
+    println!("Tuning on {} positions", positions.len());
+
+    let start = Instant::now();
+    let tuned = tuning::tune(&positions, &params, &options, &mut |iteration, error| {
+        println!("Iteration {}: error {:.6} after {:.1}s", iteration, error, start.elapsed().as_secs_f64());
+    });
+
+    match fs::write(&out, tuned.to_text()) {
+        Ok(_) => println!("Wrote parameters to {}", out),
+        Err(err) => println!("Couldnt write {}: {}", out, err)
+    }
+}
+
+//
+// Checks a network file, "chess nnue <file> [plies N] [seed S]". Random games are played
+// from the fen variable and the updated accumulator is compared with a fresh one after every move.
+fn run_nnue_check(args: &[String]) {
+    let path = match args.first() {
+        Some(path) => path,
+        None => {
+            println!("Usage: chess nnue <file> [plies N] [seed S]");
+            return;
+        }
+    };
+
+    let network = match Network::load(std::path::Path::new(path)) {
+        Ok(network) => network,
+        Err(err) => {
+            println!("{}", err);
+            return;
+        }
+    };
+
+    let mut plies = 200;
+    let mut seed = 1;
+    let mut rest = args[1..].iter();
+    while let Some(name) = rest.next() {
+        let value = rest.next().map(|value| value.parse::<u64>().ok()).flatten();
+        match (name.as_str(), value) {
+            ("plies", Some(value)) => plies = value as usize,
+            ("seed", Some(value)) => seed = value,
+            _ => println!("Ignoring {}", name)
+        }
+    }
+
+    let fen = env::var("fen").unwrap_or("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w".to_string());
+    let game = match engine::uci::uci::game_from_fen(&fen) {
+        Some(game) => game,
+        None => {
+            println!("Invalid fen {}", fen);
+            return;
+        }
+    };
+    println!("{} hidden neurons, evaluation {}", network.hidden_size(), network.evaluate(&game));
+
+    let start = Instant::now();
+    match network.check_updates(&game, plies, seed) {
+        Ok(played) => println!("Incremental and refreshed evaluations agree over {} plies ({:.2}s)", played, start.elapsed().as_secs_f64()),
+        Err(err) => println!("{}", err)
+    }
+}
+
+//
+// Generates training data from self-play, "chess datagen <games> [depth N | nodes N] [random N]
+// [seed S] [shard I/N] [openings FILE] [out NAME]". Positions are written to NAME.txt as
+// "fen | score | result" and to NAME.bin, with the shard added to the name when sharding.
+fn run_datagen(args: &[String]) {
+    let games = match args.first().map(|games| games.parse::<usize>()) {
+        Some(Ok(games)) => games,
+        _ => {
+            println!("Usage: chess datagen <games> [depth N | nodes N] [random N] [seed S] [shard I/N] [openings FILE] [out NAME]");
+            return;
+        }
+    };
+
+    let mut options = DataGenOptions::new(games, TimeControl::Depth(4));
+    let mut out = "training".to_string();
+
+    let mut rest = args[1..].iter();
+    while let Some(name) = rest.next() {
+        let value = match rest.next() {
+            Some(value) => value,
+            None => {
+                println!("{} needs a value", name);
+                return;
+            }
+        };
+
+        match name.as_str() {
+            "depth" => options.control = TimeControl::Depth(value.parse::<usize>().unwrap_or(4)),
+            "nodes" => options.control = TimeControl::Nodes(value.parse::<u64>().unwrap_or(5000)),
+            "random" => options.random_plies = value.parse::<usize>().unwrap_or(options.random_plies),
+            "seed" => options.seed = value.parse::<u64>().unwrap_or(options.seed),
+            "shard" => match value.split_once('/').map(|(shard, shards)| (shard.parse::<usize>(), shards.parse::<usize>())) {
+                Some((Ok(shard), Ok(shards))) if shard < shards => {
+                    options.shard = shard;
+                    options.shards = shards;
+                },
+                _ => println!("shard needs to look like 0/4")
+            },
+            "openings" => match fs::read_to_string(value) {
+                Ok(text) => options.openings = text.lines()
+                    .map(|line| line.trim().to_string())
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .collect(),
+                Err(err) => println!("Couldnt read {}: {}", value, err)
+            },
+            "out" => out = value.clone(),
+            _ => println!("Ignoring {}", name)
+        }
+    }
+    if options.shards > 1 {
+        out = format!("{}.{}", out, options.shard);
+    }
+
+    let (text_path, binary_path) = (format!("{}.txt", out), format!("{}.bin", out));
+    let files = (fs::File::create(&text_path), fs::File::create(&binary_path));
+    let (mut text_file, mut binary_file) = match files {
+        (Ok(text_file), Ok(binary_file)) => (text_file, binary_file),
+        (Err(err), _) | (_, Err(err)) => {
+            println!("Couldnt create {}: {}", out, err);
+            return;
+        }
+    };
+
+    let search = match_player("datagen", &env::var("datagen_options").unwrap_or(String::new())).search;
+    let mut failed = binary_file.write_all(&datagen::binary_header()).err();
+    let mut total = 0;
+    let start = Instant::now();
+
+    datagen::generate(&search, &options, &mut |number, result, positions| {
+        let mut text = String::new();
+        let mut binary = vec![];
+        for position in positions.iter() {
+            text.push_str(&position.to_text());
+            text.push('\n');
+            position.write_binary(&mut binary);
+        }
+
+        if failed.is_none() {
+            failed = text_file.write_all(text.as_bytes()).and_then(|_| binary_file.write_all(&binary)).err();
+        }
+        total += positions.len();
+        println!("Game {}: {}, {} positions, {} in total after {:.1}s", number + 1, result, positions.len(), total, start.elapsed().as_secs_f64());
+    });
+
+    match failed {
+        None => println!("Wrote {} positions to {} and {}", total, text_path, binary_path),
+        Some(err) => println!("Couldnt write {}: {}", out, err)
+    }
+}
+
+// chess chess960 [index | check], prints a start position, a random one without an index.
+fn run_chess960(args: &[String]) {
+    match args.first().map(|arg| arg.as_str()) {
+        Some("check") => {
+            let problems = chess960::check_positions();
+            for problem in problems.iter() {
+                println!("{}", problem);
+            }
+            println!("{} positions, {} problems", chess960::POSITIONS, problems.len());
+        },
+        argument => {
+            let index = match argument.map(|index| index.parse::<usize>()) {
+                Some(Ok(index)) if index < chess960::POSITIONS => index,
+                Some(_) => {
+                    println!("Usage: chess chess960 [0-{} | check]", chess960::POSITIONS - 1);
+                    return;
+                },
+                None => (SkillRandom::new().next() % chess960::POSITIONS as u64) as usize
+            };
+
+            let game = chess960::start_position(index);
+            println!("{} {}", index, engine::uci::uci::fen_from_game(&game));
+            println!("{} legal moves", game.get_all_legal_moves().len());
+        }
+    }
+}
+
+//
+// chess perft check [max nodes] compares known positions with their move counts, by default up
+// to 100000 leaves. chess perft <depth> [variant] [fen] counts the moves of a position, by
+// default the start of the variant, with the count below each move.
+fn run_perft(args: &[String]) {
+    match args.first().map(|arg| arg.as_str()) {
+        Some("check") => {
+            let max_nodes = args.get(1).and_then(|nodes| nodes.parse::<u64>().ok()).unwrap_or(100000);
+            let start = Instant::now();
+            let problems = perft::check_cases(max_nodes);
+            for problem in problems.iter() {
+                println!("{}", problem);
+            }
+            println!("{} positions, {} problems in {:.1}s", perft::CASES.len() + perft::CHESS960_CASES.len(), problems.len(), start.elapsed().as_secs_f64());
+        },
+        argument => {
+            let depth = match argument.map(|depth| depth.parse::<usize>()) {
+                Some(Ok(depth)) if depth > 0 => depth,
+                _ => {
+                    println!("Usage: chess perft [check [max nodes] | depth [variant] [fen]]");
+                    return;
+                }
+            };
+            let game_variant = match args.get(1) {
+                Some(name) => match variant::from_name(name) {
+                    Some(game_variant) => game_variant,
+                    None => {
+                        println!("Unknown variant {}", name);
+                        return;
+                    }
+                },
+                None => variant::standard()
+            };
+            let game = match args.get(2) {
+                Some(_) => match engine::uci::uci::game_from_variant_fen(&args[2..].join(" "), &game_variant) {
+                    Some(game) => game,
+                    None => {
+                        println!("Couldnt read the FEN");
+                        return;
+                    }
+                },
+                None => Game::with_variant(game_variant)
+            };
+
+            let start = Instant::now();
+            let mut total = 0;
+            for chess_move in game.get_all_legal_moves().iter() {
+                let nodes = match GameTree::play(&game, chess_move) {
+                    Some(after_move) => perft::perft(&after_move, depth - 1),
+                    None => 0
+                };
+                println!("{}: {}", chess_move.to_uci(game.board.num_rows()), nodes);
+                total += nodes;
+            }
+            println!("{} nodes at depth {} in {:.1}s", total, depth, start.elapsed().as_secs_f64());
+        }
+    }
+}
+
 fn main() {
     let use_graphics = match env::var("graphics") {
         Ok(t) => t,
         Err(err) => "0".to_string()
      };
 
-    if use_graphics == "1" {
+    let use_uci = match env::var("uci") {
+        Ok(t) => t,
+        Err(_) => "0".to_string()
+    };
+
+    let args: Vec<String> = env::args().collect();
+
+    if args.get(1).map(|command| command.as_str()) == Some("epd") {
+        run_epd(&args[2..])
+    }
+    else if args.get(1).map(|command| command.as_str()) == Some("tune") {
+        run_tune(&args[2..])
+    }
+    else if args.get(1).map(|command| command.as_str()) == Some("nnue") {
+        run_nnue_check(&args[2..])
+    }
+    else if args.get(1).map(|command| command.as_str()) == Some("datagen") {
+        run_datagen(&args[2..])
+    }
+    else if args.get(1).map(|command| command.as_str()) == Some("chess960") {
+        run_chess960(&args[2..])
+    }
+    else if args.get(1).map(|command| command.as_str()) == Some("perft") {
+        run_perft(&args[2..])
+    }
+    else if use_uci == "1" {
+        engine::uci::uci::run_uci()
+    }
+    else if let Ok(moves) = env::var("mate") {
+        run_mate_solver(&moves)
+    }
+    else if let Ok(games) = env::var("match") {
+        run_match(&games)
+    }
+    else if use_graphics == "1" {
         main_graphics::run_graphics()
     }
     else {
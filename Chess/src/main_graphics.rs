@@ -1,11 +1,50 @@
 extern crate core;
 
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
 use ggez::graphics::Image;
 use ggez::*;
 use ggez::graphics::Drawable;
-use crate::chess::chess_move::chess_move::ChessMove;
+use ggez::input::keyboard::{KeyCode, KeyInput};
+use crate::chess::chess960::chess960;
+use crate::chess::chess_move::chess_move::{ChessMove, MoveType};
+use crate::chess::color::color::Color;
 use crate::chess::game::game::Game;
+use crate::chess::piece::piece::Piece;
 use crate::chess::position::position::Position;
+use crate::chess::umpire::umpire;
+use crate::chess::variant::variant;
+use crate::engine::analysis_tree::analysis_tree::AnalysisTree;
+use crate::engine::search::search::{EngineOptions, Search, SearchLimits, SearchResult};
+use crate::engine::skill::skill::{Skill, SkillRandom};
+use crate::engine::zobrist::zobrist;
+
+// Difficulty settings offered in the UI, with the skill level they use.
+const DIFFICULTIES: [(&str, u8); 5] = [
+    ("Beginner", 0),
+    ("Novice", 5),
+    ("Intermediate", 10),
+    ("Advanced", 15),
+    ("Maximum", 20)
+];
+
+const ENGINE_MOVE_TIME: Duration = Duration::from_secs(2);
+
+// The analysis tree is deepened one ply at a time on a worker thread until it reaches this depth.
+const ANALYSIS_MAX_DEPTH: usize = 6;
+const ANALYSIS_NODE_BUDGET: usize = 20000;
+
+// Pieces in hand are drawn below the board, white's row above black's.
+const POCKET_PIECES: [Piece; 5] = [Piece::Pawn, Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen];
+const POCKET_TOP: f32 = 915.0;
+const POCKET_LEFT: f32 = 15.0;
+const POCKET_SIZE: f32 = 70.0;
+const POCKET_SPACING: f32 = 110.0;
+
+// The board is this many pixels on its longer side whatever the number of squares, on 8x8 a square is 100.
+const BOARD_PIXELS: f32 = 800.0;
 
 #[derive(Clone)]
 struct State {
@@ -17,6 +56,28 @@ struct State {
     mouse_down: bool,
 
     selected: Option<Position>,
+    // A piece in hand picked to be dropped on the next square clicked.
+    selected_drop: Option<Piece>,
+    // What the umpire said about the last move or try, in Kriegspiel.
+    announcements: Vec<String>,
+    // In Kriegspiel between two players the board is hidden until the next one clicks.
+    handoff: bool,
+
+    search: Search,
+    // The color the engine plays, None when both sides are played by hand.
+    engine_color: Option<Color>,
+    difficulty: usize,
+    thinking: bool,
+    // Results are tagged with the search they came from, so aborted searches can be ignored.
+    search_id: u32,
+    engine_result: Arc<Mutex<Option<(u32, SearchResult)>>>,
+    engine_thread: Arc<Mutex<Option<JoinHandle<()>>>>,
+    // Hash of the position the engine is pondering on while the human thinks.
+    ponder_hash: Option<u64>,
+    // Kept between moves so the analysis gets deeper instead of restarting.
+    analysis: Option<AnalysisTree>,
+    // Deepens a copy of the tree by a ply, the last finished tree is drawn meanwhile.
+    analysis_thread: Arc<Mutex<Option<JoinHandle<AnalysisTree>>>>,
 
     white_pawn: Image,
     black_pawn: Image,
@@ -65,6 +126,20 @@ impl State {
             pos_y: 100.0,
             mouse_down: false,
             selected: None,
+            selected_drop: None,
+            announcements: vec![],
+            handoff: false,
+
+            search: Search::new(EngineOptions::default()),
+            engine_color: None,
+            difficulty: DIFFICULTIES.len() - 1,
+            thinking: false,
+            search_id: 0,
+            engine_result: Arc::new(Mutex::new(None)),
+            engine_thread: Arc::new(Mutex::new(None)),
+            ponder_hash: None,
+            analysis: None,
+            analysis_thread: Arc::new(Mutex::new(None)),
 
             white_pawn: pawn_white_image,
             black_pawn: pawn_black_image,
@@ -88,10 +163,349 @@ impl State {
 
         Ok(self.clone())
     }
+
+    fn set_difficulty(&mut self, difficulty: usize) {
+        self.difficulty = difficulty.min(DIFFICULTIES.len() - 1);
+        let (_name, skill_level) = DIFFICULTIES[self.difficulty];
+        self.search.set_skill_level(skill_level);
+    }
+
+    fn is_engine_turn(&self) -> bool {
+        return match &self.engine_color {
+            Some(color) => *color == self.game.get_turn(),
+            None => false
+        }
+    }
+
+    fn wait_for_engine(&mut self) {
+        let handle = self.engine_thread.lock().unwrap().take();
+        match handle {
+            Some(handle) => match handle.join() {
+                Ok(_) => (),
+                Err(_) => println!("The engine thread panicked.")
+            },
+            None => ()
+        }
+    }
+
+    fn start_engine(&mut self, game: Game, ponder: bool) {
+        self.wait_for_engine();
+
+        self.search_id += 1;
+        let search_id = self.search_id;
+        let engine_result = self.engine_result.clone();
+
+        let mut limits = SearchLimits::new();
+        limits.move_time = Some(ENGINE_MOVE_TIME);
+
+        let on_done = move |result| {
+            *engine_result.lock().unwrap() = Some((search_id, result));
+        };
+
+        let handle = if ponder {
+            self.search.ponder(game, limits, |_info| (), on_done)
+        } else {
+            self.search.start(game, limits, |_info| (), on_done)
+        };
+
+        *self.engine_thread.lock().unwrap() = Some(handle);
+    }
+
+    // Stops whatever the engine is doing and forgets its result.
+    fn abort_engine(&mut self) {
+        self.search.stop();
+        self.wait_for_engine();
+        self.search_id += 1;
+        self.thinking = false;
+        self.ponder_hash = None;
+    }
+
+    fn play_engine_move(&mut self, result: SearchResult) {
+        self.thinking = false;
+
+        // Without a move the game is over, update does not start the engine again.
+        match result.best_move.clone() {
+            Some(chess_move) => {
+                let turn = self.game.get_turn();
+                match self.game.move_piece(&turn, &chess_move) {
+                    Ok(_) => {
+                        self.game.turn += 1;
+                        self.announce_last_move();
+                    },
+                    Err(_) => ()
+                }
+                self.selected = None;
+                self.set_available_moves().unwrap();
+            },
+            None => ()
+        }
+
+        // Think on the human's time about the reply the engine expects.
+        match result.ponder_move() {
+            Some(ponder_move) => {
+                let mut ponder_game = self.game.clone();
+                let turn = ponder_game.get_turn();
+                match ponder_game.move_piece(&turn, &ponder_move) {
+                    Ok(_) => {
+                        ponder_game.turn += 1;
+                        self.ponder_hash = Some(zobrist::hash(&ponder_game));
+                        self.start_engine(ponder_game, true);
+                    },
+                    Err(_) => ()
+                }
+            },
+            None => ()
+        }
+    }
+
+    fn after_human_move(&mut self) {
+        match self.ponder_hash.take() {
+            Some(hash) if hash == zobrist::hash(&self.game) => {
+                // Ponder hit, the running search carries on as the real one.
+                self.search.ponder_hit();
+                self.thinking = true;
+            },
+            Some(_) => {
+                self.search.ponder_miss();
+                self.abort_engine();
+            },
+            None => ()
+        }
+    }
+
+    // Plays a move from the board, in Kriegspiel the umpire announces it or calls the try illegal.
+    fn try_human_move(&mut self, from: &Position, to: &Position) {
+        match self.game.try_move_piece(from, to) {
+            Ok(_) => {
+                self.after_human_move();
+                self.announce_last_move();
+                self.handoff = self.game.variant.hides_pieces() && self.engine_color.is_none();
+            },
+            Err(err) => {
+                if self.game.variant.hides_pieces() {
+                    match umpire::announce_try(&self.game, from, to) {
+                        Some(announcement) => self.announcements = vec![announcement.to_string()],
+                        None => ()
+                    }
+                }
+                println!("{}", err)
+            }
+        }
+    }
+
+    fn announce_last_move(&mut self) {
+        if !self.game.variant.hides_pieces() {
+            return;
+        }
+        self.announcements = match self.game.log.get_last_move() {
+            Some(last_move) => umpire::announce_move(&self.game, &last_move).iter().map(|announcement| announcement.to_string()).collect(),
+            None => vec![]
+        };
+    }
+
+    // The side whose view is drawn when pieces are hidden, against the engine always the human.
+    fn viewer(&self) -> Color {
+        return match &self.engine_color {
+            Some(color) => Color::to_opposite(color.clone()),
+            None => self.game.get_turn()
+        }
+    }
+
+    fn umpire_text(&self) -> String {
+        if self.announcements.is_empty() {
+            return "Umpire: -".to_string();
+        }
+        return format!("Umpire: {}", self.announcements.join(", "));
+    }
+
+    fn update_analysis(&mut self) {
+        let finished = match self.analysis_thread.lock().unwrap().as_ref() {
+            Some(handle) => handle.is_finished(),
+            None => false
+        };
+        if finished {
+            let handle = self.analysis_thread.lock().unwrap().take().unwrap();
+            self.analysis = handle.join().ok();
+        }
+        if self.analysis_thread.lock().unwrap().is_some() {
+            return;
+        }
+
+        match &mut self.analysis {
+            Some(analysis) => {
+                analysis.set_position(&self.game);
+                if analysis.depth() < ANALYSIS_MAX_DEPTH && !analysis.is_complete() {
+                    let mut deeper = analysis.clone();
+                    *self.analysis_thread.lock().unwrap() = Some(thread::spawn(move || {
+                        deeper.deepen();
+                        return deeper;
+                    }));
+                }
+            },
+            None => ()
+        }
+    }
+
+    // A running worker is left to finish on its own, its tree is not picked up.
+    fn stop_analysis(&mut self) {
+        self.analysis = None;
+        *self.analysis_thread.lock().unwrap() = None;
+    }
+
+    fn analysis_text(&self) -> String {
+        return match &self.analysis {
+            Some(analysis) => {
+                let best = match analysis.best_move() {
+                    Some((chess_move, value)) => format!("{} {:+.2}", chess_move.to_uci(self.game.board.num_rows()), value as f32 / 100.0),
+                    None => "-".to_string()
+                };
+                format!("Analysis on (A), depth {}, {} nodes, best {}", analysis.depth(), analysis.node_count(), best)
+            },
+            None => "Analysis off (A)".to_string()
+        }
+    }
+
+    fn variant_text(&self) -> String {
+        return format!("Variant {} (V), new Chess960 game (N)", self.game.variant.name());
+    }
+
+    fn new_game(&mut self, game: Game) {
+        self.abort_engine();
+        self.game = game;
+        self.selected = None;
+        self.selected_drop = None;
+        self.announcements = vec![];
+        self.handoff = false;
+        self.stop_analysis();
+        self.set_available_moves().unwrap();
+    }
+
+    // The piece in hand drawn at x, y and the side it belongs to.
+    fn pocket_at(&self, x: f32, y: f32) -> Option<(Color, Piece)> {
+        if !self.game.variant.uses_pockets() || x < POCKET_LEFT {
+            return None;
+        }
+
+        let color = if y >= POCKET_TOP && y < POCKET_TOP + POCKET_SIZE {
+            Color::White
+        } else if y >= POCKET_TOP + POCKET_SIZE && y < POCKET_TOP + 2.0 * POCKET_SIZE {
+            Color::Black
+        } else {
+            return None;
+        };
+
+        let index = ((x - POCKET_LEFT) / POCKET_SPACING) as usize;
+        return POCKET_PIECES.get(index).map(|piece| (color, *piece));
+    }
+
+    fn piece_image(&self, value: u8) -> Option<&Image> {
+        return match value {
+            1 => Some(&self.white_pawn),
+            2 => Some(&self.white_bishop),
+            3 => Some(&self.white_knight),
+            4 => Some(&self.white_rook),
+            5 => Some(&self.white_queen),
+            6 => Some(&self.white_king),
+            17 => Some(&self.black_pawn),
+            18 => Some(&self.black_bishop),
+            19 => Some(&self.black_knight),
+            20 => Some(&self.black_rook),
+            21 => Some(&self.black_queen),
+            22 => Some(&self.black_king),
+            _ => None
+        }
+    }
+
+    // Fairy pieces are drawn as the two pieces they combine.
+    fn piece_images(&self, value: u8) -> Vec<&Image> {
+        if value == 0 {
+            return vec![];
+        }
+
+        let color = Color::get_piece_color(value);
+        let parts = match Piece::from_u8(value) {
+            Piece::Archbishop => vec![Piece::Bishop, Piece::Knight],
+            Piece::Chancellor => vec![Piece::Rook, Piece::Knight],
+            Piece::Amazon => vec![Piece::Queen, Piece::Knight],
+            piece => vec![piece]
+        };
+        return parts.into_iter().filter_map(|piece| self.piece_image(piece + color.clone())).collect();
+    }
+
+    fn square_size(&self) -> f32 {
+        return BOARD_PIXELS / self.game.board.num_columns().max(self.game.board.num_rows()) as f32;
+    }
+
+    // Where the text right of the board starts.
+    fn board_width(&self) -> f32 {
+        return (self.square_size() + 2.0) * self.game.board.num_columns() as f32;
+    }
+
+    fn engine_text(&self) -> String {
+        let (name, skill_level) = DIFFICULTIES[self.difficulty];
+        let skill = Skill::new(skill_level);
+
+        let engine = match &self.engine_color {
+            Some(color) => format!("Engine plays {}", color),
+            None => "Engine off".to_string()
+        };
+
+        return format!("{} (E), difficulty {} ~{} Elo (1-{})", engine, name, skill.elo(), DIFFICULTIES.len());
+    }
 }
 
 impl ggez::event::EventHandler<GameError> for State {
     fn update(&mut self, _ctx: &mut ggez::Context) -> GameResult {
+        let result = self.engine_result.lock().unwrap().take();
+        match result {
+            Some((search_id, result)) if search_id == self.search_id && self.thinking => self.play_engine_move(result),
+            _ => ()
+        }
+
+        if self.is_engine_turn() && !self.thinking && self.game.outcome().is_none() {
+            self.thinking = true;
+            self.start_engine(self.game.clone(), false);
+        }
+
+        self.update_analysis();
+
+        Ok(())
+    }
+
+    fn key_down_event(&mut self, _ctx: &mut Context, input: KeyInput, _repeated: bool) -> GameResult {
+        match input.keycode {
+            Some(KeyCode::E) => {
+                // The engine takes over the side that is not to move.
+                self.abort_engine();
+                self.engine_color = match self.engine_color {
+                    Some(_) => None,
+                    None => Some(Color::to_opposite(self.game.get_turn()))
+                };
+            },
+            Some(KeyCode::A) => {
+                match self.analysis {
+                    Some(_) => self.stop_analysis(),
+                    None => self.analysis = Some(AnalysisTree::new(self.game.clone(), ANALYSIS_NODE_BUDGET))
+                }
+            },
+            Some(KeyCode::N) => {
+                // A new game from a random Chess960 start position.
+                self.new_game(chess960::start_position((SkillRandom::new().next() % chess960::POSITIONS as u64) as usize));
+            },
+            Some(KeyCode::V) => {
+                // A new game of the next variant.
+                let variants = variant::all();
+                let current = variants.iter().position(|known| known.uci_name() == self.game.variant.uci_name()).unwrap_or(0);
+                self.new_game(Game::with_variant(variants[(current + 1) % variants.len()].clone()));
+            },
+            Some(KeyCode::Key1) => self.set_difficulty(0),
+            Some(KeyCode::Key2) => self.set_difficulty(1),
+            Some(KeyCode::Key3) => self.set_difficulty(2),
+            Some(KeyCode::Key4) => self.set_difficulty(3),
+            Some(KeyCode::Key5) => self.set_difficulty(4),
+            _ => ()
+        }
+
         Ok(())
     }
 
@@ -117,60 +531,65 @@ impl ggez::event::EventHandler<GameError> for State {
         x: f32,
         y: f32,
     ) -> GameResult {
-        let xdiv = x / 100.0;
-        let ydiv = y / 100.0;
-        let column = if xdiv < 1.0 && xdiv >= 0.0 {
-            0_usize
-        } else if xdiv < 2.0 && xdiv >= 1.0 {
-            1_usize
-        } else if xdiv < 3.0 && xdiv >= 2.0 {
-            2_usize
-        } else if xdiv < 4.0 && xdiv >= 3.0 {
-            3_usize
-        } else if xdiv < 5.0 && xdiv >= 4.0 {
-            4_usize
-        } else if xdiv < 6.0 && xdiv >= 5.0 {
-            5_usize
-        } else if xdiv < 7.0 && xdiv >= 6.0 {
-            6_usize
-        } else if xdiv < 8.0 && xdiv >= 7.0 {
-            7_usize
-        } else {
-            8_usize
-        };
+        // The click after a handoff only shows the board to the next player.
+        if self.handoff {
+            self.handoff = false;
+            return Ok(());
+        }
 
-        let row = if ydiv < 1.0 && ydiv >= 0.0 {
-            0_usize
-        } else if ydiv < 2.0 && ydiv >= 1.0 {
-            1_usize
-        } else if ydiv < 3.0 && ydiv >= 2.0 {
-            2_usize
-        } else if ydiv < 4.0 && ydiv >= 3.0 {
-            3_usize
-        } else if ydiv < 5.0 && ydiv >= 4.0 {
-            4_usize
-        } else if ydiv < 6.0 && ydiv >= 5.0 {
-            5_usize
-        } else if ydiv < 7.0 && ydiv >= 6.0 {
-            6_usize
-        } else if ydiv < 8.0 && ydiv >= 7.0 {
-            7_usize
-        } else {
-            8_usize
-        };
+        let xdiv = x / self.square_size();
+        let ydiv = y / self.square_size();
+        // Clicks beside the board give a column or row past its end.
+        let (columns, rows) = (self.game.board.num_columns(), self.game.board.num_rows());
+        let column = if xdiv >= 0.0 { (xdiv as usize).min(columns) } else { columns };
+        let row = if ydiv >= 0.0 { (ydiv as usize).min(rows) } else { rows };
+
+        // Clicks on a pocket pick the piece to drop.
+        match self.pocket_at(x, y) {
+            Some((color, piece)) => {
+                let in_hand = self.game.pockets[variant::color_index(&color)][Game::pocket_index(&piece)];
+                if color == self.game.get_turn() && in_hand > 0 && !self.is_engine_turn() {
+                    self.selected = None;
+                    self.selected_drop = Some(piece);
+                }
+                return Ok(());
+            },
+            None => ()
+        }
 
-        if column < 8 && row < 8 {
+        if column < columns && row < rows && !self.is_engine_turn() {
             let pos = Position::new(column, row);
 
-            match self.game.get_piece_from_position(&pos) {
+            match self.selected_drop.take() {
+                Some(piece) => {
+                    match self.game.try_drop_piece(&piece, &pos) {
+                        Ok(_) => {
+                            self.after_human_move();
+                            self.announce_last_move();
+                            self.handoff = self.game.variant.hides_pieces() && self.engine_color.is_none();
+                        },
+                        Err(err) => println!("{}", err)
+                    };
+                    self.set_available_moves().unwrap();
+                    return Ok(());
+                },
+                None => ()
+            }
+
+            // In Kriegspiel the click only knows what the player sees.
+            let clicked = if self.game.variant.hides_pieces() {
+                self.game.view_for(self.viewer()).get_piece_from_position(&pos)
+            } else {
+                self.game.get_piece_from_position(&pos)
+            };
+
+            match clicked {
                 None => {
                     match &self.selected {
                         None => (),
                         Some(t) => {
-                            match self.game.try_move_piece( t, &pos) {
-                                Ok(_) => (),
-                                Err(err) => println!("{}", err)
-                            };
+                            let from = *t;
+                            self.try_human_move(&from, &pos);
                             self.set_available_moves().unwrap();
                             ()
                         }
@@ -180,7 +599,23 @@ impl ggez::event::EventHandler<GameError> for State {
                     let turn = self.game.get_turn();
 
                     if color == turn {
-                        self.selected = Some(pos)
+                        // A king moving onto its own rook castles, as in Chess960.
+                        let castles = match &self.selected {
+                            Some(t) => self.current_available_moves.iter().any(|chess_move| chess_move.from == *t && match chess_move.move_type {
+                                MoveType::Castle(rook_from, _) => rook_from == pos,
+                                _ => false
+                            }),
+                            None => false
+                        };
+
+                        if castles {
+                            let from = self.selected.unwrap();
+                            self.try_human_move(&from, &pos);
+                            self.selected = None;
+                            self.set_available_moves().unwrap();
+                        } else {
+                            self.selected = Some(pos)
+                        }
                     }
                     else {
                         match &self.selected {
@@ -188,10 +623,8 @@ impl ggez::event::EventHandler<GameError> for State {
                                 self.selected = Some(pos);
                             },
                             Some(t) => {
-                                match self.game.try_move_piece( t, &pos) {
-                                    Ok(_) => (),
-                                    Err(err) => println!("{}", err)
-                                };
+                                let from = *t;
+                                self.try_human_move(&from, &pos);
                                 self.set_available_moves().unwrap();
                                 ()
                             }
@@ -207,9 +640,11 @@ impl ggez::event::EventHandler<GameError> for State {
     fn draw(&mut self, ctx: &mut ggez::Context) -> GameResult {
         let mut canvas = graphics::Canvas::from_frame(ctx, graphics::Color::BLACK);
 
-        let mut filler = true;
+        // In Kriegspiel only one side's view is drawn, and nothing of it during a handoff.
+        let view = if self.game.variant.hides_pieces() { Some(self.game.view_for(self.viewer())) } else { None };
 
-        let strings =  Game::available_moves_to_string(&self.current_available_moves);
+        // The moves would give hidden pieces away.
+        let strings = if self.game.variant.hides_pieces() { vec![] } else { Game::available_moves_to_string(&self.current_available_moves) };
         for (i, v) in strings.iter().enumerate() {
             let text1 = graphics::Text::new(v.get(0).unwrap());
 
@@ -217,7 +652,7 @@ impl ggez::event::EventHandler<GameError> for State {
                         graphics::DrawParam::new()
                         .color((1.0, 1.0, 1.0, 1.0))
                         .scale([1.0, 1.0])
-                        .dest([102.0 * 8.0 + 20.0, 15.0 + 25.0 * (i as f32)])
+                        .dest([self.board_width() + 20.0, 15.0 + 25.0 * (i as f32)])
             );
 
             let text2 = graphics::Text::new(v.get(1).unwrap());
@@ -226,7 +661,7 @@ impl ggez::event::EventHandler<GameError> for State {
                         graphics::DrawParam::new()
                             .color((1.0, 1.0, 1.0, 1.0))
                             .scale([1.0, 1.0])
-                            .dest([102.0 * 8.0 + 200.0, 15.0 + 25.0 * (i as f32)])
+                            .dest([self.board_width() + 200.0, 15.0 + 25.0 * (i as f32)])
             );
 
             let text3 = graphics::Text::new(v.get(2).unwrap());
@@ -235,7 +670,7 @@ impl ggez::event::EventHandler<GameError> for State {
                         graphics::DrawParam::new()
                             .color((1.0, 1.0, 1.0, 1.0))
                             .scale([1.0, 1.0])
-                            .dest([102.0 * 8.0 + 350.0, 15.0 + 25.0 * (i as f32)])
+                            .dest([self.board_width() + 350.0, 15.0 + 25.0 * (i as f32)])
             );
 
             let text4 = graphics::Text::new(v.get(3).unwrap());
@@ -244,15 +679,16 @@ impl ggez::event::EventHandler<GameError> for State {
                         graphics::DrawParam::new()
                             .color((1.0, 1.0, 1.0, 1.0))
                             .scale([1.0, 1.0])
-                            .dest([102.0 * 8.0 + 400.0, 15.0 + 25.0 * (i as f32)])
+                            .dest([self.board_width() + 400.0, 15.0 + 25.0 * (i as f32)])
             );
         }
 
         let mut string = String::new();
         string.push_str("Selected: ");
-        match &self.selected {
-            None => string.push_str("None"),
-            Some(pos) => string.push_str(&pos.to_string())
+        match (&self.selected, &self.selected_drop) {
+            (_, Some(piece)) => string.push_str(&format!("{} in hand", piece)),
+            (None, None) => string.push_str("None"),
+            (Some(pos), None) => string.push_str(&pos.to_string())
         }
 
         let text_selected = graphics::Text::new(string);
@@ -264,50 +700,134 @@ impl ggez::event::EventHandler<GameError> for State {
                         .dest([15.0, 815.0])
         );
 
-        for x in 0..8 {
-            for y in 0..8 {
-                let image = match self.game.board.get(y, x) {
-                    None => panic!("shfsdkgjsdgf"),
-                    Some(t) => match t {
-                        1 => Some(&self.white_pawn),
-                        2 => Some(&self.white_bishop),
-                        3 => Some(&self.white_knight),
-                        4 => Some(&self.white_rook),
-                        5 => Some(&self.white_queen),
-                        6 => Some(&self.white_king),
-                        9 => Some(&self.black_pawn),
-                        10 => Some(&self.black_bishop),
-                        11 => Some(&self.black_knight),
-                        12 => Some(&self.black_rook),
-                        13 => Some(&self.black_queen),
-                        14 => Some(&self.black_king),
-                        _ => None
+        let text_engine = graphics::Text::new(self.engine_text());
+
+        canvas.draw(&text_engine,
+                    graphics::DrawParam::new()
+                        .color((1.0, 1.0, 1.0, 1.0))
+                        .scale([1.0, 1.0])
+                        .dest([15.0, 840.0])
+        );
+
+        let text_analysis = graphics::Text::new(self.analysis_text());
+
+        canvas.draw(&text_analysis,
+                    graphics::DrawParam::new()
+                        .color((1.0, 1.0, 1.0, 1.0))
+                        .scale([1.0, 1.0])
+                        .dest([15.0, 865.0])
+        );
+
+        let text_variant = graphics::Text::new(self.variant_text());
+
+        canvas.draw(&text_variant,
+                    graphics::DrawParam::new()
+                        .color((1.0, 1.0, 1.0, 1.0))
+                        .scale([1.0, 1.0])
+                        .dest([15.0, 890.0])
+        );
+
+        match &view {
+            Some(view) => {
+                let text_umpire = graphics::Text::new(format!("{}, {} sees the board", self.umpire_text(), view.color));
+
+                canvas.draw(&text_umpire,
+                            graphics::DrawParam::new()
+                                .color((1.0, 1.0, 1.0, 1.0))
+                                .scale([1.0, 1.0])
+                                .dest([15.0, 915.0])
+                );
+            },
+            None => ()
+        }
+
+        if self.handoff {
+            let text_handoff = graphics::Text::new(format!("{} to move, hand over and click to continue", self.game.get_turn()));
+
+            canvas.draw(&text_handoff,
+                        graphics::DrawParam::new()
+                            .color((1.0, 1.0, 1.0, 1.0))
+                            .scale([2.0, 2.0])
+                            .dest([40.0, BOARD_PIXELS / 2.0])
+                            .z(64)
+            );
+        }
+
+        if self.game.variant.uses_pockets() {
+            for (side, color) in [Color::White, Color::Black].iter().enumerate() {
+                for (i, piece) in POCKET_PIECES.iter().enumerate() {
+                    let x = POCKET_LEFT + POCKET_SPACING * i as f32;
+                    let y = POCKET_TOP + POCKET_SIZE * side as f32;
+
+                    match self.piece_image(*piece + color.clone()) {
+                        Some(image) => image.draw(&mut canvas, graphics::DrawParam::new().dest_rect(graphics::Rect::new(x, y, 1.0, 1.0))),
+                        None => ()
                     }
+
+                    let count = graphics::Text::new(format!("x{}", self.game.pockets[side][Game::pocket_index(piece)]));
+                    canvas.draw(&count,
+                                graphics::DrawParam::new()
+                                    .color((1.0, 1.0, 1.0, 1.0))
+                                    .scale([1.0, 1.0])
+                                    .dest([x + POCKET_SIZE, y + POCKET_SIZE / 2.0])
+                    );
+                }
+            }
+        }
+
+        // Squares blown up by the last move in Atomic are drawn red.
+        let exploded: Vec<Position> = match self.game.log.get_last_move() {
+            Some(last_move) => last_move.exploded.iter().map(|(position, _, _)| *position).collect(),
+            None => vec![]
+        };
+
+        let board = match &view {
+            Some(view) => &view.board,
+            None => &self.game.board
+        };
+
+        let square = self.square_size();
+        for x in 0..self.game.board.num_columns() {
+            for y in 0..self.game.board.num_rows() {
+                let images = match board.get(y, x) {
+                    None => panic!("shfsdkgjsdgf"),
+                    Some(_) if self.handoff => vec![],
+                    Some(t) => self.piece_images(*t)
+                };
+                let hidden = self.handoff || match &view {
+                    Some(view) => !view.is_visible(&Position::new(x, y)),
+                    None => false
                 };
 
-                match image {
-                    None => (),
-                    Some(t) => {
-                        let draw_params = ggez::graphics::DrawParam::new();
-                        let recto = ggez::graphics::Rect::new((x * 100_usize) as f32, (y * 100_usize) as f32, 1.6, 1.6);
-                        let draw_params = draw_params.dest_rect(recto);
-                        let draw_params = draw_params.z(32);
-                        t.draw(&mut canvas, draw_params)
-                    }
+                // A single piece fills the square, the two parts of a fairy piece share it.
+                let (scale, shift) = if images.len() > 1 { (1.0, 38.0) } else { (1.6, 0.0) };
+                let (scale, shift) = (scale * square / 100.0, shift * square / 100.0);
+                for (i, t) in images.iter().enumerate() {
+                    let draw_params = ggez::graphics::DrawParam::new();
+                    let offset = shift * i as f32;
+                    let recto = ggez::graphics::Rect::new(x as f32 * square + offset, y as f32 * square + offset, scale, scale);
+                    let draw_params = draw_params.dest_rect(recto);
+                    let draw_params = draw_params.z(32);
+                    t.draw(&mut canvas, draw_params)
                 }
 
 
                 let rect = graphics::Mesh::new_rectangle(
                     ctx,
                     graphics::DrawMode::fill(),
-                    graphics::Rect::new((x * 100) as f32, (y * 100) as f32, 100.0 as f32, 100.0 as f32),
-                    if filler { graphics::Color::WHITE } else { graphics::Color::BLACK },
+                    graphics::Rect::new(x as f32 * square, y as f32 * square, square, square),
+                    if exploded.contains(&Position::new(x, y)) {
+                        graphics::Color::new(0.8, 0.2, 0.1, 1.0)
+                    } else if hidden {
+                        if (x + y) % 2 == 0 { graphics::Color::new(0.45, 0.45, 0.45, 1.0) } else { graphics::Color::new(0.25, 0.25, 0.25, 1.0) }
+                    } else if (x + y) % 2 == 0 {
+                        graphics::Color::WHITE
+                    } else {
+                        graphics::Color::BLACK
+                    },
                 )?;
                 canvas.draw(&rect, graphics::DrawParam::default());
-
-                filler = !filler;
             }
-            filler = !filler;
         }
 
         canvas.finish(ctx)?;